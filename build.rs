@@ -0,0 +1,20 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use prost::Message;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+
+    // Protos are compiled by protox, so that building does not need protoc
+    let descriptor_path = PathBuf::from(env::var("OUT_DIR")?).join("clob_descriptor.bin");
+    let file_descriptors = protox::compile(["clob.proto"], ["proto"])?;
+    fs::write(&descriptor_path, file_descriptors.encode_to_vec())?;
+
+    tonic_build::configure()
+        .file_descriptor_set_path(&descriptor_path)
+        .skip_protoc_run()
+        .compile_with_config(prost_build::Config::new(), &["proto/clob.proto"], &["proto"])?;
+    Ok(())
+}
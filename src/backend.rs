@@ -0,0 +1,889 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use futures::future::try_join_all;
+use miden_client::client::accounts::{AccountStorageMode, AccountTemplate};
+use miden_client::client::rpc::{NodeRpcClient, TonicRpcClient};
+use miden_client::client::transactions::transaction_request::TransactionRequest;
+use miden_client::client::transactions::{TransactionResult, TransactionStatus};
+use miden_client::errors::{ClientError, StoreError};
+use miden_client::store::sqlite_store::SqliteStore;
+use miden_client::store::{InputNoteRecord, NoteFilter, Store, TransactionFilter};
+use miden_lib::accounts::faucets::create_basic_fungible_faucet;
+use miden_lib::accounts::wallets::create_basic_wallet;
+use miden_lib::AuthScheme;
+use miden_mock::mock::account::MockAccountType;
+use miden_mock::mock::block::mock_block_header;
+use miden_mock::mock::notes::AssetPreservationStatus;
+use miden_mock::mock::transaction::mock_inputs_with_existing;
+use miden_objects::accounts::{Account, AccountId, AccountType};
+use miden_objects::assembly::{ModuleAst, ProgramAst};
+use miden_objects::assets::TokenSymbol;
+use miden_objects::crypto::dsa::rpo_falcon512::KeyPair;
+use miden_objects::notes::{Note, NoteId};
+use miden_objects::transaction::{
+    ChainMmr, ExecutedTransaction, InputNote, InputNotes, ProvenTransaction, TransactionArgs,
+    TransactionId, TransactionScript,
+};
+use miden_objects::utils::serde::Serializable;
+use miden_objects::{AccountError, BlockHeader};
+use miden_tx::{
+    DataStore, DataStoreError, ProvingOptions, TransactionExecutor, TransactionInputs,
+    TransactionProver,
+};
+use tracing::{debug, info, instrument, warn};
+use vm_processor::{Felt, Word, ONE};
+
+use crate::errors::ClobError;
+use crate::receipt::TransactionReceipt;
+use crate::{client_config, create_client, MidenClient, COMMIT_POLL_INTERVAL, COMMIT_TIMEOUT};
+
+/// Chain access needed to run CLOB transactions
+///
+/// [execute_on] drives a transaction through these steps. [NodeBackend] talks
+/// to a Miden node, [SimulatedChain] keeps the whole chain in process.
+#[tonic::async_trait]
+pub trait ClobBackend: Send {
+    /// Catches up with the chain and returns the synced block height
+    async fn sync(&mut self) -> Result<u32, ClobError>;
+
+    /// Returns the input notes known to the backend that match `filter`
+    fn get_notes(&self, filter: NoteFilter) -> Result<Vec<InputNoteRecord>, ClobError>;
+
+    /// Returns the latest known state of an account
+    ///
+    /// Fails with [ClobError::NotFound] if the backend does not know it.
+    fn get_account(&self, account_id: AccountId) -> Result<Account, ClobError>;
+
+    /// Creates a basic wallet signing with a new key
+    fn new_wallet(&mut self, storage_mode: AccountStorageMode) -> Result<Account, ClobError>;
+
+    /// Creates a basic fungible faucet of token `symbol`, signing with a new key
+    fn new_faucet(
+        &mut self,
+        symbol: TokenSymbol,
+        decimals: u8,
+        max_supply: u64,
+        storage_mode: AccountStorageMode,
+    ) -> Result<Account, ClobError>;
+
+    /// Compiles a transaction script run by `account_id`, with the key of the
+    /// account in its advice inputs
+    fn compile_tx_script(
+        &self,
+        account_id: AccountId,
+        program: ProgramAst,
+    ) -> Result<TransactionScript, ClobError>;
+
+    /// Executes the request against the synced state, without proving it
+    fn execute(&mut self, tx_request: TransactionRequest) -> Result<TransactionResult, ClobError>;
+
+    /// Submits a proven transaction and records it as pending
+    async fn submit(
+        &mut self,
+        result: TransactionResult,
+        proven: ProvenTransaction,
+    ) -> Result<(), ClobError>;
+
+    /// Checks once whether a submitted transaction was committed, returning
+    /// its block if so
+    ///
+    /// Fails with [ClobError::NoteAlreadyConsumed] when a competing transaction
+    /// consumed one of `input_note_ids` first.
+    async fn poll_commit(
+        &mut self,
+        transaction_id: TransactionId,
+        input_note_ids: &[NoteId],
+    ) -> Result<Option<u32>, ClobError>;
+}
+
+/// Execute, prove and submit a transaction on `backend`, then wait for it to be
+/// committed
+#[instrument(skip_all)]
+pub async fn execute_on<B: ClobBackend>(
+    backend: &mut B,
+    tx_request: TransactionRequest,
+) -> Result<TransactionReceipt, ClobError> {
+    let mut receipts = execute_batch_on(backend, vec![tx_request]).await.into_result()?;
+    Ok(receipts.remove(0))
+}
+
+/// Outcome of [execute_batch_on]
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// Receipts of the committed transactions, in the order of the requests.
+    /// When the batch stopped at [BatchReport::error] they are those of the
+    /// requests before the failing one.
+    pub receipts: Vec<TransactionReceipt>,
+    /// Error that stopped the batch, after the transactions reported above
+    pub error: Option<ClobError>,
+}
+
+impl BatchReport {
+    /// Returns the receipts, or the error if the batch did not go through as
+    /// a whole
+    pub fn into_result(self) -> Result<Vec<TransactionReceipt>, ClobError> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.receipts),
+        }
+    }
+}
+
+/// Executes, proves and submits transactions of distinct accounts together,
+/// then waits for all of them to be committed.
+///
+/// The proofs are computed concurrently on the blocking thread pool. If a
+/// submission is rejected, the transactions submitted before it are still
+/// waited for, and the report holds their receipts along with the error.
+#[instrument(skip_all, fields(transactions = tx_requests.len()))]
+pub async fn execute_batch_on<B: ClobBackend>(
+    backend: &mut B,
+    tx_requests: Vec<TransactionRequest>,
+) -> BatchReport {
+    let proven = match execute_and_prove(backend, tx_requests).await {
+        Ok(proven) => proven,
+        Err(err) => {
+            return BatchReport {
+                receipts: Vec::new(),
+                error: Some(err),
+            }
+        }
+    };
+
+    let mut report = BatchReport::default();
+    let mut submitted = Vec::with_capacity(proven.len());
+    for (input_note_ids, result, (proven_transaction, proving_time)) in proven {
+        let transaction_id = result.executed_transaction().id();
+        let created_notes = result.created_notes().to_vec();
+        let account_delta = result.account_delta().clone();
+
+        info!(transaction_id = transaction_id.to_hex(), "Submitting transaction");
+        if let Err(err) = backend.submit(result, proven_transaction).await {
+            report.error = Some(classify_rejection(backend, &input_note_ids, err).await);
+            break;
+        }
+
+        let (payback_notes, clone_notes) = TransactionReceipt::split_created_notes(created_notes);
+        let receipt = TransactionReceipt {
+            transaction_id,
+            block_num: 0,
+            consumed_notes: input_note_ids,
+            payback_notes,
+            clone_notes,
+            account_delta,
+            commit_latency: Duration::ZERO,
+            proving_time,
+        };
+        submitted.push((receipt, Instant::now()));
+    }
+
+    for (mut receipt, submitted_at) in submitted {
+        let transaction_id = receipt.transaction_id;
+        match wait_for_commit(backend, transaction_id, &receipt.consumed_notes).await {
+            Ok(block_num) => receipt.block_num = block_num,
+            Err(err) => {
+                report.error.get_or_insert(err);
+                break;
+            }
+        }
+        receipt.commit_latency = submitted_at.elapsed();
+        info!(
+            transaction_id = transaction_id.to_hex(),
+            block_num = receipt.block_num,
+            commit_latency = ?receipt.commit_latency,
+            "Committed"
+        );
+        report.receipts.push(receipt);
+    }
+
+    report
+}
+
+/// Executed transaction with its input notes, its proof and the proving time
+type ProvenRequest = (Vec<NoteId>, TransactionResult, (ProvenTransaction, Duration));
+
+/// Syncs `backend`, then executes the requests in order and proves them
+/// concurrently
+async fn execute_and_prove<B: ClobBackend>(
+    backend: &mut B,
+    tx_requests: Vec<TransactionRequest>,
+) -> Result<Vec<ProvenRequest>, ClobError> {
+    backend.sync().await?;
+    let mut executed = Vec::with_capacity(tx_requests.len());
+    for tx_request in tx_requests {
+        let input_note_ids = tx_request.get_input_note_ids();
+        executed.push((input_note_ids, backend.execute(tx_request)?));
+    }
+
+    let proofs = try_join_all(
+        executed.iter().map(|(_, result)| prove_transaction(result.executed_transaction().clone())),
+    )
+    .await?;
+
+    Ok(executed
+        .into_iter()
+        .zip(proofs)
+        .map(|((input_note_ids, result), proof)| (input_note_ids, result, proof))
+        .collect())
+}
+
+/// Proves an executed transaction on the blocking thread pool, and returns the
+/// proof along with the time spent in the prover
+pub async fn prove_transaction(
+    executed: ExecutedTransaction,
+) -> Result<(ProvenTransaction, Duration), ClobError> {
+    tokio::task::spawn_blocking(move || {
+        let prover = TransactionProver::new(ProvingOptions::default());
+        let proving_started_at = Instant::now();
+        let proven = prover.prove_transaction(executed).map_err(ClientError::from)?;
+        Ok((proven, proving_started_at.elapsed()))
+    })
+    .await
+    .map_err(|err| ClobError::Backend(format!("proving task failed: {err}")))?
+}
+
+/// Polls `backend` every [COMMIT_POLL_INTERVAL] until a submitted transaction
+/// is committed, and returns its block
+///
+/// Fails with [ClobError::CommitTimeout] after [COMMIT_TIMEOUT].
+pub async fn wait_for_commit<B: ClobBackend>(
+    backend: &mut B,
+    transaction_id: TransactionId,
+    input_note_ids: &[NoteId],
+) -> Result<u32, ClobError> {
+    let submitted_at = Instant::now();
+    loop {
+        if let Some(block_num) = backend.poll_commit(transaction_id, input_note_ids).await? {
+            return Ok(block_num);
+        }
+        if submitted_at.elapsed() > COMMIT_TIMEOUT {
+            return Err(ClobError::CommitTimeout(transaction_id, submitted_at.elapsed()));
+        }
+        tokio::time::sleep(COMMIT_POLL_INTERVAL).await;
+    }
+}
+
+/// Returns the notes of `note_ids` that the backend has seen being consumed
+pub fn consumed_notes<B: ClobBackend + ?Sized>(
+    backend: &B,
+    note_ids: &[NoteId],
+) -> Result<Vec<NoteId>, ClobError> {
+    let consumed = backend.get_notes(NoteFilter::Consumed)?;
+    Ok(consumed.iter().map(|note| note.id()).filter(|id| note_ids.contains(id)).collect())
+}
+
+/// Number of syncs after a rejected submission to look for a competing
+/// transaction that consumed one of our input notes
+const CONFLICT_CHECKS: u32 = 3;
+
+/// Turns a rejected submission into [ClobError::NoteAlreadyConsumed] when one
+/// of its input notes turns out to be nullified
+///
+/// The competing transaction may still be in the node's mempool when ours is
+/// rejected, so the backend is synced up to [CONFLICT_CHECKS] times,
+/// [COMMIT_POLL_INTERVAL] apart, before the rejection is returned as is.
+async fn classify_rejection<B: ClobBackend>(
+    backend: &mut B,
+    input_note_ids: &[NoteId],
+    err: ClobError,
+) -> ClobError {
+    if input_note_ids.is_empty() || err.is_note_conflict() {
+        return err;
+    }
+
+    for check in 1..=CONFLICT_CHECKS {
+        if backend.sync().await.is_ok() {
+            if let Ok(consumed) = consumed_notes(backend, input_note_ids) {
+                if !consumed.is_empty() {
+                    return ClobError::NoteAlreadyConsumed(consumed);
+                }
+            }
+        }
+        if check < CONFLICT_CHECKS {
+            tokio::time::sleep(COMMIT_POLL_INTERVAL).await;
+        }
+    }
+
+    err
+}
+
+// NODE BACKEND
+// ================================================================================================
+
+/// Backend talking to the Miden node configured for the local client
+pub struct NodeBackend {
+    client: MidenClient,
+    rpc: TonicRpcClient,
+    store: SqliteStore,
+}
+
+impl NodeBackend {
+    /// Opens the local client store and connects to the configured node
+    pub fn new() -> Result<Self, ClobError> {
+        let client_config = client_config();
+        let rpc = TonicRpcClient::new(&client_config.rpc.endpoint.to_string());
+        let store = SqliteStore::new((&client_config).into()).map_err(ClientError::from)?;
+        Ok(Self {
+            client: create_client()?,
+            rpc,
+            store,
+        })
+    }
+
+    pub fn client(&self) -> &MidenClient {
+        &self.client
+    }
+
+    pub fn client_mut(&mut self) -> &mut MidenClient {
+        &mut self.client
+    }
+}
+
+#[tonic::async_trait]
+impl ClobBackend for NodeBackend {
+    async fn sync(&mut self) -> Result<u32, ClobError> {
+        Ok(self.client.sync_state().await?)
+    }
+
+    fn get_notes(&self, filter: NoteFilter) -> Result<Vec<InputNoteRecord>, ClobError> {
+        Ok(self.client.get_input_notes(filter)?)
+    }
+
+    fn get_account(&self, account_id: AccountId) -> Result<Account, ClobError> {
+        match self.client.get_account(account_id) {
+            Ok((account, _)) => Ok(account),
+            Err(ClientError::StoreError(StoreError::AccountDataNotFound(_))) => {
+                Err(ClobError::NotFound(format!("account {account_id}")))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn new_wallet(&mut self, storage_mode: AccountStorageMode) -> Result<Account, ClobError> {
+        let (account, _) = self.client.new_account(AccountTemplate::BasicWallet {
+            mutable_code: false,
+            storage_mode,
+        })?;
+        Ok(account)
+    }
+
+    fn new_faucet(
+        &mut self,
+        symbol: TokenSymbol,
+        decimals: u8,
+        max_supply: u64,
+        storage_mode: AccountStorageMode,
+    ) -> Result<Account, ClobError> {
+        let (faucet, _) = self.client.new_account(AccountTemplate::FungibleFaucet {
+            token_symbol: symbol,
+            decimals,
+            max_supply,
+            storage_mode,
+        })?;
+        Ok(faucet)
+    }
+
+    fn compile_tx_script(
+        &self,
+        account_id: AccountId,
+        program: ProgramAst,
+    ) -> Result<TransactionScript, ClobError> {
+        let auth = self.client.get_account_auth(account_id)?;
+        let script_inputs = vec![auth.into_advice_inputs()];
+        Ok(self.client.compile_tx_script(program, script_inputs, vec![])?)
+    }
+
+    fn execute(&mut self, tx_request: TransactionRequest) -> Result<TransactionResult, ClobError> {
+        Ok(self.client.new_transaction(tx_request)?)
+    }
+
+    async fn submit(
+        &mut self,
+        result: TransactionResult,
+        proven: ProvenTransaction,
+    ) -> Result<(), ClobError> {
+        self.rpc.submit_proven_transaction(proven).await.map_err(ClientError::from)?;
+        self.store.apply_transaction(result).map_err(ClientError::from)?;
+        Ok(())
+    }
+
+    async fn poll_commit(
+        &mut self,
+        transaction_id: TransactionId,
+        input_note_ids: &[NoteId],
+    ) -> Result<Option<u32>, ClobError> {
+        debug!(cur_block = self.client.get_sync_height()?, "Syncing state...");
+        self.client.sync_state().await?;
+
+        // Check if executed transaction got committed by the node
+        let uncommited_transactions =
+            self.client.get_transactions(TransactionFilter::Uncomitted)?;
+        let is_tx_committed =
+            !uncommited_transactions.iter().any(|uncommited_tx| uncommited_tx.id == transaction_id);
+
+        if !is_tx_committed {
+            // The nullifiers of our own transaction are only seen together with its
+            // commit, so an input note consumed while we are still pending was taken
+            // by a competing transaction and ours will never make it into a block
+            let consumed = consumed_notes(self, input_note_ids)?;
+            if !consumed.is_empty() {
+                warn!(
+                    transaction_id = transaction_id.to_hex(),
+                    "Input notes consumed by another tx"
+                );
+                return Err(ClobError::NoteAlreadyConsumed(consumed));
+            }
+            return Ok(None);
+        }
+
+        let block_num = self
+            .client
+            .get_transactions(TransactionFilter::All)?
+            .into_iter()
+            .find(|tx| tx.id == transaction_id)
+            .and_then(|tx| match tx.transaction_status {
+                TransactionStatus::Committed(block_num) => Some(block_num),
+                TransactionStatus::Pending => None,
+            })
+            .ok_or_else(|| {
+                ClobError::Backend(format!(
+                    "no commit block recorded for transaction {}",
+                    transaction_id.to_hex()
+                ))
+            })?;
+
+        Ok(Some(block_num))
+    }
+}
+
+// SIMULATED CHAIN
+// ================================================================================================
+
+/// In-process chain for running CLOB transactions without a node
+///
+/// Transactions are executed and proven with the real transaction kernel.
+/// Submitted transactions stay pending until the next [SimulatedChain::produce_block],
+/// which applies their account deltas, nullifies their input notes and
+/// includes the notes of [TransactionResult::created_notes]. Those are the
+/// expected output notes of the request, the only created notes whose details
+/// are known, just as with a real client: notes created by note scripts, e.g.
+/// the paybacks of the limit-swap script, are not included.
+///
+/// Input notes are authenticated by the block header and chain MMR of
+/// miden-mock. Once [SimulatedChain::block_num] is past the reference block of
+/// miden-mock, e.g. after [SimulatedChain::advance_blocks], the mock chain is
+/// extended with empty blocks up to it, so that scripts reading the block
+/// number, e.g. to let notes expire, see the height of the simulated chain.
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedChain {
+    block_num: u32,
+    accounts: BTreeMap<AccountId, Account>,
+    /// Public key and key pair bytes of the accounts created by the chain
+    keys: BTreeMap<AccountId, (Word, Vec<Felt>)>,
+    /// Unconsumed notes and the block that included them
+    notes: BTreeMap<NoteId, (Note, u32)>,
+    consumed: BTreeMap<NoteId, Note>,
+    /// Notes added since the last block
+    pending_notes: Vec<Note>,
+    pending_transactions: Vec<TransactionResult>,
+    committed: BTreeMap<TransactionId, u32>,
+    /// Seeds handed out to new accounts
+    accounts_created: u64,
+}
+
+impl SimulatedChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Height of the latest produced block
+    pub fn block_num(&self) -> u32 {
+        self.block_num
+    }
+
+    /// Adds or replaces an account, effective immediately
+    pub fn add_account(&mut self, account: Account) {
+        self.accounts.insert(account.id(), account);
+    }
+
+    /// Queues a note for inclusion in the next block
+    pub fn add_note(&mut self, note: Note) {
+        self.pending_notes.push(note);
+    }
+
+    /// Returns the block that included the note, if it is unconsumed
+    pub fn note_block(&self, note_id: &NoteId) -> Option<u32> {
+        self.notes.get(note_id).map(|(_, block_num)| *block_num)
+    }
+
+    /// Returns true if a committed transaction consumed the note
+    pub fn is_consumed(&self, note_id: &NoteId) -> bool {
+        self.consumed.contains_key(note_id)
+    }
+
+    /// Number of submitted transactions waiting for the next block
+    pub fn pending_transactions(&self) -> usize {
+        self.pending_transactions.len()
+    }
+
+    /// Produces `blocks` blocks, committing what is pending in the first one,
+    /// and returns the number of the last one
+    pub fn advance_blocks(&mut self, blocks: u32) -> Result<u32, ClobError> {
+        for _ in 0..blocks {
+            self.produce_block()?;
+        }
+        Ok(self.block_num)
+    }
+
+    /// Commits the pending transactions and notes in a new block and returns
+    /// its number
+    ///
+    /// The account deltas are applied to copies of the accounts first: if one
+    /// of them fails, the chain is left as it was, pending transactions
+    /// included.
+    pub fn produce_block(&mut self) -> Result<u32, ClobError> {
+        let block_num = self.block_num + 1;
+
+        let mut updated = BTreeMap::new();
+        for result in &self.pending_transactions {
+            let account_id = result.executed_transaction().account_id();
+            let account = match updated.entry(account_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.get_account(account_id)?),
+            };
+            account
+                .apply_delta(result.account_delta())
+                .map_err(|err| ClobError::Backend(err.to_string()))?;
+        }
+        self.accounts.extend(updated);
+
+        for result in std::mem::take(&mut self.pending_transactions) {
+            let executed = result.executed_transaction();
+            for note in executed.input_notes().iter() {
+                if let Some((note, _)) = self.notes.remove(&note.id()) {
+                    self.consumed.insert(note.id(), note);
+                }
+            }
+            for note in result.created_notes() {
+                self.notes.insert(note.id(), (note.clone(), block_num));
+            }
+            self.committed.insert(executed.id(), block_num);
+        }
+
+        for note in std::mem::take(&mut self.pending_notes) {
+            self.notes.insert(note.id(), (note, block_num));
+        }
+
+        self.block_num = block_num;
+        debug!(block_num, notes = self.notes.len(), "Produced block");
+
+        Ok(block_num)
+    }
+
+    /// Returns the note ids that are unconsumed on chain and not spent by a
+    /// pending transaction
+    fn available_notes(&self, note_ids: &[NoteId]) -> Vec<NoteId> {
+        note_ids
+            .iter()
+            .filter(|id| self.notes.contains_key(id))
+            .filter(|id| {
+                !self.pending_transactions.iter().any(|pending| {
+                    pending
+                        .executed_transaction()
+                        .input_notes()
+                        .iter()
+                        .any(|note| note.id() == **id)
+                })
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Draws a new key pair and a seed for the next account
+    fn next_account_keys(&mut self) -> Result<(KeyPair, [u8; 32]), ClobError> {
+        let key_pair = KeyPair::new().map_err(|err| ClobError::Backend(err.to_string()))?;
+        self.accounts_created += 1;
+        let mut init_seed = [0; 32];
+        init_seed[..8].copy_from_slice(&self.accounts_created.to_le_bytes());
+        Ok((key_pair, init_seed))
+    }
+
+    /// Registers an account created from a seed, along with its key
+    ///
+    /// The account is recorded as already deployed, i.e. with nonce 1, so
+    /// that its first transaction does not need the seed.
+    fn register_new_account(
+        &mut self,
+        created: Result<(Account, Word), AccountError>,
+        key_pair: KeyPair,
+    ) -> Result<Account, ClobError> {
+        let (account, _) = created.map_err(|err| ClobError::Backend(err.to_string()))?;
+        let account = Account::new(
+            account.id(),
+            account.vault().clone(),
+            account.storage().clone(),
+            account.code().clone(),
+            ONE,
+        );
+
+        let pub_key: Word = key_pair.public_key().into();
+        let key_pair_felts = key_pair.to_bytes().iter().map(|byte| Felt::new(*byte as u64));
+        self.keys.insert(account.id(), (pub_key, key_pair_felts.collect()));
+        self.add_account(account.clone());
+        Ok(account)
+    }
+}
+
+#[tonic::async_trait]
+impl ClobBackend for SimulatedChain {
+    async fn sync(&mut self) -> Result<u32, ClobError> {
+        Ok(self.block_num)
+    }
+
+    fn get_notes(&self, filter: NoteFilter) -> Result<Vec<InputNoteRecord>, ClobError> {
+        let committed = self.notes.values().map(|(note, _)| InputNoteRecord::from(note.clone()));
+        let consumed = self.consumed.values().map(|note| InputNoteRecord::from(note.clone()));
+        Ok(match filter {
+            NoteFilter::All => committed.chain(consumed).collect(),
+            NoteFilter::Committed => committed.collect(),
+            NoteFilter::Consumed => consumed.collect(),
+            NoteFilter::Pending => Vec::new(),
+        })
+    }
+
+    fn get_account(&self, account_id: AccountId) -> Result<Account, ClobError> {
+        self.accounts
+            .get(&account_id)
+            .cloned()
+            .ok_or_else(|| ClobError::NotFound(format!("simulated account {account_id}")))
+    }
+
+    fn new_wallet(&mut self, storage_mode: AccountStorageMode) -> Result<Account, ClobError> {
+        let (key_pair, init_seed) = self.next_account_keys()?;
+        let auth_scheme = AuthScheme::RpoFalcon512 {
+            pub_key: key_pair.public_key(),
+        };
+        let wallet = create_basic_wallet(
+            init_seed,
+            auth_scheme,
+            AccountType::RegularAccountImmutableCode,
+            storage_mode.into(),
+        );
+        self.register_new_account(wallet, key_pair)
+    }
+
+    fn new_faucet(
+        &mut self,
+        symbol: TokenSymbol,
+        decimals: u8,
+        max_supply: u64,
+        storage_mode: AccountStorageMode,
+    ) -> Result<Account, ClobError> {
+        let (key_pair, init_seed) = self.next_account_keys()?;
+        let auth_scheme = AuthScheme::RpoFalcon512 {
+            pub_key: key_pair.public_key(),
+        };
+        let faucet = create_basic_fungible_faucet(
+            init_seed,
+            symbol,
+            decimals,
+            Felt::new(max_supply),
+            storage_mode.into(),
+            auth_scheme,
+        );
+        self.register_new_account(faucet, key_pair)
+    }
+
+    fn compile_tx_script(
+        &self,
+        account_id: AccountId,
+        program: ProgramAst,
+    ) -> Result<TransactionScript, ClobError> {
+        let account = self.get_account(account_id)?;
+        let script_inputs = self.keys.get(&account_id).cloned().into_iter().collect::<Vec<_>>();
+
+        let snapshot = ChainSnapshot::new(account, Vec::new(), self.block_num);
+        let executor = TransactionExecutor::new(snapshot);
+        executor
+            .compile_tx_script(program, script_inputs, vec![])
+            .map_err(|err| ClobError::Backend(err.to_string()))
+    }
+
+    fn execute(&mut self, tx_request: TransactionRequest) -> Result<TransactionResult, ClobError> {
+        let account = self.get_account(tx_request.account_id())?;
+        let note_ids = tx_request.get_input_note_ids();
+        let consumed = note_ids
+            .iter()
+            .filter(|id| self.consumed.contains_key(id))
+            .copied()
+            .collect::<Vec<_>>();
+        if !consumed.is_empty() {
+            return Err(ClobError::NoteAlreadyConsumed(consumed));
+        }
+        let input_notes = note_ids
+            .iter()
+            .map(|id| {
+                self.notes
+                    .get(id)
+                    .map(|(note, _)| note.clone())
+                    .ok_or_else(|| ClobError::NotFound(format!("unconsumed note {}", id.to_hex())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let data_store = ChainSnapshot::new(account, input_notes, self.block_num);
+        let block_num = data_store.block_header.block_num();
+        let mut executor = TransactionExecutor::new(data_store);
+        executor
+            .load_account(tx_request.account_id())
+            .map_err(|err| ClobError::Backend(err.to_string()))?;
+
+        let tx_args =
+            TransactionArgs::new(tx_request.tx_script().cloned(), Some(tx_request.get_note_args()));
+        let executed = executor
+            .execute_transaction(tx_request.account_id(), block_num, &note_ids, tx_args)
+            .map_err(|err| ClobError::Backend(err.to_string()))?;
+
+        Ok(TransactionResult::new(executed, tx_request.expected_output_notes().to_vec()))
+    }
+
+    /// Queues the transaction for the next block; the proof is not verified
+    ///
+    /// An account may only have one pending transaction, as a second one
+    /// would have been executed against its state before the first.
+    async fn submit(
+        &mut self,
+        result: TransactionResult,
+        _proven: ProvenTransaction,
+    ) -> Result<(), ClobError> {
+        let input_note_ids = result
+            .executed_transaction()
+            .input_notes()
+            .iter()
+            .map(|note| note.id())
+            .collect::<Vec<_>>();
+        let available = self.available_notes(&input_note_ids);
+        let spent =
+            input_note_ids.into_iter().filter(|id| !available.contains(id)).collect::<Vec<_>>();
+        if !spent.is_empty() {
+            return Err(ClobError::NoteAlreadyConsumed(spent));
+        }
+
+        let account_id = result.executed_transaction().account_id();
+        if self
+            .pending_transactions
+            .iter()
+            .any(|pending| pending.executed_transaction().account_id() == account_id)
+        {
+            return Err(ClobError::Backend(format!(
+                "account {account_id} already has a pending transaction"
+            )));
+        }
+
+        self.pending_transactions.push(result);
+        Ok(())
+    }
+
+    /// Produces a block when the transaction is still pending, so that every
+    /// poll commits it
+    async fn poll_commit(
+        &mut self,
+        transaction_id: TransactionId,
+        _input_note_ids: &[NoteId],
+    ) -> Result<Option<u32>, ClobError> {
+        if let Some(block_num) = self.committed.get(&transaction_id) {
+            return Ok(Some(*block_num));
+        }
+        if !self
+            .pending_transactions
+            .iter()
+            .any(|pending| pending.executed_transaction().id() == transaction_id)
+        {
+            return Err(ClobError::NotFound(format!("transaction {}", transaction_id.to_hex())));
+        }
+
+        self.produce_block().map(Some)
+    }
+}
+
+/// Transaction inputs for one account and its input notes, with the block
+/// header and chain MMR of miden-mock authenticating the notes
+struct ChainSnapshot {
+    account: Account,
+    block_header: BlockHeader,
+    block_chain: ChainMmr,
+    notes: Vec<InputNote>,
+}
+
+impl ChainSnapshot {
+    /// Inputs executing at `block_num`, or at the reference block of
+    /// miden-mock if that one is higher
+    fn new(account: Account, notes: Vec<Note>, block_num: u32) -> Self {
+        let (account, mut block_header, mut block_chain, notes, _) = mock_inputs_with_existing(
+            MockAccountType::StandardExisting,
+            AssetPreservationStatus::Preserved,
+            Some(account),
+            Some(notes),
+        );
+
+        // The chain MMR holds every block before the reference block, so each
+        // empty block appends the previous reference block to it
+        while block_header.block_num() < block_num {
+            block_chain.add_block(block_header, false);
+            block_header = mock_block_header(
+                block_header.block_num() + 1,
+                Some(block_chain.peaks().hash_peaks()),
+                None,
+                &[account.clone()],
+            );
+        }
+
+        Self {
+            account,
+            block_header,
+            block_chain,
+            notes,
+        }
+    }
+}
+
+impl DataStore for ChainSnapshot {
+    fn get_transaction_inputs(
+        &self,
+        account_id: AccountId,
+        block_num: u32,
+        notes: &[NoteId],
+    ) -> Result<TransactionInputs, DataStoreError> {
+        if account_id != self.account.id() {
+            return Err(DataStoreError::AccountNotFound(account_id));
+        }
+        if block_num != self.block_header.block_num() {
+            return Err(DataStoreError::BlockNotFound(block_num));
+        }
+
+        let notes = self
+            .notes
+            .iter()
+            .filter(|note| notes.contains(&note.id()))
+            .cloned()
+            .collect::<Vec<_>>();
+        let notes = InputNotes::new(notes).map_err(DataStoreError::InvalidTransactionInput)?;
+
+        TransactionInputs::new(
+            self.account.clone(),
+            None,
+            self.block_header,
+            self.block_chain.clone(),
+            notes,
+        )
+        .map_err(DataStoreError::InvalidTransactionInput)
+    }
+
+    fn get_account_code(&self, account_id: AccountId) -> Result<ModuleAst, DataStoreError> {
+        if account_id != self.account.id() {
+            return Err(DataStoreError::AccountNotFound(account_id));
+        }
+        Ok(self.account.code().module().clone())
+    }
+}
@@ -0,0 +1,24 @@
+use std::net::SocketAddr;
+
+use keom_clob::clob::Clob;
+use keom_clob::config::ClobConfig;
+use keom_clob::grpc::{serve_grpc, DEFAULT_GRPC_ADDR};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Serves the gRPC API on KEOM_GRPC_ADDR, 127.0.0.1:50051 by default
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_timer(fmt::time::Uptime::default()))
+        .with(EnvFilter::from_default_env())
+        .init();
+
+    let addr: SocketAddr = std::env::var("KEOM_GRPC_ADDR")
+        .unwrap_or_else(|_| DEFAULT_GRPC_ADDR.to_string())
+        .parse()
+        .expect("KEOM_GRPC_ADDR is not a socket address");
+
+    let config = ClobConfig::load_default().unwrap();
+    let clob = Clob::open(&config).unwrap();
+    serve_grpc(addr, clob).await.unwrap();
+}
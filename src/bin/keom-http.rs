@@ -0,0 +1,24 @@
+use std::net::SocketAddr;
+
+use keom_clob::clob::Clob;
+use keom_clob::config::ClobConfig;
+use keom_clob::server::{serve, DEFAULT_HTTP_ADDR};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Serves the HTTP API on KEOM_HTTP_ADDR, 127.0.0.1:3000 by default
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_timer(fmt::time::Uptime::default()))
+        .with(EnvFilter::from_default_env())
+        .init();
+
+    let addr: SocketAddr = std::env::var("KEOM_HTTP_ADDR")
+        .unwrap_or_else(|_| DEFAULT_HTTP_ADDR.to_string())
+        .parse()
+        .expect("KEOM_HTTP_ADDR is not a socket address");
+
+    let config = ClobConfig::load_default().unwrap();
+    let clob = Clob::open(&config).unwrap();
+    serve(addr, clob).await.unwrap();
+}
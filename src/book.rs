@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use miden_client::store::{InputNoteRecord, NoteFilter};
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use tracing::{debug, instrument};
+
+use crate::backend::ClobBackend;
+use crate::errors::ClobError;
+use crate::limit_swap_note_script;
+use crate::market::Price;
+use crate::order::{Fill, LimitOrder};
+
+/// Resting limit orders, as seen by the local client
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    orders: BTreeMap<NoteId, LimitOrder>,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Syncs the backend with the chain and reloads the resting orders
+    #[instrument(skip_all)]
+    pub async fn refresh<B: ClobBackend>(&mut self, backend: &mut B) -> Result<(), ClobError> {
+        backend.sync().await?;
+        self.reload(backend)
+    }
+
+    /// Reloads the resting orders from the committed limit-swap notes known to
+    /// the backend, without syncing
+    pub fn reload<B: ClobBackend>(&mut self, backend: &B) -> Result<(), ClobError> {
+        self.load(backend.get_notes(NoteFilter::Committed)?);
+        Ok(())
+    }
+
+    fn load(&mut self, records: Vec<InputNoteRecord>) {
+        let script_hash = limit_swap_note_script().hash();
+
+        self.orders.clear();
+        for record in records {
+            if record.details().script().hash() != script_hash {
+                continue;
+            }
+            match LimitOrder::from_record(&record) {
+                Ok(order) => {
+                    self.orders.insert(order.note_id, order);
+                }
+                Err(err) => debug!(%err, "Skipping limit-swap note"),
+            }
+        }
+        debug!(orders = self.orders.len(), "Reloaded order book");
+    }
+
+    pub fn insert(&mut self, order: LimitOrder) {
+        self.orders.insert(order.note_id, order);
+    }
+
+    pub fn remove(&mut self, note_id: &NoteId) -> Option<LimitOrder> {
+        self.orders.remove(note_id)
+    }
+
+    pub fn get(&self, note_id: &NoteId) -> Option<&LimitOrder> {
+        self.orders.get(note_id)
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &LimitOrder> {
+        self.orders.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// Returns the orders offering assets of `offered_faucet` in exchange for
+    /// assets of `requested_faucet`, cheapest for a taker first
+    pub fn offers(
+        &self,
+        offered_faucet: AccountId,
+        requested_faucet: AccountId,
+    ) -> Vec<&LimitOrder> {
+        let mut offers = self
+            .orders
+            .values()
+            .filter(|order| {
+                order.offered.faucet_id() == offered_faucet
+                    && order.requested.faucet_id() == requested_faucet
+            })
+            .filter_map(|order| {
+                Price::new(order.requested.amount(), order.offered.amount())
+                    .map(|cost| (cost, order))
+            })
+            .collect::<Vec<_>>();
+        offers.sort_by(|(a_cost, a), (b_cost, b)| {
+            a_cost.cmp(b_cost).then_with(|| a.note_id.cmp(&b.note_id))
+        });
+        offers.into_iter().map(|(_, order)| order).collect()
+    }
+
+    /// Returns the resting orders an order offering `offered` for `requested`
+    /// would trade against, i.e. opposite orders giving at least as much of the
+    /// requested asset per unit of the offered asset as the order asks for
+    pub fn crossing_orders(
+        &self,
+        offered: &FungibleAsset,
+        requested: &FungibleAsset,
+    ) -> Vec<NoteId> {
+        let Some(asked) = Price::new(requested.amount(), offered.amount()) else {
+            return Vec::new();
+        };
+        self.offers(requested.faucet_id(), offered.faucet_id())
+            .into_iter()
+            .filter(|order| {
+                Price::new(order.offered.amount(), order.requested.amount())
+                    .is_some_and(|given| given >= asked)
+            })
+            .map(|order| order.note_id)
+            .collect()
+    }
+
+    /// Like [OrderBook::offers], with the fill for paying up to `in_amount`,
+    /// skipping orders whose minimum fill the payment does not reach
+    pub fn fillable_offers(
+        &self,
+        offered_faucet: AccountId,
+        requested_faucet: AccountId,
+        in_amount: u64,
+    ) -> Vec<(&LimitOrder, Fill)> {
+        self.offers(offered_faucet, requested_faucet)
+            .into_iter()
+            .filter_map(|order| order.fill_for_input(in_amount).map(|fill| (order, fill)))
+            .collect()
+    }
+}
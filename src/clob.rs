@@ -0,0 +1,476 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use miden_client::store::NoteFilter;
+use miden_objects::accounts::{Account, AccountId};
+use miden_objects::assets::{Asset, FungibleAsset};
+use miden_objects::notes::NoteId;
+use serde::Deserialize;
+use tracing::instrument;
+use vm_processor::Digest;
+
+use crate::backend::{execute_on, BatchReport, ClobBackend, NodeBackend};
+use crate::book::OrderBook;
+use crate::config::{ClobConfig, MarketConfig};
+use crate::depth::{depth, Depth};
+use crate::errors::ClobError;
+use crate::maker::{cancel_all, cancel_order, replace_order, CancelReport};
+use crate::market::{Market, Markets, Pair};
+use crate::order::{Fill, LimitOrder, NewOrder, OrderOptions};
+use crate::profiles::AccountProfiles;
+use crate::quote::{quote_buy, Quote};
+use crate::receipt::TransactionReceipt;
+use crate::registry::OrderRegistry;
+use crate::taker::{take_best_order, RetryPolicy};
+use crate::tokens::TokenRegistry;
+use crate::{build_consume_notes_tx_req, mint_batch, place_limit_order, MidenClient, MintRequest};
+
+/// The CLOB as seen by one chain backend: its accounts, tokens, markets, the
+/// order book and the orders placed by its makers.
+///
+/// Accounts are referred to by their profile alias. Every operation touching
+/// the book refreshes it first.
+pub struct Clob<B: ClobBackend = NodeBackend> {
+    backend: B,
+    profiles: AccountProfiles,
+    tokens: TokenRegistry,
+    markets: Markets,
+    book: OrderBook,
+    registry: OrderRegistry,
+}
+
+impl Clob<NodeBackend> {
+    /// Opens the default client store, profiles and order registry, with the
+    /// tokens and markets of `config`
+    pub fn open(config: &ClobConfig) -> Result<Self, ClobError> {
+        Self::new(
+            NodeBackend::new()?,
+            AccountProfiles::load_default()?,
+            OrderRegistry::load_default()?,
+            config,
+        )
+    }
+
+    pub fn client(&self) -> &MidenClient {
+        self.backend.client()
+    }
+
+    pub fn client_mut(&mut self) -> &mut MidenClient {
+        self.backend.client_mut()
+    }
+}
+
+impl<B: ClobBackend> Clob<B> {
+    /// Builds a CLOB over `backend`, with the tokens and markets of `config`
+    pub fn new(
+        backend: B,
+        profiles: AccountProfiles,
+        registry: OrderRegistry,
+        config: &ClobConfig,
+    ) -> Result<Self, ClobError> {
+        let mut tokens = TokenRegistry::from_config(config)?;
+        tokens.load_faucets(&backend, &profiles)?;
+        let markets = Markets::from_config(config, &tokens)?;
+
+        Ok(Self {
+            backend,
+            profiles,
+            tokens,
+            markets,
+            book: OrderBook::new(),
+            registry,
+        })
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn profiles(&self) -> &AccountProfiles {
+        &self.profiles
+    }
+
+    pub fn tokens(&self) -> &TokenRegistry {
+        &self.tokens
+    }
+
+    pub fn markets(&self) -> &Markets {
+        &self.markets
+    }
+
+    /// The order book as of the last refresh
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn registry(&self) -> &OrderRegistry {
+        &self.registry
+    }
+
+    /// Syncs the backend with the chain and reloads the order book
+    pub async fn sync(&mut self) -> Result<(), ClobError> {
+        self.book.refresh(&mut self.backend).await
+    }
+
+    /// Returns the recipients of the notes known to the backend, which include
+    /// the paybacks of the orders it saw being filled
+    pub fn known_recipients(&self) -> Result<BTreeSet<Digest>, ClobError> {
+        let notes = self.backend.get_notes(NoteFilter::All)?;
+        Ok(notes.iter().map(|note| note.recipient()).collect())
+    }
+
+    /// Returns the account registered under `alias`
+    pub fn account(&self, alias: &str) -> Result<Account, ClobError> {
+        let account_id = self
+            .profiles
+            .account(alias)
+            .ok_or_else(|| ClobError::NotFound(format!("account {alias}")))?;
+        self.backend.get_account(account_id)
+    }
+
+    /// Creates a wallet registered under `alias`, or returns the one already
+    /// registered
+    pub fn new_account(&mut self, alias: &str) -> Result<Account, ClobError> {
+        self.profiles.get_or_create_account(&mut self.backend, alias)
+    }
+
+    /// Creates the faucet of token `symbol` and registers the token, or
+    /// returns the faucet already registered
+    pub fn new_faucet(
+        &mut self,
+        symbol: &str,
+        decimals: u8,
+        max_supply: u64,
+    ) -> Result<Account, ClobError> {
+        let faucet =
+            self.profiles.get_or_create_faucet(&mut self.backend, symbol, decimals, max_supply)?;
+        self.tokens.load_faucets(&self.backend, &self.profiles)?;
+        Ok(faucet)
+    }
+
+    /// Mints `assets` to the account registered under `alias`, see [mint_batch]
+    #[instrument(skip_all, fields(account = alias))]
+    pub async fn mint(
+        &mut self,
+        alias: &str,
+        assets: Vec<FungibleAsset>,
+    ) -> Result<BatchReport, ClobError> {
+        let account = self.account(alias)?;
+        let requests = [MintRequest::new(account.id(), assets)];
+        let mut report = mint_batch(&mut self.backend, &requests).await;
+        if let Err(err) = self.backend.sync().await {
+            report.error.get_or_insert(err);
+        }
+        Ok(report)
+    }
+
+    /// Opens the markets of `markets` on top of the configured ones. Their
+    /// tokens must be known already, e.g. created with [Clob::new_faucet].
+    pub fn add_markets(&mut self, markets: &[MarketConfig]) -> Result<(), ClobError> {
+        let config = ClobConfig {
+            tokens: Vec::new(),
+            markets: markets.to_vec(),
+        };
+        for market in Markets::from_config(&config, &self.tokens)?.iter() {
+            self.markets.insert(*market);
+        }
+        Ok(())
+    }
+
+    /// Consumes `note_ids` into the account registered under `alias`, e.g. the
+    /// paybacks of its filled orders
+    #[instrument(skip_all, fields(account = alias, notes = note_ids.len()))]
+    pub async fn consume_notes(
+        &mut self,
+        alias: &str,
+        note_ids: Vec<NoteId>,
+    ) -> Result<TransactionReceipt, ClobError> {
+        let account = self.account(alias)?;
+        let notes = note_ids.into_iter().map(|note_id| (note_id, None)).collect();
+        let tx_request = build_consume_notes_tx_req(&self.backend, account.id(), notes)?;
+        execute_on(&mut self.backend, tx_request).await
+    }
+
+    /// Returns the fungible assets held by the account registered under `alias`
+    pub fn balances(&self, alias: &str) -> Result<Vec<FungibleAsset>, ClobError> {
+        Ok(fungible_assets(&self.account(alias)?))
+    }
+
+    /// Returns the market trading `base` against `quote`, by token symbol
+    pub fn pair(&self, base: &str, quote: &str) -> Result<Pair, ClobError> {
+        find_pair(&self.tokens, &self.markets, base, quote)
+    }
+
+    /// Returns the market named like "ETH/DAI", see [Clob::pair]
+    pub fn parse_pair(&self, market: &str) -> Result<Pair, ClobError> {
+        parse_pair(&self.tokens, &self.markets, market)
+    }
+
+    /// Copies the state reads are served from, see [ClobView]
+    pub fn view(&self) -> Result<ClobView, ClobError> {
+        let balances = self
+            .profiles
+            .accounts()
+            .map(|(_, account_id)| {
+                let account = self.backend.get_account(account_id)?;
+                Ok((account_id, fungible_assets(&account)))
+            })
+            .collect::<Result<_, ClobError>>()?;
+
+        Ok(ClobView {
+            profiles: self.profiles.clone(),
+            tokens: self.tokens.clone(),
+            markets: self.markets.clone(),
+            book: self.book.clone(),
+            registry: self.registry.clone(),
+            balances,
+        })
+    }
+
+    /// Returns the best `max_levels` levels of each side of `pair`
+    pub async fn depth(&mut self, pair: Pair, max_levels: usize) -> Result<Depth, ClobError> {
+        self.sync().await?;
+        Ok(depth(&self.book, pair, max_levels))
+    }
+
+    /// Quotes buying `want` with assets of `give`
+    pub async fn quote(
+        &mut self,
+        want: FungibleAsset,
+        give: AccountId,
+    ) -> Result<Quote, ClobError> {
+        self.sync().await?;
+        quote_buy(&self.book, &self.markets, want, give).ok_or(ClobError::NoLiquidity)
+    }
+
+    /// Returns the resting order of note `note_id`
+    pub fn order(&self, note_id: &NoteId) -> Result<&LimitOrder, ClobError> {
+        self.book
+            .get(note_id)
+            .ok_or_else(|| ClobError::NotFound(format!("order {}", note_id.to_hex())))
+    }
+
+    #[instrument(skip_all, fields(maker = maker))]
+    pub async fn place_order(
+        &mut self,
+        maker: &str,
+        order: &NewOrder,
+    ) -> Result<TransactionReceipt, ClobError> {
+        let maker = self.account(maker)?;
+        self.sync().await?;
+        let market = *self.market_of(order)?;
+        place_limit_order(&mut self.backend, &market, &self.book, &mut self.registry, &maker, order)
+            .await
+    }
+
+    #[instrument(skip_all, fields(maker = maker, note = note_id.to_hex()))]
+    pub async fn cancel_order(
+        &mut self,
+        maker: &str,
+        note_id: &NoteId,
+    ) -> Result<TransactionReceipt, ClobError> {
+        let maker = self.account(maker)?;
+        self.sync().await?;
+        let order = self.order(note_id)?.clone();
+        cancel_order(&mut self.backend, &mut self.registry, &maker, &order).await
+    }
+
+    /// Reclaims every resting order of `maker`, or only those of `pair`, see
+    /// [cancel_all]
+    #[instrument(skip_all, fields(maker = maker, pair = ?pair))]
+    pub async fn cancel_all(
+        &mut self,
+        maker: &str,
+        pair: Option<Pair>,
+    ) -> Result<CancelReport, ClobError> {
+        let maker = self.account(maker)?;
+        let report = cancel_all(&mut self.backend, &mut self.registry, &maker, pair).await?;
+        self.sync().await?;
+        Ok(report)
+    }
+
+    /// Replaces the order of note `note_id` by `new` atomically, see
+    /// [replace_order]
+    #[instrument(skip_all, fields(maker = maker, note = note_id.to_hex()))]
+    pub async fn replace_order(
+        &mut self,
+        maker: &str,
+        note_id: &NoteId,
+        new: &NewOrder,
+    ) -> Result<TransactionReceipt, ClobError> {
+        let maker = self.account(maker)?;
+        self.sync().await?;
+        let old = self.order(note_id)?.clone();
+        let market = *self.market_of(new)?;
+        replace_order(&mut self.backend, &market, &self.book, &mut self.registry, &maker, &old, new)
+            .await
+    }
+
+    /// Takes the best order offering assets of `want` for at most `give`
+    #[instrument(skip_all, fields(taker = taker))]
+    pub async fn take(
+        &mut self,
+        taker: &str,
+        want: AccountId,
+        give: FungibleAsset,
+    ) -> Result<(Fill, TransactionReceipt), ClobError> {
+        let taker = self.account(taker)?;
+        take_best_order(
+            &mut self.backend,
+            &mut self.book,
+            &self.markets,
+            &taker,
+            want,
+            give,
+            RetryPolicy::default(),
+        )
+        .await
+    }
+
+    fn market_of(&self, order: &NewOrder) -> Result<&Market, ClobError> {
+        self.markets.find(order.offered.faucet_id(), order.requested.faucet_id()).ok_or_else(|| {
+            ClobError::NotFound(format!(
+                "market for {} in exchange for {}",
+                self.tokens.format_amount(&order.offered),
+                self.tokens.format_amount(&order.requested)
+            ))
+        })
+    }
+}
+
+/// An order as entered by a user, with amounts such as "1.5 ETH"
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct OrderInput {
+    /// What the maker offers
+    pub offered: String,
+    /// What the maker wants in exchange
+    pub requested: String,
+    #[serde(default)]
+    pub post_only: bool,
+    /// See [OrderOptions::min_fill], in the offered token
+    #[serde(default)]
+    pub min_fill: Option<String>,
+    /// See [OrderOptions::min_remaining], in the offered token
+    #[serde(default)]
+    pub min_remaining: Option<String>,
+}
+
+impl OrderInput {
+    pub fn to_order(&self, tokens: &TokenRegistry) -> Result<NewOrder, ClobError> {
+        let offered = tokens.parse_amount(&self.offered)?;
+        let requested = tokens.parse_amount(&self.requested)?;
+        let offered_amount = |amount: &Option<String>| match amount {
+            Some(amount) => parse_amount_of(tokens, amount, &offered),
+            None => Ok(0),
+        };
+        let options = OrderOptions {
+            min_remaining: offered_amount(&self.min_remaining)?,
+            min_fill: offered_amount(&self.min_fill)?,
+        };
+
+        let order = NewOrder::new(offered, requested).with_options(options);
+        Ok(if self.post_only {
+            order.post_only()
+        } else {
+            order
+        })
+    }
+}
+
+/// Parses an amount such as "0.1 ETH", which must be of the same token as
+/// `asset`, into base units
+pub fn parse_amount_of(
+    tokens: &TokenRegistry,
+    amount: &str,
+    asset: &FungibleAsset,
+) -> Result<u64, ClobError> {
+    let parsed = tokens.parse_amount(amount)?;
+    if parsed.faucet_id() != asset.faucet_id() {
+        return Err(ClobError::InvalidOrder(format!("{amount} is not of the offered token")));
+    }
+    Ok(parsed.amount())
+}
+
+/// Parses the hex ID of a note
+pub fn parse_note_id(hex: &str) -> Result<NoteId, ClobError> {
+    Digest::try_from(hex)
+        .map(NoteId::from)
+        .map_err(|_| ClobError::InvalidOrder(format!("invalid note id {hex}")))
+}
+
+/// Copy of the state of a [Clob] as of its last refresh, to serve reads while
+/// the CLOB itself is busy proving or waiting for a transaction
+#[derive(Debug, Clone)]
+pub struct ClobView {
+    pub profiles: AccountProfiles,
+    pub tokens: TokenRegistry,
+    pub markets: Markets,
+    pub book: OrderBook,
+    pub registry: OrderRegistry,
+    /// Fungible assets held by each account of the profiles
+    pub balances: BTreeMap<AccountId, Vec<FungibleAsset>>,
+}
+
+impl ClobView {
+    /// Returns the ID of the account registered under `alias`
+    pub fn account_id(&self, alias: &str) -> Result<AccountId, ClobError> {
+        self.profiles.account(alias).ok_or_else(|| ClobError::NotFound(format!("account {alias}")))
+    }
+
+    /// Returns the fungible assets held by the account registered under `alias`
+    pub fn balances(&self, alias: &str) -> Result<&[FungibleAsset], ClobError> {
+        let account_id = self.account_id(alias)?;
+        self.balances
+            .get(&account_id)
+            .map(Vec::as_slice)
+            .ok_or_else(|| ClobError::NotFound(format!("account {alias}")))
+    }
+
+    /// Returns the market trading `base` against `quote`, by token symbol
+    pub fn pair(&self, base: &str, quote: &str) -> Result<Pair, ClobError> {
+        find_pair(&self.tokens, &self.markets, base, quote)
+    }
+
+    /// Returns the market named like "ETH/DAI"
+    pub fn parse_pair(&self, market: &str) -> Result<Pair, ClobError> {
+        parse_pair(&self.tokens, &self.markets, market)
+    }
+
+    /// Returns the best `max_levels` levels of each side of `pair`
+    pub fn depth(&self, pair: Pair, max_levels: usize) -> Depth {
+        depth(&self.book, pair, max_levels)
+    }
+}
+
+fn fungible_assets(account: &Account) -> Vec<FungibleAsset> {
+    account
+        .vault()
+        .assets()
+        .filter_map(|asset| match asset {
+            Asset::Fungible(asset) => Some(asset),
+            Asset::NonFungible(_) => None,
+        })
+        .collect()
+}
+
+fn find_pair(
+    tokens: &TokenRegistry,
+    markets: &Markets,
+    base: &str,
+    quote: &str,
+) -> Result<Pair, ClobError> {
+    let pair = Pair::new(tokens.faucet_id(base)?, tokens.faucet_id(quote)?);
+    markets
+        .get(&pair)
+        .map(|market| market.pair)
+        .ok_or_else(|| ClobError::NotFound(format!("market {base}/{quote}")))
+}
+
+fn parse_pair(tokens: &TokenRegistry, markets: &Markets, market: &str) -> Result<Pair, ClobError> {
+    let (base, quote) =
+        market.split_once('/').ok_or_else(|| ClobError::NotFound(format!("market {market}")))?;
+    find_pair(tokens, markets, base.trim(), quote.trim())
+}
@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ClobError;
+
+/// Where the CLOB configuration is read from by default
+pub const DEFAULT_CONFIG_PATH: &str = "./keom.toml";
+
+/// Configuration shared by the library and the binaries, read from a TOML file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClobConfig {
+    /// Tokens known up front, in addition to the faucets found in the profiles
+    #[serde(default)]
+    pub tokens: Vec<TokenConfig>,
+    /// Markets and their trading rules
+    #[serde(default)]
+    pub markets: Vec<MarketConfig>,
+}
+
+/// A token entry of the configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenConfig {
+    pub symbol: String,
+    /// Hex encoded faucet account ID
+    pub faucet_id: String,
+    pub decimals: u8,
+    pub max_supply: u64,
+}
+
+/// A market entry of the configuration, with sizes in token units
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketConfig {
+    /// Symbol of the base token
+    pub base: String,
+    /// Symbol of the quote token
+    pub quote: String,
+    /// Price increment, in quote tokens per base token, e.g. "0.01"
+    pub tick_size: String,
+    /// Size increment, in base tokens, e.g. "0.001"
+    pub lot_size: String,
+}
+
+impl ClobConfig {
+    /// Loads the configuration at `path`, using defaults if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ClobError> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|err| ClobError::Config(format!("{}: {err}", path.display()))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(ClobError::Io(err)),
+        }
+    }
+
+    /// Loads the configuration at [DEFAULT_CONFIG_PATH]
+    pub fn load_default() -> Result<Self, ClobError> {
+        Self::load(DEFAULT_CONFIG_PATH)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ClobError> {
+        toml::from_str(contents).map_err(|err| ClobError::Config(err.to_string()))
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+
+use tracing::{debug, instrument};
+
+use crate::backend::ClobBackend;
+use crate::book::OrderBook;
+use crate::errors::ClobError;
+use crate::market::{Pair, Price, Side};
+
+/// Resting orders of one side of a market at the same price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub price: Price,
+    /// Total size of the orders, in base units
+    pub size: u64,
+    /// Number of orders
+    pub orders: usize,
+}
+
+/// Aggregated view of the best levels of a market
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Depth {
+    pub pair: Pair,
+    /// Highest price first
+    pub bids: Vec<Level>,
+    /// Lowest price first
+    pub asks: Vec<Level>,
+}
+
+/// Levels of both sides of a market, keyed by price
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Levels {
+    bids: BTreeMap<Price, Level>,
+    asks: BTreeMap<Price, Level>,
+}
+
+impl Levels {
+    fn aggregate(book: &OrderBook, pair: Pair) -> Self {
+        let mut levels = Self::default();
+        for order in book.orders() {
+            let Some((side, size, price)) = pair.classify(&order.offered, &order.requested) else {
+                continue;
+            };
+            let level = levels.side_mut(side).entry(price).or_insert(Level {
+                price,
+                size: 0,
+                orders: 0,
+            });
+            level.size += size;
+            level.orders += 1;
+        }
+        levels
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<Price, Level> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    fn depth(&self, pair: Pair, max_levels: usize) -> Depth {
+        Depth {
+            pair,
+            bids: self.bids.values().rev().take(max_levels).copied().collect(),
+            asks: self.asks.values().take(max_levels).copied().collect(),
+        }
+    }
+}
+
+/// Returns the best `max_levels` levels of each side of `pair`
+pub fn depth(book: &OrderBook, pair: Pair, max_levels: usize) -> Depth {
+    Levels::aggregate(book, pair).depth(pair, max_levels)
+}
+
+/// How a level changed between two snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelChange {
+    Added(Level),
+    Changed(Level),
+    /// No order is left at the price
+    Removed(Price),
+}
+
+/// A change to one level of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelDiff {
+    pub side: Side,
+    pub change: LevelChange,
+}
+
+/// Changes of a market between two consecutive updates of a [DepthTracker]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthUpdate {
+    pub pair: Pair,
+    /// Incremented by one on every update, so consumers can detect gaps
+    pub sequence: u64,
+    pub diffs: Vec<LevelDiff>,
+}
+
+impl DepthUpdate {
+    /// Every level of `depth` as added, so that a subscriber starting at
+    /// `sequence` can apply the following updates on top
+    pub fn snapshot(depth: &Depth, sequence: u64) -> Self {
+        let levels = |side, levels: &[Level]| {
+            levels.iter().map(move |level| LevelDiff {
+                side,
+                change: LevelChange::Added(*level),
+            })
+        };
+        let diffs = levels(Side::Bid, &depth.bids).chain(levels(Side::Ask, &depth.asks)).collect();
+        Self {
+            pair: depth.pair,
+            sequence,
+            diffs,
+        }
+    }
+}
+
+/// Follows the depth of a market across book refreshes, emitting the level
+/// changes in between
+#[derive(Debug, Clone)]
+pub struct DepthTracker {
+    pair: Pair,
+    sequence: u64,
+    levels: Levels,
+}
+
+impl DepthTracker {
+    /// Starts tracking `pair` from an empty book, at sequence 0
+    pub fn new(pair: Pair) -> Self {
+        Self {
+            pair,
+            sequence: 0,
+            levels: Levels::default(),
+        }
+    }
+
+    pub fn pair(&self) -> Pair {
+        self.pair
+    }
+
+    /// Sequence number of the last update
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the current best `max_levels` levels, with the sequence number
+    /// the following updates build on
+    pub fn snapshot(&self, max_levels: usize) -> (u64, Depth) {
+        (self.sequence, self.levels.depth(self.pair, max_levels))
+    }
+
+    /// Compares `book` with the last seen state, returning the level changes
+    /// as a new update, or None if no level changed
+    pub fn update(&mut self, book: &OrderBook) -> Option<DepthUpdate> {
+        let levels = Levels::aggregate(book, self.pair);
+
+        let mut diffs = diff_side(Side::Bid, &self.levels.bids, &levels.bids);
+        diffs.extend(diff_side(Side::Ask, &self.levels.asks, &levels.asks));
+        self.levels = levels;
+        if diffs.is_empty() {
+            return None;
+        }
+
+        self.sequence += 1;
+        Some(DepthUpdate {
+            pair: self.pair,
+            sequence: self.sequence,
+            diffs,
+        })
+    }
+}
+
+fn diff_side(
+    side: Side,
+    old: &BTreeMap<Price, Level>,
+    new: &BTreeMap<Price, Level>,
+) -> Vec<LevelDiff> {
+    let removed = old
+        .keys()
+        .filter(|price| !new.contains_key(price))
+        .map(|price| LevelChange::Removed(*price));
+    let added_or_changed = new.values().filter_map(|level| match old.get(&level.price) {
+        None => Some(LevelChange::Added(*level)),
+        Some(previous) if previous != level => Some(LevelChange::Changed(*level)),
+        Some(_) => None,
+    });
+
+    removed.chain(added_or_changed).map(|change| LevelDiff { side, change }).collect()
+}
+
+/// Syncs the backend, refreshes `book` and returns the depth updates of the
+/// tracked markets that changed
+#[instrument(skip_all, fields(markets = trackers.len()))]
+pub async fn sync_depth<B: ClobBackend>(
+    backend: &mut B,
+    book: &mut OrderBook,
+    trackers: &mut [DepthTracker],
+) -> Result<Vec<DepthUpdate>, ClobError> {
+    book.refresh(backend).await?;
+    let updates =
+        trackers.iter_mut().filter_map(|tracker| tracker.update(book)).collect::<Vec<_>>();
+    debug!(updates = updates.len(), "Depth synced");
+    Ok(updates)
+}
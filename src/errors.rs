@@ -0,0 +1,113 @@
+use core::fmt;
+use std::time::Duration;
+
+use miden_client::errors::ClientError;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use miden_objects::transaction::TransactionId;
+use miden_objects::NoteError;
+
+/// Errors returned by the CLOB layer on top of the miden client
+#[derive(Debug)]
+pub enum ClobError {
+    /// The miden client failed for a reason not specific to order handling
+    Client(ClientError),
+    /// One or more input notes of the transaction were consumed by another
+    /// transaction before ours got committed
+    NoteAlreadyConsumed(Vec<NoteId>),
+    /// The transaction was not committed within the allowed time
+    CommitTimeout(TransactionId, Duration),
+    /// A note could not be decoded as a limit-swap order
+    InvalidOrderNote(NoteId, String),
+    /// Building a note failed
+    Note(NoteError),
+    /// No resting order can satisfy the request
+    NoLiquidity,
+    /// The account lacks these amounts to settle the request
+    InsufficientFunds(Vec<FungibleAsset>),
+    /// Every attempt allowed by the retry policy failed on a note conflict
+    RetriesExhausted { attempts: u32 },
+    /// Reading or writing a local file failed
+    Io(std::io::Error),
+    /// The account profiles are malformed
+    Profile(String),
+    /// The order registry is malformed
+    Registry(String),
+    /// The configuration file is malformed
+    Config(String),
+    /// An amount or token could not be resolved
+    Token(String),
+    /// The order breaks the rules of its market
+    InvalidOrder(String),
+    /// A post-only order would trade against these resting orders
+    WouldCross(Vec<NoteId>),
+    /// No account, order or market is known by this name
+    NotFound(String),
+    /// The chain backend failed to execute or prove a transaction
+    Backend(String),
+}
+
+impl ClobError {
+    /// Returns true if the error was caused by a competing transaction
+    /// consuming one of our input notes first
+    pub fn is_note_conflict(&self) -> bool {
+        matches!(self, ClobError::NoteAlreadyConsumed(_))
+    }
+}
+
+impl fmt::Display for ClobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClobError::Client(err) => write!(f, "miden client error: {err}"),
+            ClobError::NoteAlreadyConsumed(note_ids) => {
+                let ids = note_ids.iter().map(|id| id.to_hex()).collect::<Vec<_>>().join(", ");
+                write!(f, "note already consumed: {ids}")
+            }
+            ClobError::CommitTimeout(tx_id, waited) => {
+                write!(f, "transaction {} not committed after {:?}", tx_id.to_hex(), waited)
+            }
+            ClobError::InvalidOrderNote(note_id, reason) => {
+                write!(f, "note {} is not a valid limit order: {reason}", note_id.to_hex())
+            }
+            ClobError::Note(err) => write!(f, "note error: {err}"),
+            ClobError::NoLiquidity => write!(f, "no resting order can fill the request"),
+            ClobError::InsufficientFunds(missing) => {
+                let missing = missing
+                    .iter()
+                    .map(|asset| format!("{} of {}", asset.amount(), asset.faucet_id().to_hex()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "insufficient funds, missing {missing}")
+            }
+            ClobError::RetriesExhausted { attempts } => {
+                write!(f, "gave up after {attempts} conflicting attempts")
+            }
+            ClobError::Io(err) => write!(f, "io error: {err}"),
+            ClobError::Profile(reason) => write!(f, "invalid account profiles: {reason}"),
+            ClobError::Registry(reason) => write!(f, "invalid order registry: {reason}"),
+            ClobError::Config(reason) => write!(f, "invalid configuration: {reason}"),
+            ClobError::Token(reason) => write!(f, "token error: {reason}"),
+            ClobError::InvalidOrder(reason) => write!(f, "invalid order: {reason}"),
+            ClobError::WouldCross(note_ids) => {
+                let ids = note_ids.iter().map(|id| id.to_hex()).collect::<Vec<_>>().join(", ");
+                write!(f, "post-only order would cross: {ids}")
+            }
+            ClobError::Backend(reason) => write!(f, "backend error: {reason}"),
+            ClobError::NotFound(what) => write!(f, "not found: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for ClobError {}
+
+impl From<ClientError> for ClobError {
+    fn from(err: ClientError) -> Self {
+        ClobError::Client(err)
+    }
+}
+
+impl From<NoteError> for ClobError {
+    fn from(err: NoteError) -> Self {
+        ClobError::Note(err)
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use miden_objects::accounts::AccountId;
+use miden_objects::notes::NoteId;
+use serde_json::{json, Value};
+use vm_processor::Digest;
+
+use crate::book::OrderBook;
+use crate::depth::{Depth, DepthTracker, DepthUpdate, LevelChange};
+use crate::market::{Pair, Price, Side};
+use crate::order::LimitOrder;
+use crate::registry::{OrderRegistry, OrderStatus};
+
+/// Market data and order events, derived from consecutive book refreshes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedEvent {
+    Depth(DepthUpdate),
+    Trade(Trade),
+    Order(OrderUpdate),
+}
+
+/// A resting order was taken, fully or partially
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trade {
+    pub pair: Pair,
+    /// The consumed limit-swap note
+    pub note_id: NoteId,
+    pub maker: AccountId,
+    /// Side of the maker's order
+    pub side: Side,
+    /// Limit price of the maker's order
+    pub price: Price,
+    /// Traded size, in base units
+    pub size: u64,
+}
+
+/// What became of an order, as far as the local client can tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// The note appeared on the book
+    Open,
+    /// A taker consumed the note and a clone offers the rest
+    PartiallyFilled { remaining: NoteId },
+    /// A taker consumed the whole order
+    Filled,
+    /// The maker reclaimed the note
+    Cancelled,
+    /// The maker reclaimed the note and placed another one
+    Replaced { by: NoteId },
+    /// The note was consumed without a clone, by a maker or a taker the
+    /// local client does not follow
+    Closed,
+}
+
+/// A change of state of an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderUpdate {
+    pub note_id: NoteId,
+    pub maker: AccountId,
+    /// Market of the order, if it is of a tracked pair
+    pub pair: Option<Pair>,
+    pub state: OrderState,
+}
+
+impl FeedEvent {
+    /// Market the event is about
+    pub fn pair(&self) -> Option<Pair> {
+        match self {
+            FeedEvent::Depth(update) => Some(update.pair),
+            FeedEvent::Trade(trade) => Some(trade.pair),
+            FeedEvent::Order(update) => update.pair,
+        }
+    }
+
+    /// Account the event is about, None for market-wide events
+    pub fn account(&self) -> Option<AccountId> {
+        match self {
+            FeedEvent::Depth(_) => None,
+            FeedEvent::Trade(trade) => Some(trade.maker),
+            FeedEvent::Order(update) => Some(update.maker),
+        }
+    }
+
+    /// Encodes the event as JSON, with IDs in hex and amounts in base units
+    pub fn to_json(&self) -> Value {
+        match self {
+            FeedEvent::Depth(update) => {
+                let diffs = update
+                    .diffs
+                    .iter()
+                    .map(|diff| {
+                        let (change, level) = match diff.change {
+                            LevelChange::Added(level) => ("added", Some(level)),
+                            LevelChange::Changed(level) => ("changed", Some(level)),
+                            LevelChange::Removed(_) => ("removed", None),
+                        };
+                        let price = match diff.change {
+                            LevelChange::Added(level) | LevelChange::Changed(level) => level.price,
+                            LevelChange::Removed(price) => price,
+                        };
+                        json!({
+                            "side": side_name(diff.side),
+                            "change": change,
+                            "price": price.to_string(),
+                            "size": level.map_or(0, |level| level.size),
+                            "orders": level.map_or(0, |level| level.orders),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!({
+                    "type": "depth",
+                    "pair": pair_json(update.pair),
+                    "sequence": update.sequence,
+                    "diffs": diffs,
+                })
+            }
+            FeedEvent::Trade(trade) => json!({
+                "type": "trade",
+                "pair": pair_json(trade.pair),
+                "note_id": trade.note_id.to_hex(),
+                "maker": trade.maker.to_hex(),
+                "side": side_name(trade.side),
+                "price": trade.price.to_string(),
+                "size": trade.size,
+            }),
+            FeedEvent::Order(update) => {
+                let (state, note) = match update.state {
+                    OrderState::Open => ("open", None),
+                    OrderState::PartiallyFilled { remaining } => {
+                        ("partially_filled", Some(remaining))
+                    }
+                    OrderState::Filled => ("filled", None),
+                    OrderState::Cancelled => ("cancelled", None),
+                    OrderState::Replaced { by } => ("replaced", Some(by)),
+                    OrderState::Closed => ("closed", None),
+                };
+                json!({
+                    "type": "order",
+                    "pair": update.pair.map(pair_json),
+                    "note_id": update.note_id.to_hex(),
+                    "maker": update.maker.to_hex(),
+                    "state": state,
+                    "next_note_id": note.map(|id| id.to_hex()),
+                })
+            }
+        }
+    }
+}
+
+fn pair_json(pair: Pair) -> Value {
+    json!({ "base": pair.base.to_hex(), "quote": pair.quote.to_hex() })
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Bid => "bid",
+        Side::Ask => "ask",
+    }
+}
+
+/// Events a subscriber is interested in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subscription {
+    /// Markets to follow, all if empty
+    pub pairs: BTreeSet<Pair>,
+    /// Accounts whose trades and orders to follow, all if empty. Depth
+    /// updates are market-wide and only filtered by pair.
+    pub accounts: BTreeSet<AccountId>,
+}
+
+impl Subscription {
+    pub fn matches(&self, event: &FeedEvent) -> bool {
+        let pair_matches =
+            self.pairs.is_empty() || event.pair().is_some_and(|pair| self.pairs.contains(&pair));
+        let account_matches = match event.account() {
+            Some(account) => self.accounts.is_empty() || self.accounts.contains(&account),
+            None => true,
+        };
+        pair_matches && account_matches
+    }
+}
+
+/// Turns consecutive states of the book into [FeedEvent]s
+#[derive(Debug, Clone)]
+pub struct MarketFeed {
+    pairs: Vec<Pair>,
+    trackers: Vec<DepthTracker>,
+    previous: BTreeMap<NoteId, LimitOrder>,
+}
+
+impl MarketFeed {
+    /// Follows the depth and trades of `pairs`, starting from an empty book
+    pub fn new(pairs: impl IntoIterator<Item = Pair>) -> Self {
+        let pairs = pairs.into_iter().collect::<Vec<_>>();
+        Self {
+            trackers: pairs.iter().copied().map(DepthTracker::new).collect(),
+            pairs,
+            previous: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the best `max_levels` levels of `pair` as of the last update,
+    /// with the sequence number of its following depth updates, or None if
+    /// the pair is not followed
+    pub fn snapshot(&self, pair: Pair, max_levels: usize) -> Option<(u64, Depth)> {
+        let tracker = self.trackers.iter().find(|tracker| tracker.pair() == pair)?;
+        Some(tracker.snapshot(max_levels))
+    }
+
+    /// Returns a snapshot of every followed market of `subscription`, see
+    /// [DepthUpdate::snapshot]
+    pub fn snapshots(&self, subscription: &Subscription) -> Vec<DepthUpdate> {
+        self.trackers
+            .iter()
+            .filter(|tracker| {
+                subscription.pairs.is_empty() || subscription.pairs.contains(&tracker.pair())
+            })
+            .map(|tracker| {
+                let (sequence, depth) = tracker.snapshot(usize::MAX);
+                DepthUpdate::snapshot(&depth, sequence)
+            })
+            .collect()
+    }
+
+    /// Compares `book` with the previous state, returning what changed.
+    ///
+    /// The order registry tells which local orders were cancelled or replaced
+    /// by their maker. Otherwise a consumed order whose clone appeared, i.e. a
+    /// new note of the same maker with the same partial recipient, was
+    /// partially filled, and `paybacks`, the recipients of the notes known to
+    /// the client, reveal full fills.
+    pub fn update(
+        &mut self,
+        book: &OrderBook,
+        registry: &OrderRegistry,
+        paybacks: &BTreeSet<Digest>,
+    ) -> Vec<FeedEvent> {
+        let mut events = self
+            .trackers
+            .iter_mut()
+            .filter_map(|tracker| tracker.update(book))
+            .map(FeedEvent::Depth)
+            .collect::<Vec<_>>();
+
+        let current =
+            book.orders().map(|order| (order.note_id, order.clone())).collect::<BTreeMap<_, _>>();
+        let opened = current
+            .values()
+            .filter(|order| !self.previous.contains_key(&order.note_id))
+            .collect::<Vec<_>>();
+
+        for order in self.previous.values() {
+            if current.contains_key(&order.note_id) {
+                continue;
+            }
+            let clone = opened.iter().find(|new| {
+                new.maker == order.maker && new.partial_recipient == order.partial_recipient
+            });
+            let record =
+                registry.get(&order.note_id).map(|record| (record.status, record.replaced_by));
+            let state = match (record, clone) {
+                (Some((OrderStatus::Replaced, Some(by))), _) => OrderState::Replaced { by },
+                (Some((OrderStatus::Cancelled, _)), _) => OrderState::Cancelled,
+                (_, Some(clone)) => OrderState::PartiallyFilled {
+                    remaining: clone.note_id,
+                },
+                (Some((OrderStatus::Filled, _)), None) => OrderState::Filled,
+                _ if paybacks.contains(&order.payback_recipient) => OrderState::Filled,
+                _ => OrderState::Closed,
+            };
+
+            let traded = match state {
+                OrderState::PartiallyFilled { .. } => {
+                    clone.map(|clone| order.offered.amount().saturating_sub(clone.offered.amount()))
+                }
+                OrderState::Filled => Some(order.offered.amount()),
+                _ => None,
+            };
+            let pair = self.pair_of(order);
+            if let (Some(pair), Some(traded)) = (pair, traded) {
+                if let Some(trade) = trade(pair, order, traded) {
+                    events.push(FeedEvent::Trade(trade));
+                }
+            }
+
+            events.push(FeedEvent::Order(OrderUpdate {
+                note_id: order.note_id,
+                maker: order.maker,
+                pair,
+                state,
+            }));
+        }
+
+        for order in opened {
+            events.push(FeedEvent::Order(OrderUpdate {
+                note_id: order.note_id,
+                maker: order.maker,
+                pair: self.pair_of(order),
+                state: OrderState::Open,
+            }));
+        }
+
+        self.previous = current;
+        events
+    }
+
+    fn pair_of(&self, order: &LimitOrder) -> Option<Pair> {
+        self.pairs
+            .iter()
+            .find(|pair| pair.classify(&order.offered, &order.requested).is_some())
+            .copied()
+    }
+}
+
+/// Builds the trade of `traded` units of the offered asset of `order`
+fn trade(pair: Pair, order: &LimitOrder, traded: u64) -> Option<Trade> {
+    let (side, _, price) = pair.classify(&order.offered, &order.requested)?;
+    let size = match side {
+        Side::Ask => traded,
+        // A bid offers quote, its size in base is rounded down
+        Side::Bid => {
+            (traded as u128 * price.base() as u128).checked_div(price.quote() as u128)? as u64
+        }
+    };
+    (size > 0).then_some(Trade {
+        pair,
+        note_id: order.note_id,
+        maker: order.maker,
+        side,
+        price,
+        size,
+    })
+}
@@ -0,0 +1,433 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+use crate::backend::ClobBackend;
+use crate::clob::{parse_note_id, Clob};
+use crate::depth::{DepthUpdate, LevelChange};
+use crate::errors::ClobError;
+use crate::feed::{FeedEvent, MarketFeed};
+use crate::market::{Pair, Price, Side};
+use crate::order::{Fill, LimitOrder, NewOrder, OrderOptions, Remainder};
+use crate::quote::Quote;
+use crate::receipt::TransactionReceipt;
+use crate::server::{run_feed, AppState, SharedClob, FEED_INTERVAL};
+
+use self::proto::clob_server::{Clob as ClobRpc, ClobServer};
+
+/// Messages and service generated from `proto/clob.proto`
+pub mod proto {
+    tonic::include_proto!("clob");
+}
+
+/// Address the gRPC service listens on by default
+pub const DEFAULT_GRPC_ADDR: &str = "127.0.0.1:50051";
+
+/// Updates buffered per book stream before it waits for the subscriber
+const BOOK_STREAM_CAPACITY: usize = 16;
+
+/// What the gRPC service needs from the CLOB.
+///
+/// Implemented by [SharedClob] over any backend.
+#[tonic::async_trait]
+pub trait ClobApi: Send + Sync + 'static {
+    async fn place_order(
+        &self,
+        maker: &str,
+        order: &NewOrder,
+    ) -> Result<TransactionReceipt, ClobError>;
+
+    async fn cancel_order(
+        &self,
+        maker: &str,
+        note_id: &NoteId,
+    ) -> Result<TransactionReceipt, ClobError>;
+
+    /// Quotes buying `want` with assets of `give`
+    async fn quote(&self, want: FungibleAsset, give: AccountId) -> Result<Quote, ClobError>;
+
+    /// Takes the best order offering assets of `want` for at most `give`
+    async fn take(
+        &self,
+        taker: &str,
+        want: AccountId,
+        give: FungibleAsset,
+    ) -> Result<(Fill, TransactionReceipt), ClobError>;
+
+    /// Returns the resting order of note `note_id`
+    async fn order(&self, note_id: &NoteId) -> Result<LimitOrder, ClobError>;
+}
+
+#[tonic::async_trait]
+impl<B: ClobBackend + 'static> ClobApi for SharedClob<B> {
+    async fn place_order(
+        &self,
+        maker: &str,
+        order: &NewOrder,
+    ) -> Result<TransactionReceipt, ClobError> {
+        self.lock().await.place_order(maker, order).await
+    }
+
+    async fn cancel_order(
+        &self,
+        maker: &str,
+        note_id: &NoteId,
+    ) -> Result<TransactionReceipt, ClobError> {
+        self.lock().await.cancel_order(maker, note_id).await
+    }
+
+    async fn quote(&self, want: FungibleAsset, give: AccountId) -> Result<Quote, ClobError> {
+        self.lock().await.quote(want, give).await
+    }
+
+    async fn take(
+        &self,
+        taker: &str,
+        want: AccountId,
+        give: FungibleAsset,
+    ) -> Result<(Fill, TransactionReceipt), ClobError> {
+        self.lock().await.take(taker, want, give).await
+    }
+
+    async fn order(&self, note_id: &NoteId) -> Result<LimitOrder, ClobError> {
+        let mut clob = self.lock().await;
+        clob.sync().await?;
+        clob.order(note_id).cloned()
+    }
+}
+
+/// Serves the gRPC service on `addr` until the server fails, streaming books
+/// from a background sync loop
+pub async fn serve_grpc<B: ClobBackend + 'static>(
+    addr: SocketAddr,
+    clob: Clob<B>,
+) -> Result<(), ClobError> {
+    let state = AppState::new(clob)?;
+    tokio::spawn(run_feed(state.clone(), FEED_INTERVAL));
+    let service = ClobService::new(state.clob.clone(), &state);
+
+    info!(%addr, "Serving gRPC API");
+    tonic::transport::Server::builder()
+        .add_service(service.into_server())
+        .serve(addr)
+        .await
+        .map_err(|err| ClobError::Io(std::io::Error::other(err)))
+}
+
+/// The `clob.Clob` gRPC service, over any [ClobApi].
+///
+/// Books are streamed from the feed of an [AppState], which [run_feed] must
+/// keep up to date.
+pub struct ClobService<A> {
+    api: Arc<A>,
+    market: Arc<RwLock<MarketFeed>>,
+    feed: broadcast::Sender<FeedEvent>,
+}
+
+impl<A: ClobApi> ClobService<A> {
+    pub fn new<B: ClobBackend>(api: A, state: &AppState<B>) -> Self {
+        Self {
+            api: Arc::new(api),
+            market: state.market.clone(),
+            feed: state.feed.clone(),
+        }
+    }
+
+    pub fn into_server(self) -> ClobServer<Self> {
+        ClobServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl<A: ClobApi> ClobRpc for ClobService<A> {
+    type StreamBookStream = ReceiverStream<Result<proto::BookUpdate, Status>>;
+
+    async fn place_order(
+        &self,
+        request: Request<proto::PlaceOrderRequest>,
+    ) -> Result<Response<proto::TransactionReceipt>, Status> {
+        let request = request.into_inner();
+        let options = OrderOptions {
+            min_remaining: request.min_remaining,
+            min_fill: request.min_fill,
+        };
+        let order = NewOrder::new(
+            parse_asset(request.offered, "offered")?,
+            parse_asset(request.requested, "requested")?,
+        )
+        .with_options(options);
+        let order = if request.post_only {
+            order.post_only()
+        } else {
+            order
+        };
+
+        let receipt = self.api.place_order(&request.maker, &order).await?;
+        Ok(Response::new((&receipt).into()))
+    }
+
+    async fn cancel_order(
+        &self,
+        request: Request<proto::CancelOrderRequest>,
+    ) -> Result<Response<proto::TransactionReceipt>, Status> {
+        let request = request.into_inner();
+        let note_id = parse_note_id(&request.note_id)?;
+        let receipt = self.api.cancel_order(&request.maker, &note_id).await?;
+        Ok(Response::new((&receipt).into()))
+    }
+
+    async fn quote(
+        &self,
+        request: Request<proto::QuoteRequest>,
+    ) -> Result<Response<proto::QuoteResponse>, Status> {
+        let request = request.into_inner();
+        let want = parse_asset(request.want, "want")?;
+        let give = parse_account_id(&request.give_faucet_id, "give_faucet_id")?;
+        let quote = self.api.quote(want, give).await?;
+        Ok(Response::new((&quote).into()))
+    }
+
+    async fn take(
+        &self,
+        request: Request<proto::TakeRequest>,
+    ) -> Result<Response<proto::TakeResponse>, Status> {
+        let request = request.into_inner();
+        let give = parse_asset(request.give, "give")?;
+        let want = parse_account_id(&request.want_faucet_id, "want_faucet_id")?;
+        let (fill, receipt) = self.api.take(&request.taker, want, give).await?;
+        Ok(Response::new(proto::TakeResponse {
+            fill: Some((&fill).into()),
+            receipt: Some((&receipt).into()),
+        }))
+    }
+
+    async fn get_order(
+        &self,
+        request: Request<proto::GetOrderRequest>,
+    ) -> Result<Response<proto::Order>, Status> {
+        let note_id = parse_note_id(&request.into_inner().note_id)?;
+        let order = self.api.order(&note_id).await?;
+        Ok(Response::new((&order).into()))
+    }
+
+    async fn stream_book(
+        &self,
+        request: Request<proto::StreamBookRequest>,
+    ) -> Result<Response<Self::StreamBookStream>, Status> {
+        let pair = request.into_inner().pair.ok_or_else(|| missing("pair"))?;
+        let pair = Pair::new(
+            parse_account_id(&pair.base, "pair.base")?,
+            parse_account_id(&pair.quote, "pair.quote")?,
+        );
+
+        // Subscribing under the read lock, the feed cannot move past the
+        // snapshot before the subscription starts
+        let (snapshot, events) = {
+            let market = self.market.read().expect("market feed lock poisoned");
+            let (sequence, depth) = market
+                .snapshot(pair, usize::MAX)
+                .ok_or_else(|| ClobError::NotFound(format!("market {pair}")))?;
+            (DepthUpdate::snapshot(&depth, sequence), self.feed.subscribe())
+        };
+
+        let (sender, receiver) = mpsc::channel(BOOK_STREAM_CAPACITY);
+        tokio::spawn(stream_book(snapshot, events, sender));
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Sends `snapshot`, then the depth updates of its pair following it, until
+/// the subscriber goes away or falls behind the feed
+async fn stream_book(
+    snapshot: DepthUpdate,
+    mut events: broadcast::Receiver<FeedEvent>,
+    sender: mpsc::Sender<Result<proto::BookUpdate, Status>>,
+) {
+    let pair = snapshot.pair;
+    let mut sequence = snapshot.sequence;
+    if sender.send(Ok((&snapshot).into())).await.is_err() {
+        return;
+    }
+
+    loop {
+        let update = match events.recv().await {
+            Ok(FeedEvent::Depth(update)) if update.pair == pair && update.sequence > sequence => {
+                update
+            }
+            Ok(_) => continue,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(skipped, "Book stream subscriber lagging");
+                let status = Status::data_loss(format!("missed {skipped} feed events"));
+                let _ = sender.send(Err(status)).await;
+                return;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        sequence = update.sequence;
+        if sender.send(Ok((&update).into())).await.is_err() {
+            return;
+        }
+    }
+}
+
+impl From<ClobError> for Status {
+    fn from(err: ClobError) -> Self {
+        let message = err.to_string();
+        match err {
+            ClobError::InvalidOrder(_) | ClobError::Token(_) | ClobError::WouldCross(_) => {
+                Status::invalid_argument(message)
+            }
+            ClobError::NotFound(_) => Status::not_found(message),
+            ClobError::NoteAlreadyConsumed(_) | ClobError::RetriesExhausted { .. } => {
+                Status::aborted(message)
+            }
+            ClobError::NoLiquidity | ClobError::InsufficientFunds(_) => {
+                Status::failed_precondition(message)
+            }
+            ClobError::CommitTimeout(..) => Status::deadline_exceeded(message),
+            _ => Status::internal(message),
+        }
+    }
+}
+
+impl From<FungibleAsset> for proto::Asset {
+    fn from(asset: FungibleAsset) -> Self {
+        Self {
+            faucet_id: asset.faucet_id().to_hex(),
+            amount: asset.amount(),
+        }
+    }
+}
+
+impl From<Pair> for proto::Pair {
+    fn from(pair: Pair) -> Self {
+        Self {
+            base: pair.base.to_hex(),
+            quote: pair.quote.to_hex(),
+        }
+    }
+}
+
+impl From<Price> for proto::Price {
+    fn from(price: Price) -> Self {
+        Self {
+            quote: price.quote(),
+            base: price.base(),
+        }
+    }
+}
+
+impl From<Side> for proto::Side {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Bid => proto::Side::Bid,
+            Side::Ask => proto::Side::Ask,
+        }
+    }
+}
+
+impl From<&TransactionReceipt> for proto::TransactionReceipt {
+    fn from(receipt: &TransactionReceipt) -> Self {
+        Self {
+            transaction_id: receipt.transaction_id.to_hex(),
+            block_num: receipt.block_num,
+            consumed_notes: receipt.consumed_notes.iter().map(|id| id.to_hex()).collect(),
+            orders: receipt.clone_notes.iter().map(|note| note.id().to_hex()).collect(),
+            payback_notes: receipt.payback_notes.iter().map(|note| note.id().to_hex()).collect(),
+        }
+    }
+}
+
+impl From<&Fill> for proto::Fill {
+    fn from(fill: &Fill) -> Self {
+        let (remaining, returned) = match fill.remainder {
+            Remainder::None => (0, 0),
+            Remainder::Clone(amount) => (amount, 0),
+            Remainder::Returned(amount) => (0, amount),
+        };
+        Self {
+            note_id: fill.note_id.to_hex(),
+            in_amount: fill.in_amount,
+            out_amount: fill.out_amount,
+            remaining,
+            returned,
+        }
+    }
+}
+
+impl From<&Quote> for proto::QuoteResponse {
+    fn from(quote: &Quote) -> Self {
+        Self {
+            paid: Some(quote.paid.into()),
+            received: Some(quote.received.into()),
+            average_price: Some(quote.average_price().into()),
+            worst_price: Some(quote.worst_price().into()),
+            fills: quote.fills.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&LimitOrder> for proto::Order {
+    fn from(order: &LimitOrder) -> Self {
+        Self {
+            note_id: order.note_id.to_hex(),
+            maker: order.maker.to_hex(),
+            offered: Some(order.offered.into()),
+            requested: Some(order.requested.into()),
+            min_remaining: order.min_remaining,
+            min_fill: order.min_fill,
+        }
+    }
+}
+
+impl From<&DepthUpdate> for proto::BookUpdate {
+    fn from(update: &DepthUpdate) -> Self {
+        let levels = update
+            .diffs
+            .iter()
+            .map(|diff| {
+                let (price, size, orders) = match diff.change {
+                    LevelChange::Added(level) | LevelChange::Changed(level) => {
+                        (level.price, level.size, level.orders as u64)
+                    }
+                    LevelChange::Removed(price) => (price, 0, 0),
+                };
+                proto::Level {
+                    side: proto::Side::from(diff.side).into(),
+                    price: Some(price.into()),
+                    size,
+                    orders,
+                }
+            })
+            .collect();
+        Self {
+            pair: Some(update.pair.into()),
+            sequence: update.sequence,
+            levels,
+        }
+    }
+}
+
+fn missing(field: &str) -> Status {
+    Status::invalid_argument(format!("missing {field}"))
+}
+
+fn parse_account_id(hex: &str, field: &str) -> Result<AccountId, Status> {
+    AccountId::from_hex(hex)
+        .map_err(|err| Status::invalid_argument(format!("invalid {field} {hex}: {err}")))
+}
+
+fn parse_asset(asset: Option<proto::Asset>, field: &str) -> Result<FungibleAsset, Status> {
+    let asset = asset.ok_or_else(|| missing(field))?;
+    FungibleAsset::new(parse_account_id(&asset.faucet_id, field)?, asset.amount)
+        .map_err(|err| Status::invalid_argument(format!("invalid {field}: {err}")))
+}
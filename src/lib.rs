@@ -1,32 +1,68 @@
-use tracing::{debug, info, instrument};
-use vm_processor::{crypto::RpoRandomCoin, Digest, Felt, Word, ZERO};
-
-use std::collections::BTreeMap;
+pub mod backend;
+pub mod book;
+pub mod clob;
+pub mod config;
+pub mod depth;
+pub mod errors;
+pub mod feed;
+pub mod grpc;
+pub mod maker;
+pub mod market;
+pub mod matcher;
+pub mod model;
+pub mod order;
+pub mod profiles;
+pub mod quote;
+pub mod receipt;
+pub mod registry;
+pub mod router;
+pub mod scenario;
+pub mod server;
+pub mod taker;
+pub mod tokens;
+
+use tracing::{debug, info, instrument, warn};
+use vm_processor::{crypto::RpoRandomCoin, Digest, Felt, Word};
+
+use std::collections::{BTreeMap, VecDeque};
 use std::path::PathBuf;
+use std::time::Duration;
 use std::vec;
 
-use miden_client::client::accounts::AccountTemplate;
+use miden_client::client::accounts::AccountStorageMode;
 use miden_client::client::rpc::TonicRpcClient;
 use miden_client::client::transactions::transaction_request::{
-    NoteArgs, TransactionRequest, TransactionTemplate, AUTH_SEND_ASSET_SCRIPT,
+    NoteArgs, TransactionRequest, AUTH_CONSUME_NOTES_SCRIPT, AUTH_SEND_ASSET_SCRIPT,
 };
-use miden_client::client::{self, get_random_coin, Client};
+use miden_client::client::{get_random_coin, Client};
 use miden_client::config::{ClientConfig, RpcConfig};
+use miden_client::errors::ClientError;
 use miden_client::store::sqlite_store::SqliteStore;
-use miden_client::store::TransactionFilter;
 
+use miden_lib::notes::create_p2id_note;
 use miden_lib::transaction::TransactionKernel;
 use miden_mock::utils::prepare_word;
 use miden_objects::accounts::{Account, AccountId};
 use miden_objects::assembly::ProgramAst;
-use miden_objects::assets::{Asset, FungibleAsset, TokenSymbol};
+use miden_objects::assets::{Asset, FungibleAsset};
 use miden_objects::crypto::rand::FeltRng;
 use miden_objects::notes::{
     Note, NoteAssets, NoteId, NoteInputs, NoteMetadata, NoteRecipient, NoteScript, NoteTag,
+    NoteType,
 };
-use miden_objects::transaction::TransactionId;
 
+use miden_objects::transaction::TransactionScript;
 use miden_objects::{Hasher, NoteError};
+
+use crate::backend::{execute_batch_on, execute_on, BatchReport, ClobBackend, NodeBackend};
+use crate::book::OrderBook;
+use crate::errors::ClobError;
+use crate::market::Market;
+use crate::order::{encode_limit_swap_inputs, Fill, LimitOrder, NewOrder, OrderOptions};
+use crate::profiles::AccountProfiles;
+use crate::receipt::TransactionReceipt;
+use crate::registry::OrderRegistry;
+
 pub type MidenClient = Client<TonicRpcClient, RpoRandomCoin, SqliteStore>;
 
 /// Creates the partial_RECIPIENT for generating note clones
@@ -42,6 +78,16 @@ pub fn build_partial_recipient(
     Ok(Hasher::merge(&[serial_num_hash, script_hash]))
 }
 
+/// Compiles the limit swap note script in masm/limit_swap.masm
+pub fn limit_swap_note_script() -> NoteScript {
+    let assembler = TransactionKernel::assembler();
+
+    let note_script = include_str!("./masm/limit_swap.masm");
+    let note_script = ProgramAst::parse(note_script).unwrap();
+    let (note_script, _) = NoteScript::new(note_script, &assembler).unwrap();
+    note_script
+}
+
 /// Creates a limit swap note for the maker account
 /// offering a certain amount of an asset in exchange for another asset
 /// The note code is in the masm/limit_swap.masm
@@ -49,39 +95,41 @@ pub fn create_limit_swap_note<R: FeltRng>(
     maker: AccountId,
     offered_asset: Asset,
     requested_asset: Asset,
-    mut rng: R,
+    rng: R,
 ) -> Result<Note, NoteError> {
-    let assembler = TransactionKernel::assembler();
+    create_limit_swap_note_with_options(
+        maker,
+        offered_asset,
+        requested_asset,
+        &OrderOptions::default(),
+        rng,
+    )
+}
 
-    let note_script = include_str!("./masm/limit_swap.masm");
-    let note_script = ProgramAst::parse(note_script).unwrap();
-    let (note_script, _) = NoteScript::new(note_script, &assembler).unwrap();
+/// Creates a limit swap note like [create_limit_swap_note], with the
+/// maker-chosen `options` encoded in the note inputs
+pub fn create_limit_swap_note_with_options<R: FeltRng>(
+    maker: AccountId,
+    offered_asset: Asset,
+    requested_asset: Asset,
+    options: &OrderOptions,
+    mut rng: R,
+) -> Result<Note, NoteError> {
+    let note_script = limit_swap_note_script();
 
     let payback_serial_num = rng.draw_word();
     let note_serial_num = rng.draw_word();
 
     let p2id_recipient = miden_lib::notes::utils::build_p2id_recipient(maker, payback_serial_num)?;
     let partial_recipient = build_partial_recipient(note_script.clone(), note_serial_num)?;
-    let requested_asset_word: Word = requested_asset.into();
-
-    let inputs = [
-        p2id_recipient[0],
-        p2id_recipient[1],
-        p2id_recipient[2],
-        p2id_recipient[3],
-        requested_asset_word[0],
-        requested_asset_word[1],
-        requested_asset_word[2],
-        requested_asset_word[3],
-        maker.into(),
-        ZERO,
-        ZERO,
-        ZERO,
-        partial_recipient[0],
-        partial_recipient[1],
-        partial_recipient[2],
-        partial_recipient[3],
-    ];
+
+    let inputs = encode_limit_swap_inputs(
+        p2id_recipient,
+        requested_asset,
+        maker,
+        options,
+        partial_recipient,
+    );
 
     let note_assets = NoteAssets::new(vec![offered_asset]).unwrap();
     let note_recipient = NoteRecipient::new(
@@ -102,202 +150,295 @@ pub fn create_limit_swap_note<R: FeltRng>(
     Ok(note)
 }
 
-/// Execute a transaction and wait for it to be committed by the node
-#[instrument(skip_all)]
-pub async fn execute(tx_request: TransactionRequest) -> (TransactionId, Vec<Note>) {
-    let mut client = create_client();
-    client.sync_state().await.unwrap();
-    let transaction_execution_result = client.new_transaction(tx_request).unwrap();
-    let transaction_id = transaction_execution_result.executed_transaction().id();
-    let created_notes = transaction_execution_result.created_notes().to_vec();
+/// Maximum time to wait for a submitted transaction to be committed
+pub const COMMIT_TIMEOUT: Duration = Duration::from_secs(180);
 
-    info!(transaction_id = transaction_id.to_hex(), "Sending transaction to node");
-    client.submit_transaction(transaction_execution_result).await.unwrap();
-
-    loop {
-        debug!(cur_block = client.get_sync_height().unwrap(), "Syncing state...");
-        client.sync_state().await.unwrap();
+/// Delay between two checks of whether a submitted transaction was committed
+pub const COMMIT_POLL_INTERVAL: Duration = Duration::from_secs(3);
 
-        // Check if executed transaction got committed by the node
-        let uncommited_transactions =
-            client.get_transactions(TransactionFilter::Uncomitted).unwrap();
-        let is_tx_committed =
-            !uncommited_transactions.iter().any(|uncommited_tx| uncommited_tx.id == transaction_id);
-
-        if is_tx_committed {
-            break;
-        }
-
-        std::thread::sleep(std::time::Duration::new(3, 0));
-    }
-    (transaction_id, created_notes)
-}
-
-/// Create a miden client
-pub fn create_client() -> MidenClient {
+/// Configuration of the local client store and the node it talks to
+pub(crate) fn client_config() -> ClientConfig {
     let mut file = PathBuf::from("./db");
     file.push(format!("{}.sqlite3", "miden-db"));
-    let client_config = ClientConfig {
+    ClientConfig {
         store: file.into_os_string().into_string().unwrap().try_into().unwrap(),
         rpc: RpcConfig::default(),
-    };
+    }
+}
 
+/// Create a miden client
+pub fn create_client() -> Result<MidenClient, ClobError> {
+    let client_config = client_config();
     let rpc_endpoint = client_config.rpc.endpoint.to_string();
-    let store = SqliteStore::new((&client_config).into()).unwrap();
-    let executor_store = SqliteStore::new((&client_config).into()).unwrap();
+    let store = SqliteStore::new((&client_config).into()).map_err(ClientError::from)?;
+    let executor_store = SqliteStore::new((&client_config).into()).map_err(ClientError::from)?;
     let rng = get_random_coin();
 
-    Client::new(TonicRpcClient::new(&rpc_endpoint), rng, store, executor_store).unwrap()
+    Ok(Client::new(TonicRpcClient::new(&rpc_endpoint), rng, store, executor_store)?)
 }
 
-/// Create accounts for the maker, taker, eth, dai
-pub fn get_accounts() -> (Account, Account, Account, Account) {
-    let mut client = create_client();
-    let (maker, _) = client
-        .new_account(AccountTemplate::BasicWallet {
-            mutable_code: false,
-            storage_mode: client::accounts::AccountStorageMode::Local,
-        })
-        .unwrap();
-    let (taker, _) = client
-        .new_account(AccountTemplate::BasicWallet {
-            mutable_code: false,
-            storage_mode: client::accounts::AccountStorageMode::Local,
-        })
-        .unwrap();
-
-    let (eth_fauc, _) = client
-        .new_account(AccountTemplate::FungibleFaucet {
-            token_symbol: TokenSymbol::new("ETH").unwrap(),
-            decimals: 8,
-            max_supply: 100_000_000_000,
-            storage_mode: client::accounts::AccountStorageMode::Local,
-        })
-        .unwrap();
-    let (dai_fauc, _) = client
-        .new_account(AccountTemplate::FungibleFaucet {
-            token_symbol: TokenSymbol::new("DAI").unwrap(),
-            decimals: 8,
-            max_supply: 100_000_000_000,
-            storage_mode: client::accounts::AccountStorageMode::Local,
-        })
-        .unwrap();
-
-    (maker, taker, eth_fauc, dai_fauc)
+/// Returns the wallet with the given profile alias, creating it on first use
+/// with `storage_mode`
+pub fn get_or_create_account(
+    alias: &str,
+    storage_mode: AccountStorageMode,
+) -> Result<Account, ClobError> {
+    let mut backend = NodeBackend::new()?;
+    let mut profiles = AccountProfiles::load_default()?.with_storage_mode(storage_mode);
+    profiles.get_or_create_account(&mut backend, alias)
 }
 
-/// Builds a transaction request to consume a
-/// limit order note
-#[instrument(skip_all, fields(note_id, taker=taker.id().to_hex(), out_asset_amount, in_asset_amount))]
-pub fn build_consume_order_tx_req(
-    note_id: NoteId,
-    taker: Account,
-    out_asset_amount: u64,
-    in_asset_amount: u64,
-    in_asset: Asset,
-) -> TransactionRequest {
-    let client = create_client();
-    let note = client.get_input_note(note_id).unwrap();
-    let mut note_tree: BTreeMap<NoteId, Option<NoteArgs>> = BTreeMap::new();
-    let taker_args: Word = [
-        ZERO,
-        ZERO,
-        Felt::new(in_asset_amount),
-        Felt::new(out_asset_amount),
-    ];
-    note_tree.insert(note_id, Some(taker_args));
+/// Returns the faucet of the token `symbol`, creating it on first use with
+/// `storage_mode`
+///
+/// Fails if the token already has a faucet with other decimals or another max
+/// supply.
+pub fn get_or_create_faucet(
+    symbol: &str,
+    decimals: u8,
+    max_supply: u64,
+    storage_mode: AccountStorageMode,
+) -> Result<Account, ClobError> {
+    let mut backend = NodeBackend::new()?;
+    let mut profiles = AccountProfiles::load_default()?.with_storage_mode(storage_mode);
+    profiles.get_or_create_faucet(&mut backend, symbol, decimals, max_supply)
+}
 
-    // build recipient
-    let recipient =
-        note.recipient().iter().map(|x| x.as_int().to_string()).collect::<Vec<_>>().join(".");
-    let note_tag = note.metadata().unwrap().tag();
+/// Get the accounts for the maker, taker, eth, dai
+/// Accounts are kept as profiles and reused across runs
+pub fn get_accounts() -> Result<(Account, Account, Account, Account), ClobError> {
+    let mut backend = NodeBackend::new()?;
+    let mut profiles = AccountProfiles::load_default()?;
 
-    let tx_ast = ProgramAst::parse(
-        &AUTH_SEND_ASSET_SCRIPT
-            .replace("{recipient}", &recipient)
-            .replace(
-                "{note_type}",
-                &Felt::new(note.metadata().unwrap().note_type() as u64).to_string(),
-            )
-            .replace("{tag}", &Felt::new(note_tag.into()).to_string())
-            .replace("{asset}", &prepare_word(&in_asset.into()).to_string())
-            .to_string(),
-    )
-    .unwrap();
-    let auth = client.get_account_auth(taker.id()).unwrap();
-    let script_inputs = vec![auth.into_advice_inputs()];
-    let tx_script = client.compile_tx_script(tx_ast, script_inputs, vec![]).unwrap();
+    let maker = profiles.get_or_create_account(&mut backend, "maker")?;
+    let taker = profiles.get_or_create_account(&mut backend, "taker")?;
 
-    TransactionRequest::new(taker.id(), note_tree, vec![], Some(tx_script))
+    let eth_fauc = profiles.get_or_create_faucet(&mut backend, "ETH", 8, 100_000_000_000)?;
+    let dai_fauc = profiles.get_or_create_faucet(&mut backend, "DAI", 8, 100_000_000_000)?;
+
+    Ok((maker, taker, eth_fauc, dai_fauc))
 }
 
 /// mints an asset to an account in a transaction.
 /// The output note is consumed in another transaction
 /// and is submitted to the node.
+/// Reports the receipt of the mint followed by the receipt of the consumption,
+/// see [mint_batch].
 #[instrument(skip_all, fields(acc = account.id().to_hex()))]
-pub async fn mint(account: &Account, faucet: FungibleAsset) {
-    let mut client = create_client();
-    let tx_template = TransactionTemplate::MintFungibleAsset(
-        faucet,
-        account.id(),
-        miden_objects::notes::NoteType::OffChain,
-    );
+pub async fn mint<B: ClobBackend>(
+    backend: &mut B,
+    account: &Account,
+    faucet: FungibleAsset,
+) -> BatchReport {
+    mint_batch(backend, &[MintRequest::new(account.id(), vec![faucet])]).await
+}
+
+/// Assets to mint to an account with [mint_batch]
+#[derive(Debug, Clone)]
+pub struct MintRequest {
+    pub account: AccountId,
+    pub assets: Vec<FungibleAsset>,
+}
 
-    let tx_request = client.build_transaction_request(tx_template).unwrap();
-    let (_tx_id, consumables) = execute(tx_request).await;
-    for note in consumables {
-        info!(id = note.id().to_hex(), "Consuming note");
-        // consume
-        let tx_template = TransactionTemplate::ConsumeNotes(account.id(), vec![note.id()]);
-        let tx_req = client.build_transaction_request(tx_template).unwrap();
-        let (tx_id, _created_notes) = execute(tx_req).await;
-        info!(id = note.id().to_hex(), "Consumed note");
+impl MintRequest {
+    pub fn new(account: AccountId, assets: Vec<FungibleAsset>) -> Self {
+        Self { account, assets }
     }
 }
 
-/// Build miden transaction to create a limit order and submit it to the network.
-/// The transaction creates an output note that can be consumed by a taker to
-/// execute the swap fully or partially.
-#[instrument(skip_all,fields(maker_id = maker.id().to_hex(), from_asset = ?from_asset, to_asset = ?to_asset))]
-pub async fn create_and_submit_limit_order(
-    client: &MidenClient,
-    maker: &Account,
-    from_asset: Asset,
-    to_asset: Asset,
-) -> (TransactionId, Vec<Note>) {
-    let felt_rng = get_random_coin();
-    let limit_swap_note =
-        create_limit_swap_note(maker.id(), from_asset, to_asset, felt_rng).unwrap();
-    let note_tag = limit_swap_note.metadata().tag().inner();
+/// Mints several assets to several accounts and consumes the minted notes.
+///
+/// Each mint updates the state of its faucet, so mints run in rounds of at
+/// most one mint per faucet, the mints of a round being proven concurrently.
+/// Every account then consumes all of its minted notes in a single
+/// transaction, all accounts at once.
+/// Reports the receipts of the mints followed by the receipts of the
+/// consumptions. A failure stops the batch: the report then holds the receipts
+/// of the transactions committed so far along with the error, and the notes
+/// minted so far are left for their accounts to consume.
+#[instrument(skip_all, fields(requests = requests.len()))]
+pub async fn mint_batch<B: ClobBackend>(backend: &mut B, requests: &[MintRequest]) -> BatchReport {
+    let mut report = BatchReport::default();
+    if let Err(err) = mint_into(backend, requests, &mut report.receipts).await {
+        warn!(%err, committed = report.receipts.len(), "Stopped minting");
+        report.error = Some(err);
+    }
+    report
+}
+
+/// Runs the rounds of [mint_batch], pushing each receipt to `receipts` as soon
+/// as its transaction is committed
+async fn mint_into<B: ClobBackend>(
+    backend: &mut B,
+    requests: &[MintRequest],
+    receipts: &mut Vec<TransactionReceipt>,
+) -> Result<(), ClobError> {
+    let mut mints_per_faucet: BTreeMap<AccountId, VecDeque<(AccountId, FungibleAsset)>> =
+        BTreeMap::new();
+    for request in requests {
+        for asset in &request.assets {
+            mints_per_faucet
+                .entry(asset.faucet_id())
+                .or_default()
+                .push_back((request.account, *asset));
+        }
+    }
+
+    let mut notes_per_account: BTreeMap<AccountId, Vec<NoteId>> = BTreeMap::new();
+    loop {
+        let round =
+            mints_per_faucet.values_mut().filter_map(|mints| mints.pop_front()).collect::<Vec<_>>();
+        if round.is_empty() {
+            break;
+        }
 
-    debug!(name: "create_and_submit_limit_order",  tag=note_tag);
+        let mut tx_requests = Vec::with_capacity(round.len());
+        for (account_id, asset) in &round {
+            info!(to = account_id.to_hex(), ?asset, "Minting");
+            tx_requests.push(build_mint_tx_req(backend, *asset, *account_id)?);
+        }
+        let batch = execute_batch_on(backend, tx_requests).await;
+        for ((account_id, _), receipt) in round.into_iter().zip(batch.receipts) {
+            notes_per_account
+                .entry(account_id)
+                .or_default()
+                .extend(receipt.created_notes().map(|note| note.id()));
+            receipts.push(receipt);
+        }
+        if let Some(err) = batch.error {
+            return Err(err);
+        }
+    }
+
+    let mut tx_requests = Vec::with_capacity(notes_per_account.len());
+    for (account_id, note_ids) in notes_per_account {
+        info!(acc = account_id.to_hex(), notes = note_ids.len(), "Consuming notes");
+        let notes = note_ids.into_iter().map(|note_id| (note_id, None)).collect();
+        tx_requests.push(build_consume_notes_tx_req(backend, account_id, notes)?);
+    }
+    let batch = execute_batch_on(backend, tx_requests).await;
+    receipts.extend(batch.receipts);
+    match batch.error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Builds a transaction in which the faucet of `asset` mints it into a P2ID
+/// note to `target`
+pub fn build_mint_tx_req<B: ClobBackend>(
+    backend: &B,
+    asset: FungibleAsset,
+    target: AccountId,
+) -> Result<TransactionRequest, ClobError> {
+    let faucet_id = asset.faucet_id();
+    let note = create_p2id_note(
+        faucet_id,
+        target,
+        vec![asset.into()],
+        NoteType::OffChain,
+        get_random_coin(),
+    )?;
+
+    let recipient = note
+        .recipient_digest()
+        .iter()
+        .map(|x| x.as_int().to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+    let tx_ast = ProgramAst::parse(
+        &include_str!("./masm/distribute_fungible_asset.masm")
+            .replace("{recipient}", &recipient)
+            .replace("{note_type}", &Felt::new(note.metadata().note_type() as u64).to_string())
+            .replace("{tag}", &Felt::new(note.metadata().tag().inner().into()).to_string())
+            .replace("{amount}", &Felt::new(asset.amount()).to_string()),
+    )
+    .unwrap();
+    let tx_script = backend.compile_tx_script(faucet_id, tx_ast)?;
+
+    Ok(TransactionRequest::new(faucet_id, BTreeMap::new(), vec![note], Some(tx_script)))
+}
+
+/// Builds a transaction in which `account_id` consumes `notes` with their note
+/// args, without sending any asset out of its vault
+pub fn build_consume_notes_tx_req<B: ClobBackend>(
+    backend: &B,
+    account_id: AccountId,
+    notes: BTreeMap<NoteId, Option<NoteArgs>>,
+) -> Result<TransactionRequest, ClobError> {
+    let tx_ast = ProgramAst::parse(AUTH_CONSUME_NOTES_SCRIPT).unwrap();
+    let tx_script = backend.compile_tx_script(account_id, tx_ast)?;
+
+    Ok(TransactionRequest::new(account_id, notes, vec![], Some(tx_script)))
+}
+
+/// Builds a transaction in which `account_id` takes liquidity from one or
+/// more limit orders at once.
+///
+/// This is how every taker pays: the limit-swap script of each order takes
+/// the payment of its fill from the vault of the consuming account and puts
+/// it in the payback note to the maker. The kernel consumes the notes in
+/// [NoteId] order, so the payment of each fill must already be in the vault
+/// when its note is consumed.
+pub fn build_fill_tx_req<B: ClobBackend>(
+    backend: &B,
+    account_id: AccountId,
+    fills: &[Fill],
+) -> Result<TransactionRequest, ClobError> {
+    let notes = fills.iter().map(|fill| (fill.note_id, Some(fill.note_args()))).collect();
+    build_consume_notes_tx_req(backend, account_id, notes)
+}
+
+/// Compiles a transaction script that sends `note` with `asset` taken from
+/// the vault of `account_id`
+pub fn build_send_note_tx_script<B: ClobBackend>(
+    backend: &B,
+    account_id: AccountId,
+    note: &Note,
+    asset: Asset,
+) -> Result<TransactionScript, ClobError> {
+    let note_tag = note.metadata().tag().inner();
+
+    debug!(name: "build_send_note_tx_script",  tag=note_tag);
 
     // build recipient
-    let recipient = limit_swap_note
+    let recipient = note
         .recipient_digest()
         .iter()
         .map(|x| x.as_int().to_string())
         .collect::<Vec<_>>()
         .join(".");
-    debug!(name: "create_and_submit_limit_order",  recipient=recipient);
+    debug!(name: "build_send_note_tx_script",  recipient=recipient);
 
     let tx_ast = ProgramAst::parse(
         &AUTH_SEND_ASSET_SCRIPT
             .replace("{recipient}", &recipient)
-            .replace(
-                "{note_type}",
-                &Felt::new(limit_swap_note.metadata().note_type() as u64).to_string(),
-            )
+            .replace("{note_type}", &Felt::new(note.metadata().note_type() as u64).to_string())
             .replace("{tag}", &Felt::new(note_tag.into()).to_string())
-            .replace("{asset}", &prepare_word(&from_asset.into()).to_string())
+            .replace("{asset}", &prepare_word(&asset.into()).to_string())
             .to_string(),
     )
     .unwrap();
 
-    let auth = client.get_account_auth(maker.id()).unwrap();
-    let script_inputs = vec![auth.into_advice_inputs()];
+    backend.compile_tx_script(account_id, tx_ast)
+}
+
+/// Build miden transaction to create a limit order and submit it to the network.
+/// The transaction creates an output note that can be consumed by a taker to
+/// execute the swap fully or partially.
+#[instrument(skip_all,fields(maker_id = maker.id().to_hex(), from_asset = ?from_asset, to_asset = ?to_asset))]
+pub async fn create_and_submit_limit_order<B: ClobBackend>(
+    backend: &mut B,
+    maker: &Account,
+    from_asset: Asset,
+    to_asset: Asset,
+    options: &OrderOptions,
+) -> Result<TransactionReceipt, ClobError> {
+    let felt_rng = get_random_coin();
+    let limit_swap_note =
+        create_limit_swap_note_with_options(maker.id(), from_asset, to_asset, options, felt_rng)?;
 
-    let tx_script = client.compile_tx_script(tx_ast, script_inputs, vec![]).unwrap();
+    let tx_script = build_send_note_tx_script(backend, maker.id(), &limit_swap_note, from_asset)?;
 
     // build tx req
     let tx_req = TransactionRequest::new(
@@ -307,5 +448,57 @@ pub async fn create_and_submit_limit_order(
         Some(tx_script),
     );
 
-    execute(tx_req).await
+    execute_on(backend, tx_req).await
+}
+
+/// Validates a limit order against the rules of its market, then creates and
+/// submits it with [create_and_submit_limit_order] and records it in `registry`
+///
+/// Post-only orders are checked against `book`, which should be refreshed
+/// beforehand, and rejected with [ClobError::WouldCross] if they would trade.
+#[instrument(skip_all, fields(maker_id = maker.id().to_hex(), post_only = order.post_only))]
+pub async fn place_limit_order<B: ClobBackend>(
+    backend: &mut B,
+    market: &Market,
+    book: &OrderBook,
+    registry: &mut OrderRegistry,
+    maker: &Account,
+    order: &NewOrder,
+) -> Result<TransactionReceipt, ClobError> {
+    check_new_order(market, book, order)?;
+
+    let receipt = create_and_submit_limit_order(
+        backend,
+        maker,
+        order.offered.into(),
+        order.requested.into(),
+        &order.options,
+    )
+    .await?;
+
+    for note in &receipt.clone_notes {
+        registry.record_placed(&LimitOrder::from_note(note)?)?;
+    }
+
+    Ok(receipt)
+}
+
+/// Checks a new order against the rules of `market` and, if it is post-only,
+/// against the resting orders of `book`
+pub fn check_new_order(
+    market: &Market,
+    book: &OrderBook,
+    order: &NewOrder,
+) -> Result<(), ClobError> {
+    market.validate_order(&order.offered, &order.requested)?;
+
+    if order.post_only {
+        let crossing = book.crossing_orders(&order.offered, &order.requested);
+        if !crossing.is_empty() {
+            warn!(crossing = crossing.len(), "Rejecting post-only order");
+            return Err(ClobError::WouldCross(crossing));
+        }
+    }
+
+    Ok(())
 }
@@ -1,70 +1,402 @@
-use keom_clob::{
-    build_consume_order_tx_req, create_and_submit_limit_order, create_client, execute,
-    get_accounts, mint,
-};
-use tracing::{info, span, Level};
+use std::path::PathBuf;
 
+use clap::{Args, Parser, Subcommand};
+use keom_clob::clob::{parse_note_id, Clob, OrderInput};
+use keom_clob::config::{ClobConfig, DEFAULT_CONFIG_PATH};
+use keom_clob::errors::ClobError;
+use keom_clob::receipt::TransactionReceipt;
+use keom_clob::scenario::{run_scenario, ClobDriver, Scenario, ScenarioReport};
+use keom_clob::server::{CancelReportView, DepthView, LevelView, OrderView, ReceiptView, TakeView};
 use miden_objects::assets::FungibleAsset;
-use tracing::event;
+use serde::Serialize;
+use serde_json::json;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+/// Command line interface to the CLOB.
+///
+/// Amounts are decimal with a token symbol, e.g. "1.5 ETH", and accounts are
+/// referred to by their profile alias.
+#[derive(Debug, Parser)]
+#[command(name = "keom", version)]
+struct Cli {
+    /// Print results as JSON, for scripting
+    #[arg(long, global = true)]
+    json: bool,
+    /// Configuration file listing tokens and markets
+    #[arg(long, global = true, default_value = DEFAULT_CONFIG_PATH)]
+    config: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Manage wallets
+    #[command(subcommand)]
+    Account(AccountCommand),
+    /// Manage token faucets
+    #[command(subcommand)]
+    Faucet(FaucetCommand),
+    /// Mint tokens to an account
+    Mint {
+        account: String,
+        /// Amounts to mint, e.g. "1.5 ETH"
+        #[arg(required = true)]
+        amounts: Vec<String>,
+    },
+    /// Manage limit orders
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// Take the best order offering a token
+    Take {
+        taker: String,
+        /// Most the taker pays, e.g. "500 DAI"
+        give: String,
+        /// Symbol of the token the taker receives
+        want: String,
+    },
+    /// Show the depth of a market
+    Book {
+        base: String,
+        quote: String,
+        /// Levels shown per side
+        #[arg(long, default_value_t = 10)]
+        levels: usize,
+    },
+    /// Show the balances of an account
+    Balances { account: String },
+    /// Sync the client with the node
+    Sync,
+    /// Run scenario files and report which pass
+    Scenario {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Run each scenario on a simulated chain of its own instead of
+        /// against the node
+        #[arg(long)]
+        simulated: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AccountCommand {
+    /// Create a wallet, or show the one registered under the alias
+    New { alias: String },
+    /// List the registered wallets
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+enum FaucetCommand {
+    /// Create the faucet of a token, or show the one already registered
+    New {
+        symbol: String,
+        #[arg(long, default_value_t = 8)]
+        decimals: u8,
+        /// Max supply, in base units
+        #[arg(long, default_value_t = 100_000_000_000)]
+        max_supply: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum OrderCommand {
+    /// Place a limit order
+    Place {
+        maker: String,
+        #[command(flatten)]
+        order: OrderArgs,
+    },
+    /// Cancel a resting order
+    Cancel { maker: String, note_id: String },
+    /// Cancel every resting order of a maker
+    CancelAll {
+        maker: String,
+        /// Only cancel the orders of this market, e.g. "ETH/DAI"
+        #[arg(long)]
+        market: Option<String>,
+    },
+    /// Replace a resting order by a new one, at any price and size, atomically
+    Update {
+        maker: String,
+        note_id: String,
+        #[command(flatten)]
+        order: OrderArgs,
+    },
+    /// List the orders placed from this client
+    List {
+        /// Only list the orders of this maker
+        #[arg(long)]
+        maker: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+struct OrderArgs {
+    /// What the maker offers, e.g. "1 ETH"
+    offered: String,
+    /// What the maker wants in exchange, e.g. "2500 DAI"
+    requested: String,
+    /// Refuse to place the order if it would cross the book
+    #[arg(long)]
+    post_only: bool,
+    /// Smallest fill, in the offered token
+    #[arg(long)]
+    min_fill: Option<String>,
+    /// Smallest remainder kept on the book, in the offered token
+    #[arg(long)]
+    min_remaining: Option<String>,
+}
+
+impl From<&OrderArgs> for OrderInput {
+    fn from(args: &OrderArgs) -> Self {
+        Self {
+            offered: args.offered.clone(),
+            requested: args.requested.clone(),
+            post_only: args.post_only,
+            min_fill: args.min_fill.clone(),
+            min_remaining: args.min_remaining.clone(),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::registry()
-        .with(fmt::layer().with_timer(fmt::time::Uptime::default()))
+        .with(fmt::layer().with_writer(std::io::stderr).with_timer(fmt::time::Uptime::default()))
         .with(EnvFilter::from_default_env())
         .init();
-    info!("Creating client...");
-    let mut client = create_client();
-
-    let (maker, taker, eth, dai) = get_accounts();
-    let eth_asset = FungibleAsset::new(eth.id(), 1).unwrap();
-    let dai_asset = FungibleAsset::new(dai.id(), 5).unwrap();
-    info!("Created accounts");
-    info!(
-        maker = maker.id().to_hex(),
-        taker = taker.id().to_hex(),
-        eth = eth.id().to_hex(),
-        dai = dai.id().to_hex(),
-    );
-
-    info!("Minting assets to maker");
-    info!(mintTo = maker.id().to_hex(), asset = "eth", amount = eth_asset.amount());
-    mint(&maker, eth_asset).await;
-
-    info!("Minting assets to taker");
-    info!(mintTo = taker.id().to_hex(), asset = "dai", amount = dai_asset.amount());
-    mint(&taker, dai_asset).await;
-
-    // sync
-    client.sync_state().await.unwrap();
-
-    info!("Creating limit order");
-    // Submit a transaction to create the note
-    let (_tx_id, created_notes) =
-        create_and_submit_limit_order(&client, &maker, eth_asset.into(), dai_asset.into()).await;
-    info!(tx = _tx_id.to_hex(), output_note_len = created_notes.len());
-
-    let created_note = created_notes.first().unwrap();
-
-    // consume it
-    client.sync_state().await.unwrap();
-    info!(note = created_note.id().to_hex(), "Taker consuming note");
-
-    // TODO: consume note from the taker account
-    //       this transaction should fail because the taker does not have the required assets
-    let tx_req =
-        build_consume_order_tx_req(created_note.id(), maker.clone(), 1, 5, dai_asset.into());
-    execute(tx_req).await;
-
-    // display
-    let (maker, _) = client.get_account(maker.id()).unwrap();
-    let (taker, _) = client.get_account(taker.id()).unwrap();
-    info!("Account balances");
-    info!(
-        acc = "maker",
-        eth = maker.vault().get_balance(eth.id()).unwrap(),
-        dai = maker.vault().get_balance(dai.id()).unwrap()
-    );
-    client.sync_state().await.unwrap();
+
+    let cli = Cli::parse();
+    if let Err(err) = run(&cli).await {
+        if cli.json {
+            println!("{}", json!({ "error": err.to_string() }));
+        } else {
+            eprintln!("error: {err}");
+        }
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: &Cli) -> Result<(), ClobError> {
+    let output = Output { json: cli.json };
+    if let Command::Scenario { files, simulated } = &cli.command {
+        return run_scenarios(cli, &output, files, *simulated).await;
+    }
+
+    let config = ClobConfig::load(&cli.config)?;
+    let mut clob = Clob::open(&config)?;
+
+    match &cli.command {
+        Command::Account(AccountCommand::New { alias }) => {
+            let account = clob.new_account(alias)?;
+            output.print(&json!({ "alias": alias, "id": account.id().to_hex() }), || {
+                format!("{alias}: {}", account.id().to_hex())
+            });
+        }
+        Command::Account(AccountCommand::List) => {
+            let accounts = clob
+                .profiles()
+                .accounts()
+                .map(|(alias, id)| json!({ "alias": alias, "id": id.to_hex() }))
+                .collect::<Vec<_>>();
+            output.print(&accounts, || {
+                let lines = clob
+                    .profiles()
+                    .accounts()
+                    .map(|(alias, id)| format!("{alias}: {}", id.to_hex()));
+                lines.collect::<Vec<_>>().join("\n")
+            });
+        }
+        Command::Faucet(FaucetCommand::New {
+            symbol,
+            decimals,
+            max_supply,
+        }) => {
+            let faucet = clob.new_faucet(symbol, *decimals, *max_supply)?;
+            output.print(&json!({ "symbol": symbol, "id": faucet.id().to_hex() }), || {
+                format!("{symbol}: {}", faucet.id().to_hex())
+            });
+        }
+        Command::Mint { account, amounts } => {
+            let assets = amounts
+                .iter()
+                .map(|amount| clob.tokens().parse_amount(amount))
+                .collect::<Result<Vec<FungibleAsset>, _>>()?;
+            let report = clob.mint(account, assets).await?;
+            output.receipts(&report.receipts);
+            if let Some(err) = report.error {
+                return Err(err);
+            }
+        }
+        Command::Order(OrderCommand::Place { maker, order }) => {
+            let order = OrderInput::from(order).to_order(clob.tokens())?;
+            let receipt = clob.place_order(maker, &order).await?;
+            output.receipts(&[receipt]);
+        }
+        Command::Order(OrderCommand::Cancel { maker, note_id }) => {
+            let receipt = clob.cancel_order(maker, &parse_note_id(note_id)?).await?;
+            output.receipts(&[receipt]);
+        }
+        Command::Order(OrderCommand::CancelAll { maker, market }) => {
+            let pair = match market {
+                Some(market) => Some(clob.parse_pair(market)?),
+                None => None,
+            };
+            let report = clob.cancel_all(maker, pair).await?;
+            let view = CancelReportView::from(&report);
+            output.print(&view, || {
+                let mut lines = vec![format!(
+                    "reclaimed {}, already filled {}, failed {}",
+                    view.reclaimed.len(),
+                    view.already_filled.len(),
+                    view.failed.len()
+                )];
+                lines.extend(view.receipts.iter().map(describe_receipt));
+                lines.extend(view.error.iter().map(|err| format!("stopped: {err}")));
+                lines.join("\n")
+            });
+        }
+        Command::Order(OrderCommand::Update {
+            maker,
+            note_id,
+            order,
+        }) => {
+            let order = OrderInput::from(order).to_order(clob.tokens())?;
+            let receipt = clob.replace_order(maker, &parse_note_id(note_id)?, &order).await?;
+            output.receipts(&[receipt]);
+        }
+        Command::Order(OrderCommand::List { maker }) => {
+            let maker = match maker {
+                Some(alias) => Some(clob.account(alias)?.id()),
+                None => None,
+            };
+            let orders = clob
+                .registry()
+                .orders()
+                .filter(|record| match maker {
+                    Some(maker) => record.maker == maker,
+                    None => true,
+                })
+                .map(|record| OrderView::new(record, clob.tokens()))
+                .collect::<Vec<_>>();
+            output.print(&orders, || {
+                let lines = orders.iter().map(|order| {
+                    format!(
+                        "{} {:?}: {} for {}",
+                        order.note_id, order.status, order.offered, order.requested
+                    )
+                });
+                lines.collect::<Vec<_>>().join("\n")
+            });
+        }
+        Command::Take { taker, give, want } => {
+            let give = clob.tokens().parse_amount(give)?;
+            let want_id = clob.tokens().faucet_id(want)?;
+            let (fill, receipt) = clob.take(taker, want_id, give).await?;
+            let view = TakeView::new(clob.tokens(), &fill, give, want_id, &receipt);
+            output.print(&view, || {
+                format!(
+                    "took {}: paid {}, received {}\n{}",
+                    view.note_id,
+                    view.paid,
+                    view.received,
+                    describe_receipt(&view.receipt)
+                )
+            });
+        }
+        Command::Book {
+            base,
+            quote,
+            levels,
+        } => {
+            let pair = clob.pair(base, quote)?;
+            let depth = clob.depth(pair, *levels).await?;
+            let view = DepthView::new(&depth, clob.tokens());
+            output.print(&view, || {
+                let side = |name: &str, levels: &[LevelView]| {
+                    levels.iter().map(move |level| {
+                        format!("{name} {} x {} ({} orders)", level.price, level.size, level.orders)
+                    })
+                };
+                // Asks are listed highest first so the spread sits in the middle
+                let asks = side("ask", &view.asks).collect::<Vec<_>>().into_iter().rev();
+                asks.chain(side("bid", &view.bids)).collect::<Vec<_>>().join("\n")
+            });
+        }
+        Command::Balances { account } => {
+            let balances = clob
+                .balances(account)?
+                .iter()
+                .map(|asset| clob.tokens().format_amount(asset))
+                .collect::<Vec<_>>();
+            output.print(&balances, || balances.join("\n"));
+        }
+        Command::Sync => {
+            clob.sync().await?;
+            let orders = clob.book().len();
+            output
+                .print(&json!({ "orders": orders }), || format!("synced, {orders} resting orders"));
+        }
+        Command::Scenario { .. } => unreachable!("scenarios run without opening the CLOB"),
+    }
+    Ok(())
+}
+
+/// Runs each scenario on a fresh simulated chain, or against the node,
+/// exiting with an error status if any of them does not pass
+async fn run_scenarios(
+    cli: &Cli,
+    output: &Output,
+    files: &[PathBuf],
+    simulated: bool,
+) -> Result<(), ClobError> {
+    let mut reports: Vec<ScenarioReport> = Vec::with_capacity(files.len());
+    for file in files {
+        let scenario = Scenario::load(file)?;
+        let report = if simulated {
+            run_scenario(&mut ClobDriver::simulated()?, &scenario).await
+        } else {
+            let config = ClobConfig::load(&cli.config)?;
+            run_scenario(&mut ClobDriver::new(Clob::open(&config)?), &scenario).await
+        };
+        reports.push(report);
+    }
+
+    output.print(&reports, || reports.iter().map(ToString::to_string).collect());
+    if !reports.iter().all(ScenarioReport::passed) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints results either as JSON or as human-readable text
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn print<T: Serialize>(&self, value: &T, text: impl FnOnce() -> String) {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(value).expect("views serialize to JSON"));
+        } else {
+            println!("{}", text());
+        }
+    }
+
+    fn receipts(&self, receipts: &[TransactionReceipt]) {
+        let views = receipts.iter().map(ReceiptView::from).collect::<Vec<_>>();
+        self.print(&views, || views.iter().map(describe_receipt).collect::<Vec<_>>().join("\n"));
+    }
+}
+
+fn describe_receipt(receipt: &ReceiptView) -> String {
+    let mut lines = vec![format!(
+        "tx {} in block {}",
+        receipt.transaction_id, receipt.block_num
+    )];
+    lines.extend(receipt.consumed_notes.iter().map(|id| format!("  consumed {id}")));
+    lines.extend(receipt.orders.iter().map(|id| format!("  order {id}")));
+    lines.extend(receipt.payback_notes.iter().map(|id| format!("  payback {id}")));
+    lines.join("\n")
 }
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+
+use miden_client::client::get_random_coin;
+use miden_client::client::transactions::transaction_request::TransactionRequest;
+use miden_objects::accounts::Account;
+use miden_objects::notes::NoteId;
+use tracing::{info, instrument, warn};
+
+use crate::backend::{execute_on, ClobBackend};
+use crate::book::OrderBook;
+use crate::errors::ClobError;
+use crate::market::{Market, Pair};
+use crate::order::{LimitOrder, NewOrder, OrderUpdate};
+use crate::receipt::TransactionReceipt;
+use crate::registry::{OrderRegistry, OrderStatus};
+use crate::{
+    build_consume_notes_tx_req, build_send_note_tx_script, check_new_order,
+    create_limit_swap_note_with_options,
+};
+
+/// Largest number of notes reclaimed by a single transaction.
+///
+/// The kernel accepts up to 1023 input notes, but each reclaimed note runs the
+/// limit-swap script and a transaction must stay within the cycle limit of the
+/// VM, so batches are kept far smaller.
+pub const MAX_CANCELS_PER_TX: usize = 64;
+
+/// Outcome of [cancel_all]
+#[derive(Debug, Default)]
+pub struct CancelReport {
+    /// Notes the maker got back
+    pub reclaimed: Vec<NoteId>,
+    /// Notes a taker consumed before they could be reclaimed
+    pub already_filled: Vec<NoteId>,
+    /// One receipt per cancel transaction
+    pub receipts: Vec<TransactionReceipt>,
+    /// Notes still resting because cancelling stopped at [CancelReport::error]
+    pub failed: Vec<NoteId>,
+    /// Error that stopped the cancellation, after the batches reported above
+    pub error: Option<ClobError>,
+}
+
+/// Replaces `old` by a new order `new` in a single transaction.
+///
+/// The maker reclaims its resting note with the [OrderUpdate::CANCEL] args,
+/// which puts the offered asset back in its vault, and the transaction script
+/// sends a fresh limit-swap note for `new` out of that vault. The new order
+/// may change the price, size, tokens and options of the old one, and may
+/// offer more than the old note held as long as the maker's funds cover it. If
+/// a taker consumed the old note first the transaction is rejected with
+/// [ClobError::NoteAlreadyConsumed].
+#[instrument(skip_all, fields(maker_id = maker.id().to_hex(), old = old.note_id.to_hex()))]
+pub async fn replace_order<B: ClobBackend>(
+    backend: &mut B,
+    market: &Market,
+    book: &OrderBook,
+    registry: &mut OrderRegistry,
+    maker: &Account,
+    old: &LimitOrder,
+    new: &NewOrder,
+) -> Result<TransactionReceipt, ClobError> {
+    if old.maker != maker.id() {
+        return Err(ClobError::InvalidOrder(format!(
+            "note {} belongs to another maker",
+            old.note_id.to_hex()
+        )));
+    }
+    check_new_order(market, book, new)?;
+
+    let note = create_limit_swap_note_with_options(
+        maker.id(),
+        new.offered.into(),
+        new.requested.into(),
+        &new.options,
+        get_random_coin(),
+    )?;
+    let new_order = LimitOrder::from_note(&note)?;
+    let tx_script = build_send_note_tx_script(backend, maker.id(), &note, new.offered.into())?;
+    let notes = BTreeMap::from([(old.note_id, Some(OrderUpdate::CANCEL.note_args()))]);
+    let tx_req = TransactionRequest::new(maker.id(), notes, vec![note], Some(tx_script));
+
+    match execute_on(backend, tx_req).await {
+        Ok(receipt) => {
+            info!(new = new_order.note_id.to_hex(), "Replaced order");
+            registry.record_replaced(old, &new_order)?;
+            Ok(receipt)
+        }
+        Err(err) if err.is_note_conflict() => {
+            warn!("Order was taken before it could be replaced");
+            registry.set_status(&old.note_id, OrderStatus::Filled)?;
+            Err(err)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Reclaims a single resting order of `maker`
+#[instrument(skip_all, fields(maker_id = maker.id().to_hex(), note = order.note_id.to_hex()))]
+pub async fn cancel_order<B: ClobBackend>(
+    backend: &mut B,
+    registry: &mut OrderRegistry,
+    maker: &Account,
+    order: &LimitOrder,
+) -> Result<TransactionReceipt, ClobError> {
+    if order.maker != maker.id() {
+        return Err(ClobError::InvalidOrder(format!(
+            "note {} belongs to another maker",
+            order.note_id.to_hex()
+        )));
+    }
+
+    let notes = BTreeMap::from([(order.note_id, Some(OrderUpdate::CANCEL.note_args()))]);
+    let tx_req = build_consume_notes_tx_req(backend, maker.id(), notes)?;
+    match execute_on(backend, tx_req).await {
+        Ok(receipt) => {
+            registry.set_status(&order.note_id, OrderStatus::Cancelled)?;
+            Ok(receipt)
+        }
+        Err(err) if err.is_note_conflict() => {
+            registry.set_status(&order.note_id, OrderStatus::Filled)?;
+            Err(err)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Reclaims every resting order of `maker`, or only those of `market` if
+/// given, in batches of at most [MAX_CANCELS_PER_TX] notes.
+///
+/// Notes a taker gets to first are dropped from their batch, which is then
+/// submitted again with the remaining notes. Any other failure stops the
+/// cancellation: the notes left are reported as [CancelReport::failed] along
+/// with the error, next to what the earlier batches reclaimed.
+#[instrument(skip_all, fields(maker_id = maker.id().to_hex(), market = ?market))]
+pub async fn cancel_all<B: ClobBackend>(
+    backend: &mut B,
+    registry: &mut OrderRegistry,
+    maker: &Account,
+    market: Option<Pair>,
+) -> Result<CancelReport, ClobError> {
+    let mut book = OrderBook::new();
+    book.refresh(backend).await?;
+
+    let note_ids = book
+        .orders()
+        .filter(|order| order.maker == maker.id())
+        .filter(|order| match market {
+            Some(pair) => pair.classify(&order.offered, &order.requested).is_some(),
+            None => true,
+        })
+        .map(|order| order.note_id)
+        .collect::<Vec<_>>();
+    info!(orders = note_ids.len(), "Cancelling orders");
+
+    let mut report = CancelReport::default();
+    for (index, chunk) in note_ids.chunks(MAX_CANCELS_PER_TX).enumerate() {
+        let batch = chunk.to_vec();
+        if let Err((mut failed, err)) =
+            cancel_batch(backend, registry, &mut book, maker, batch, &mut report).await
+        {
+            warn!(%err, failed = failed.len(), "Stopped cancelling orders");
+            failed.extend(note_ids.iter().skip((index + 1) * MAX_CANCELS_PER_TX));
+            report.failed = failed;
+            report.error = Some(err);
+            break;
+        }
+    }
+
+    info!(
+        reclaimed = report.reclaimed.len(),
+        already_filled = report.already_filled.len(),
+        failed = report.failed.len(),
+        "Cancelled orders"
+    );
+    Ok(report)
+}
+
+/// Reclaims `batch` into `report`, submitting it again without the notes
+/// takers consume first. Fails with the notes not reclaimed yet.
+async fn cancel_batch<B: ClobBackend>(
+    backend: &mut B,
+    registry: &mut OrderRegistry,
+    book: &mut OrderBook,
+    maker: &Account,
+    mut batch: Vec<NoteId>,
+    report: &mut CancelReport,
+) -> Result<(), (Vec<NoteId>, ClobError)> {
+    while !batch.is_empty() {
+        let notes = batch.iter().map(|note_id| (*note_id, Some(OrderUpdate::CANCEL.note_args())));
+        let tx_req = match build_consume_notes_tx_req(backend, maker.id(), notes.collect()) {
+            Ok(tx_req) => tx_req,
+            Err(err) => return Err((batch, err)),
+        };
+
+        match execute_on(backend, tx_req).await {
+            Ok(receipt) => {
+                report.receipts.push(receipt);
+                for note_id in &batch {
+                    registry
+                        .set_status(note_id, OrderStatus::Cancelled)
+                        .map_err(|err| (Vec::new(), err))?;
+                }
+                report.reclaimed.append(&mut batch);
+            }
+            Err(err) if err.is_note_conflict() => {
+                // The rejection may blame the whole batch, so look up which
+                // notes are actually gone
+                if let Err(refresh_err) = book.refresh(backend).await {
+                    return Err((batch, refresh_err));
+                }
+                let (resting, taken): (Vec<_>, Vec<_>) =
+                    batch.into_iter().partition(|note_id| book.get(note_id).is_some());
+                if taken.is_empty() {
+                    return Err((resting, err));
+                }
+                warn!(taken = taken.len(), "Orders filled before they could be cancelled");
+
+                for note_id in &taken {
+                    registry
+                        .set_status(note_id, OrderStatus::Filled)
+                        .map_err(|err| (resting.clone(), err))?;
+                }
+                report.already_filled.extend(taken);
+                batch = resting;
+            }
+            Err(err) => return Err((batch, err)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,250 @@
+use core::cmp::Ordering;
+use core::fmt;
+use std::collections::BTreeMap;
+
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+
+use crate::config::ClobConfig;
+use crate::errors::ClobError;
+use crate::order::{Fill, LimitOrder};
+use crate::tokens::{parse_units, TokenRegistry};
+
+/// An exact price: the ratio of a quote amount to a base amount, in base units.
+///
+/// Prices are compared by cross-multiplication, so 1/2 and 2/4 are equal.
+#[derive(Debug, Clone, Copy)]
+pub struct Price {
+    quote: u64,
+    base: u64,
+}
+
+impl Price {
+    /// Returns the price of `base` units for `quote` units, None if `base` is 0
+    pub fn new(quote: u64, base: u64) -> Option<Self> {
+        (base != 0).then_some(Self { quote, base })
+    }
+
+    pub fn quote(&self) -> u64 {
+        self.quote
+    }
+
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Returns true if the price is a whole multiple of `tick`
+    pub fn is_multiple_of(&self, tick: Price) -> bool {
+        // (quote / base) / (tick.quote / tick.base) = quote * tick.base / (base * tick.quote)
+        let divisor = self.base as u128 * tick.quote as u128;
+        divisor != 0 && (self.quote as u128 * tick.base as u128) % divisor == 0
+    }
+}
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.quote as u128 * other.base as u128).cmp(&(other.quote as u128 * self.base as u128))
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.quote, self.base)
+    }
+}
+
+/// A market, trading assets of the `base` faucet against the `quote` faucet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Pair {
+    pub base: AccountId,
+    pub quote: AccountId,
+}
+
+/// Side of an order within its market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Offers quote in exchange for base
+    Bid,
+    /// Offers base in exchange for quote
+    Ask,
+}
+
+impl Pair {
+    pub fn new(base: AccountId, quote: AccountId) -> Self {
+        Self { base, quote }
+    }
+
+    /// Returns the side, base size and price of an order offering `offered` in
+    /// exchange for `requested`, or None if the order is not of this pair
+    pub fn classify(
+        &self,
+        offered: &FungibleAsset,
+        requested: &FungibleAsset,
+    ) -> Option<(Side, u64, Price)> {
+        let faucets = (offered.faucet_id(), requested.faucet_id());
+        if faucets == (self.base, self.quote) {
+            let price = Price::new(requested.amount(), offered.amount())?;
+            Some((Side::Ask, offered.amount(), price))
+        } else if faucets == (self.quote, self.base) {
+            let price = Price::new(offered.amount(), requested.amount())?;
+            Some((Side::Bid, requested.amount(), price))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Pair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base.to_hex(), self.quote.to_hex())
+    }
+}
+
+/// Trading rules of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Market {
+    pub pair: Pair,
+    /// Prices must be whole multiples of the tick size
+    pub tick_size: Price,
+    /// Order sizes, in base units, must be whole multiples of the lot size
+    pub lot_size: u64,
+}
+
+impl Market {
+    /// Checks that an order offering `offered` for `requested` belongs to this
+    /// market, is priced on the tick grid and is sized in whole lots
+    pub fn validate_order(
+        &self,
+        offered: &FungibleAsset,
+        requested: &FungibleAsset,
+    ) -> Result<Side, ClobError> {
+        let (side, size, price) = self
+            .pair
+            .classify(offered, requested)
+            .ok_or_else(|| ClobError::InvalidOrder(format!("not an order of {}", self.pair)))?;
+
+        if self.lot_size == 0 {
+            return Err(ClobError::Config(format!("market {} has a lot size of 0", self.pair)));
+        }
+        if size < self.lot_size || size % self.lot_size != 0 {
+            return Err(ClobError::InvalidOrder(format!(
+                "size {size} is not a whole number of lots of {}",
+                self.lot_size
+            )));
+        }
+        if !price.is_multiple_of(self.tick_size) {
+            return Err(ClobError::InvalidOrder(format!(
+                "price {price} is off the tick grid of {}",
+                self.tick_size
+            )));
+        }
+
+        Ok(side)
+    }
+
+    /// Checks that `fill` does not leave less than one lot of `order` resting
+    pub fn validate_fill(&self, order: &LimitOrder, fill: &Fill) -> Result<(), ClobError> {
+        let (side, size, _) = self
+            .pair
+            .classify(&order.offered, &order.requested)
+            .ok_or_else(|| ClobError::InvalidOrder(format!("not an order of {}", self.pair)))?;
+        let filled = match side {
+            Side::Ask => fill.out_amount,
+            Side::Bid => fill.in_amount,
+        };
+
+        let remaining = size.saturating_sub(filled);
+        if remaining != 0 && remaining < self.lot_size {
+            return Err(ClobError::InvalidOrder(format!(
+                "fill leaves {remaining}, less than one lot of {}",
+                self.lot_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The markets listed in the configuration
+#[derive(Debug, Clone, Default)]
+pub struct Markets {
+    markets: BTreeMap<Pair, Market>,
+}
+
+impl Markets {
+    /// Resolves the configured markets, expressed in token units, to base units
+    pub fn from_config(config: &ClobConfig, tokens: &TokenRegistry) -> Result<Self, ClobError> {
+        let mut markets = Self::default();
+        for entry in &config.markets {
+            let token = |symbol: &str| {
+                tokens
+                    .get(symbol)
+                    .ok_or_else(|| ClobError::Config(format!("unknown token {symbol} in market")))
+            };
+            let (base, quote) = (token(&entry.base)?, token(&entry.quote)?);
+            let one_base = 10u64.checked_pow(base.decimals as u32).ok_or_else(|| {
+                ClobError::Config(format!(
+                    "{} has {} decimals, more than prices support",
+                    entry.base, base.decimals
+                ))
+            })?;
+
+            let tick_quote = parse_units(&entry.tick_size, quote.decimals)?;
+            let lot_size = parse_units(&entry.lot_size, base.decimals)?;
+            if tick_quote == 0 || lot_size == 0 {
+                return Err(ClobError::Config(format!(
+                    "market {}/{} needs a non-zero tick and lot size",
+                    entry.base, entry.quote
+                )));
+            }
+
+            markets.insert(Market {
+                pair: Pair::new(base.faucet_id, quote.faucet_id),
+                tick_size: Price::new(tick_quote, one_base).expect("power of ten is not zero"),
+                lot_size,
+            });
+        }
+        Ok(markets)
+    }
+
+    pub fn insert(&mut self, market: Market) {
+        self.markets.insert(market.pair, market);
+    }
+
+    pub fn get(&self, pair: &Pair) -> Option<&Market> {
+        self.markets.get(pair)
+    }
+
+    /// Returns the market an order offering assets of `offered_faucet` for
+    /// assets of `requested_faucet` belongs to
+    pub fn find(&self, offered_faucet: AccountId, requested_faucet: AccountId) -> Option<&Market> {
+        self.get(&Pair::new(offered_faucet, requested_faucet))
+            .or_else(|| self.get(&Pair::new(requested_faucet, offered_faucet)))
+    }
+
+    /// Checks `fill` against the market of `order`, see [Market::validate_fill].
+    /// Orders outside the listed markets have no lot size to respect.
+    pub fn validate_fill(&self, order: &LimitOrder, fill: &Fill) -> Result<(), ClobError> {
+        match self.find(order.offered.faucet_id(), order.requested.faucet_id()) {
+            Some(market) => market.validate_fill(order, fill),
+            None => Ok(()),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Market> {
+        self.markets.values()
+    }
+}
@@ -0,0 +1,112 @@
+use miden_objects::accounts::Account;
+use miden_objects::assets::FungibleAsset;
+use tracing::{info, instrument};
+
+use crate::backend::{execute_on, ClobBackend};
+use crate::book::OrderBook;
+use crate::build_fill_tx_req;
+use crate::errors::ClobError;
+use crate::market::{Markets, Pair};
+use crate::order::{Fill, LimitOrder};
+use crate::receipt::TransactionReceipt;
+
+/// A bid and an ask that cross, filled against each other by a third party.
+///
+/// The matcher pays the ask in the quote asset and receives base, then pays
+/// the bid in base and receives quote. Each maker is paid through the payback
+/// note of its order, and the matcher keeps the difference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Fill of the order offering the base asset
+    pub ask: Fill,
+    /// Fill of the order offering the quote asset
+    pub bid: Fill,
+    /// Quote asset left to the matcher, received from the bid and not paid
+    /// to the ask
+    pub quote_profit: FungibleAsset,
+    /// Base asset left to the matcher, received from the ask and not paid to
+    /// the bid
+    pub base_profit: FungibleAsset,
+}
+
+impl Match {
+    /// Computes the largest match between `ask` and `bid`, or None if they are
+    /// not opposite orders, do not cross, or leave the matcher nothing
+    pub fn between(ask: &LimitOrder, bid: &LimitOrder) -> Option<Self> {
+        if ask.offered.faucet_id() != bid.requested.faucet_id()
+            || ask.requested.faucet_id() != bid.offered.faucet_id()
+        {
+            return None;
+        }
+
+        // Buy as much base from the ask as the bid wants, paying just enough
+        let base = ask.offered.amount().min(bid.requested.amount()) as u128;
+        let ask_in =
+            (base * ask.requested.amount() as u128).div_ceil(ask.offered.amount() as u128) as u64;
+        let ask_fill = ask.fill_for_input(ask_in)?;
+        let bid_fill = bid.fill_for_input(ask_fill.out_amount.min(bid.requested.amount()))?;
+
+        let quote_profit = bid_fill.out_amount.checked_sub(ask_fill.in_amount)?;
+        let base_profit = ask_fill.out_amount - bid_fill.in_amount;
+        if quote_profit == 0 && base_profit == 0 {
+            return None;
+        }
+
+        Some(Self {
+            ask: ask_fill,
+            bid: bid_fill,
+            quote_profit: FungibleAsset::new(ask.requested.faucet_id(), quote_profit).ok()?,
+            base_profit: FungibleAsset::new(ask.offered.faucet_id(), base_profit).ok()?,
+        })
+    }
+
+    /// Returns the asset the matcher must hold before submitting the match.
+    ///
+    /// Notes are consumed in [NoteId](miden_objects::notes::NoteId) order, so
+    /// the leg consumed first is paid from the matcher's own inventory, and only
+    /// the second leg is paid with what the first one yielded.
+    pub fn inventory_needed(&self) -> FungibleAsset {
+        if self.ask.note_id < self.bid.note_id {
+            FungibleAsset::new(self.quote_profit.faucet_id(), self.ask.in_amount).unwrap()
+        } else {
+            FungibleAsset::new(self.base_profit.faucet_id(), self.bid.in_amount).unwrap()
+        }
+    }
+}
+
+/// Finds crossing orders of `pair` in `book`, pairing the best ask with the
+/// best bid, the second best with the second best, and so on until they no
+/// longer cross. Each order is used at most once, and matches that would leave
+/// either order with less than a lot of `markets` are skipped.
+pub fn find_matches(book: &OrderBook, markets: &Markets, pair: Pair) -> Vec<Match> {
+    let asks = book.offers(pair.base, pair.quote);
+    let bids = book.offers(pair.quote, pair.base);
+    asks.into_iter()
+        .zip(bids)
+        .map_while(|(ask, bid)| Match::between(ask, bid).map(|matched| (ask, bid, matched)))
+        .filter(|(ask, bid, matched)| {
+            markets.validate_fill(ask, &matched.ask).is_ok()
+                && markets.validate_fill(bid, &matched.bid).is_ok()
+        })
+        .map(|(_, _, matched)| matched)
+        .collect()
+}
+
+/// Consumes both orders of `matched` in a single transaction of `matcher`.
+///
+/// See [Match::inventory_needed] for what the matcher must hold beforehand.
+#[instrument(skip_all, fields(matcher = matcher.id().to_hex(), ask = matched.ask.note_id.to_hex(), bid = matched.bid.note_id.to_hex()))]
+pub async fn execute_match<B: ClobBackend>(
+    backend: &mut B,
+    matcher: &Account,
+    matched: &Match,
+) -> Result<TransactionReceipt, ClobError> {
+    info!(
+        quote_profit = matched.quote_profit.amount(),
+        base_profit = matched.base_profit.amount(),
+        inventory = ?matched.inventory_needed(),
+        "Executing match"
+    );
+    let tx_req = build_fill_tx_req(backend, matcher.id(), &[matched.ask, matched.bid])?;
+    execute_on(backend, tx_req).await
+}
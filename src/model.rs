@@ -0,0 +1,233 @@
+//! Executable reference model of masm/limit_swap.masm
+//!
+//! Given a resting order, the account consuming its note and the note args,
+//! [consume] predicts the notes the script creates and how the vault of the
+//! consuming account changes, or the reason the script aborts. Tests run the
+//! same inputs through the transaction executor and compare.
+//!
+//! The rules modelled are:
+//! - a taker passes `[0, 0, in_amount, out_amount]`, pays `in_amount` of the
+//!   requested asset into a P2ID payback to the maker and receives
+//!   `out_amount` of the offered asset, at no better than the order's price
+//! - a take receives at least the order's minimum fill, unless it takes all
+//!   that is left of the offered asset
+//! - the rest of the offered asset is offered by a clone asking for the rest
+//!   of the requested amount, unless it is below the order's minimum
+//!   remaining size, in which case it joins the payback
+//! - the maker passes the [OrderUpdate] args `[0, 0, new_requested,
+//!   new_offered]` to replace the order with a clone of the new amounts,
+//!   taking back the difference; [OrderUpdate::CANCEL] cancels the order
+
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::{Asset, FungibleAsset};
+use miden_objects::notes::{NoteAssets, NoteId, NoteInputs};
+use miden_objects::Hasher;
+use vm_processor::{Digest, Word};
+
+use crate::errors::ClobError;
+use crate::order::{LimitOrder, OrderUpdate};
+
+/// Note args of a consumption, as the limit-swap script reads them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consumption {
+    /// A taker pays `in_amount` of the requested asset for `out_amount` of the
+    /// offered asset
+    Take { in_amount: u64, out_amount: u64 },
+    /// The maker replaces the order by one offering `offered` for `requested`,
+    /// or cancels it when both are 0
+    Update { offered: u64, requested: u64 },
+}
+
+impl Consumption {
+    /// Reads the note args passed to the script by `consumer`
+    pub fn from_args(order: &LimitOrder, consumer: AccountId, args: Word) -> Self {
+        if consumer == order.maker {
+            let update = OrderUpdate::from_note_args(args);
+            Consumption::Update {
+                offered: update.offered,
+                requested: update.requested,
+            }
+        } else {
+            Consumption::Take {
+                in_amount: args[2].as_int(),
+                out_amount: args[3].as_int(),
+            }
+        }
+    }
+}
+
+/// A note created by the limit-swap script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelNote {
+    pub recipient: Digest,
+    pub assets: Vec<FungibleAsset>,
+}
+
+impl ModelNote {
+    pub fn id(&self) -> NoteId {
+        let assets = self.assets.iter().map(|asset| Asset::from(*asset)).collect();
+        let assets = NoteAssets::new(assets).expect("model notes hold at most two assets");
+        NoteId::new(self.recipient, assets.commitment())
+    }
+}
+
+/// Effects of consuming a limit-swap note, as predicted by the model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelOutcome {
+    /// P2ID note to the maker, created when a taker fills the order
+    pub payback: Option<ModelNote>,
+    /// Clone offering what is left of the order, and the order it encodes
+    pub clone: Option<(ModelNote, LimitOrder)>,
+    /// Assets added to the vault of the consuming account
+    pub added: Vec<FungibleAsset>,
+    /// Assets removed from the vault of the consuming account
+    pub removed: Vec<FungibleAsset>,
+}
+
+impl ModelOutcome {
+    /// Ids of the created notes, in the order the script creates them
+    pub fn output_note_ids(&self) -> Vec<NoteId> {
+        self.payback
+            .iter()
+            .map(ModelNote::id)
+            .chain(self.clone.iter().map(|(note, _)| note.id()))
+            .collect()
+    }
+}
+
+/// Predicts the outcome of `consumer` consuming `order` with `consumption`
+///
+/// Fails with [ClobError::InvalidOrder] where the script would abort the
+/// transaction.
+pub fn consume(
+    order: &LimitOrder,
+    consumer: AccountId,
+    consumption: Consumption,
+) -> Result<ModelOutcome, ClobError> {
+    match consumption {
+        Consumption::Take {
+            in_amount,
+            out_amount,
+        } => {
+            if consumer == order.maker {
+                return Err(ClobError::InvalidOrder(
+                    "the maker cannot take its own order".to_string(),
+                ));
+            }
+            take(order, in_amount, out_amount)
+        }
+        Consumption::Update { offered, requested } => {
+            if consumer != order.maker {
+                return Err(ClobError::InvalidOrder(
+                    "only the maker can update the order".to_string(),
+                ));
+            }
+            update(order, offered, requested)
+        }
+    }
+}
+
+fn take(order: &LimitOrder, in_amount: u64, out_amount: u64) -> Result<ModelOutcome, ClobError> {
+    let offered = order.offered.amount();
+    let requested = order.requested.amount();
+
+    if in_amount > requested {
+        return Err(ClobError::InvalidOrder("payment exceeds the requested amount".to_string()));
+    }
+    if out_amount == 0 || out_amount > offered {
+        return Err(ClobError::InvalidOrder("fill outside the offered amount".to_string()));
+    }
+    if out_amount < order.min_fill && out_amount != offered {
+        return Err(ClobError::InvalidOrder("fill below the order's minimum".to_string()));
+    }
+    if out_amount as u128 * requested as u128 > in_amount as u128 * offered as u128 {
+        return Err(ClobError::InvalidOrder("fill better than the order's price".to_string()));
+    }
+
+    let paid = fungible(order.requested.faucet_id(), in_amount)?;
+    let received = fungible(order.offered.faucet_id(), out_amount)?;
+
+    let mut payback_assets = vec![paid];
+    let mut clone = None;
+    let remaining = offered - out_amount;
+    if is_dust(remaining, order.min_remaining) {
+        payback_assets.push(fungible(order.offered.faucet_id(), remaining)?);
+    } else if remaining > 0 {
+        clone = Some(clone_of(order, remaining, requested - in_amount)?);
+    }
+
+    Ok(ModelOutcome {
+        payback: Some(ModelNote {
+            recipient: order.payback_recipient,
+            assets: payback_assets,
+        }),
+        clone,
+        added: vec![received],
+        removed: vec![paid],
+    })
+}
+
+fn update(order: &LimitOrder, offered: u64, requested: u64) -> Result<ModelOutcome, ClobError> {
+    let current = order.offered.amount();
+    if offered > current {
+        return Err(ClobError::InvalidOrder("cannot offer more than the note holds".to_string()));
+    }
+    if (offered == 0) != (requested == 0) {
+        return Err(ClobError::InvalidOrder("an update needs both amounts".to_string()));
+    }
+
+    let reclaimed = current - offered;
+    let added = match reclaimed {
+        0 => Vec::new(),
+        amount => vec![fungible(order.offered.faucet_id(), amount)?],
+    };
+    let clone = match offered {
+        0 => None,
+        offered => Some(clone_of(order, offered, requested)?),
+    };
+
+    Ok(ModelOutcome {
+        payback: None,
+        clone,
+        added,
+        removed: Vec::new(),
+    })
+}
+
+/// Mirrors `proc.is_dust`: whether a non-zero leftover is below the maker's
+/// minimum remaining size
+fn is_dust(remaining: u64, min_remaining: u64) -> bool {
+    remaining > 0 && remaining < min_remaining
+}
+
+/// Builds the clone of `order` offering `offered` for `requested`
+///
+/// The clone keeps the serial number and script of the order, so its
+/// recipient is the order's partial recipient merged with the new inputs.
+fn clone_of(
+    order: &LimitOrder,
+    offered: u64,
+    requested: u64,
+) -> Result<(ModelNote, LimitOrder), ClobError> {
+    let offered = fungible(order.offered.faucet_id(), offered)?;
+    let requested = fungible(order.requested.faucet_id(), requested)?;
+    let mut clone = LimitOrder {
+        offered,
+        requested,
+        ..order.clone()
+    };
+
+    let inputs = NoteInputs::new(clone.to_inputs().to_vec()).map_err(ClobError::Note)?;
+    let recipient = Hasher::merge(&[order.partial_recipient, inputs.commitment()]);
+    let note = ModelNote {
+        recipient,
+        assets: vec![offered],
+    };
+    clone.note_id = note.id();
+
+    Ok((note, clone))
+}
+
+fn fungible(faucet_id: AccountId, amount: u64) -> Result<FungibleAsset, ClobError> {
+    FungibleAsset::new(faucet_id, amount).map_err(|err| ClobError::Token(err.to_string()))
+}
@@ -0,0 +1,372 @@
+use miden_client::store::InputNoteRecord;
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::{Asset, FungibleAsset};
+use miden_objects::notes::{Note, NoteAssets, NoteId, NoteInputs};
+use miden_objects::Hasher;
+use vm_processor::{Digest, Felt, Word, ZERO};
+
+use crate::errors::ClobError;
+
+/// Number of inputs of a limit-swap note
+pub const LIMIT_SWAP_NUM_INPUTS: usize = 16;
+
+// Layout of the limit-swap note inputs, as read by masm/limit_swap.masm
+const PAYBACK_RECIPIENT_OFFSET: usize = 0;
+const REQUESTED_ASSET_OFFSET: usize = 4;
+const MAKER_OFFSET: usize = 8;
+const MIN_REMAINING_OFFSET: usize = 9;
+const MIN_FILL_OFFSET: usize = 10;
+const PARTIAL_RECIPIENT_OFFSET: usize = 12;
+
+/// Maker-chosen parameters of a limit order, on top of its assets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderOptions {
+    /// Smallest amount of the offered asset worth keeping on the book. A fill
+    /// leaving less returns the leftover to the maker with the payback note
+    /// instead of creating a clone. 0 disables the check.
+    pub min_remaining: u64,
+    /// Smallest amount of the offered asset a taker may receive, unless it
+    /// takes everything that is left. 0 disables the check.
+    ///
+    /// The check is `proc.assert_min_fill` of masm/limit_swap.masm. The body
+    /// of the script calling it is maintained outside this repository, so
+    /// the CLOB only relies on [LimitOrder::allows_fill] to skip such fills.
+    pub min_fill: u64,
+}
+
+/// A limit order to be placed by a maker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NewOrder {
+    pub offered: FungibleAsset,
+    pub requested: FungibleAsset,
+    pub options: OrderOptions,
+    /// Refuse to place the order if it would cross resting orders of the
+    /// opposite side instead of resting on the book
+    pub post_only: bool,
+}
+
+impl NewOrder {
+    pub fn new(offered: FungibleAsset, requested: FungibleAsset) -> Self {
+        Self {
+            offered,
+            requested,
+            options: OrderOptions::default(),
+            post_only: false,
+        }
+    }
+
+    pub fn with_options(mut self, options: OrderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn post_only(mut self) -> Self {
+        self.post_only = true;
+        self
+    }
+}
+
+/// A resting limit order, decoded from a limit-swap note.
+///
+/// The maker offers `offered` and asks for `requested` in return. Takers may
+/// fill the order partially, at the ratio implied by the two amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitOrder {
+    pub note_id: NoteId,
+    pub maker: AccountId,
+    pub offered: FungibleAsset,
+    pub requested: FungibleAsset,
+    pub payback_recipient: Digest,
+    pub partial_recipient: Digest,
+    /// See [OrderOptions::min_remaining]
+    pub min_remaining: u64,
+    /// See [OrderOptions::min_fill]
+    pub min_fill: u64,
+}
+
+/// What happens to the unfilled part of an order after a fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remainder {
+    /// The order was filled completely
+    None,
+    /// A clone of the order keeps offering this amount
+    Clone(u64),
+    /// This amount is below the order's minimum remaining size and goes back
+    /// to the maker along with the payback
+    Returned(u64),
+}
+
+/// Amounts exchanged when a taker consumes a limit order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fill {
+    pub note_id: NoteId,
+    /// Amount of the requested asset the taker pays to the maker
+    pub in_amount: u64,
+    /// Amount of the offered asset the taker receives
+    pub out_amount: u64,
+    /// What happens to the rest of the offered asset
+    pub remainder: Remainder,
+}
+
+impl Fill {
+    /// Note args expected by the limit-swap script when a taker consumes it
+    pub fn note_args(&self) -> Word {
+        [
+            ZERO,
+            ZERO,
+            Felt::new(self.in_amount),
+            Felt::new(self.out_amount),
+        ]
+    }
+}
+
+/// New amounts a maker sets on its resting order by consuming the note.
+///
+/// The limit-swap script tells the maker from a taker by the consuming
+/// account, and reads the maker's note args as the amounts the order keeps
+/// offering and asking for. A clone of the order offers them, and the rest of
+/// the offered asset goes back to the maker's vault. An update to nothing
+/// cancels the order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderUpdate {
+    pub offered: u64,
+    pub requested: u64,
+}
+
+impl OrderUpdate {
+    /// Update reclaiming the whole order without a clone
+    pub const CANCEL: Self = Self {
+        offered: 0,
+        requested: 0,
+    };
+
+    /// Note args expected by the limit-swap script when the maker consumes it
+    pub fn note_args(&self) -> Word {
+        [
+            ZERO,
+            ZERO,
+            Felt::new(self.requested),
+            Felt::new(self.offered),
+        ]
+    }
+
+    /// Reads the note args passed to the script by the maker
+    pub fn from_note_args(args: Word) -> Self {
+        Self {
+            offered: args[3].as_int(),
+            requested: args[2].as_int(),
+        }
+    }
+
+    pub fn is_cancel(&self) -> bool {
+        *self == Self::CANCEL
+    }
+}
+
+/// Encodes the inputs of a limit-swap note
+pub fn encode_limit_swap_inputs(
+    payback_recipient: Digest,
+    requested_asset: Asset,
+    maker: AccountId,
+    options: &OrderOptions,
+    partial_recipient: Digest,
+) -> [Felt; LIMIT_SWAP_NUM_INPUTS] {
+    let requested_asset_word: Word = requested_asset.into();
+    [
+        payback_recipient[0],
+        payback_recipient[1],
+        payback_recipient[2],
+        payback_recipient[3],
+        requested_asset_word[0],
+        requested_asset_word[1],
+        requested_asset_word[2],
+        requested_asset_word[3],
+        maker.into(),
+        Felt::new(options.min_remaining),
+        Felt::new(options.min_fill),
+        ZERO,
+        partial_recipient[0],
+        partial_recipient[1],
+        partial_recipient[2],
+        partial_recipient[3],
+    ]
+}
+
+impl LimitOrder {
+    /// Decodes an order from the parts of a limit-swap note
+    pub fn from_parts(
+        note_id: NoteId,
+        assets: &NoteAssets,
+        inputs: &[Felt],
+    ) -> Result<Self, ClobError> {
+        let invalid = |reason: &str| ClobError::InvalidOrderNote(note_id, reason.to_string());
+
+        if inputs.len() != LIMIT_SWAP_NUM_INPUTS {
+            return Err(invalid("unexpected number of inputs"));
+        }
+
+        let offered = match assets.iter().collect::<Vec<_>>().as_slice() {
+            [Asset::Fungible(asset)] => *asset,
+            _ => return Err(invalid("expected a single fungible asset")),
+        };
+
+        let requested_word: Word = read_word(inputs, REQUESTED_ASSET_OFFSET);
+        let requested = match Asset::try_from(requested_word) {
+            Ok(Asset::Fungible(asset)) => asset,
+            _ => return Err(invalid("requested asset is not fungible")),
+        };
+
+        if offered.amount() == 0 || requested.amount() == 0 {
+            return Err(invalid("zero amount"));
+        }
+
+        let maker = AccountId::try_from(inputs[MAKER_OFFSET].as_int())
+            .map_err(|_| invalid("invalid maker account id"))?;
+
+        Ok(Self {
+            note_id,
+            maker,
+            offered,
+            requested,
+            payback_recipient: read_word(inputs, PAYBACK_RECIPIENT_OFFSET).into(),
+            partial_recipient: read_word(inputs, PARTIAL_RECIPIENT_OFFSET).into(),
+            min_remaining: inputs[MIN_REMAINING_OFFSET].as_int(),
+            min_fill: inputs[MIN_FILL_OFFSET].as_int(),
+        })
+    }
+
+    /// Decodes an order from a note created by this client
+    pub fn from_note(note: &Note) -> Result<Self, ClobError> {
+        Self::from_parts(note.id(), note.assets(), note.inputs().values())
+    }
+
+    /// Decodes an order from a note tracked by the client store
+    pub fn from_record(record: &InputNoteRecord) -> Result<Self, ClobError> {
+        Self::from_parts(record.id(), record.assets(), record.details().inputs())
+    }
+
+    /// Maker-chosen parameters of the order
+    pub fn options(&self) -> OrderOptions {
+        OrderOptions {
+            min_remaining: self.min_remaining,
+            min_fill: self.min_fill,
+        }
+    }
+
+    /// Note args with which the maker reclaims the order, those of
+    /// [OrderUpdate::CANCEL]. Only the maker may pass them: read as the args
+    /// of a taker they would receive nothing, which the script rejects.
+    pub fn cancel_args() -> Word {
+        OrderUpdate::CANCEL.note_args()
+    }
+
+    /// Returns the order left on the book once the maker applies `update`, or
+    /// None if the update cancels it.
+    ///
+    /// The clone keeps the serial number, script and options of the order, so
+    /// only its amounts and note id change. Fails if the update offers more
+    /// than the note holds or sets only one of the two amounts, which the
+    /// script rejects.
+    pub fn updated(&self, update: OrderUpdate) -> Result<Option<LimitOrder>, ClobError> {
+        if update.offered > self.offered.amount() {
+            return Err(ClobError::InvalidOrder(format!(
+                "cannot offer {} out of a note holding {}",
+                update.offered,
+                self.offered.amount()
+            )));
+        }
+        if (update.offered == 0) != (update.requested == 0) {
+            return Err(ClobError::InvalidOrder("an update needs both amounts".to_string()));
+        }
+        if update.is_cancel() {
+            return Ok(None);
+        }
+
+        let offered = FungibleAsset::new(self.offered.faucet_id(), update.offered)
+            .map_err(|err| ClobError::Token(err.to_string()))?;
+        let requested = FungibleAsset::new(self.requested.faucet_id(), update.requested)
+            .map_err(|err| ClobError::Token(err.to_string()))?;
+        let mut clone = LimitOrder {
+            offered,
+            requested,
+            ..self.clone()
+        };
+
+        let inputs = NoteInputs::new(clone.to_inputs().to_vec())?;
+        let recipient = Hasher::merge(&[self.partial_recipient, inputs.commitment()]);
+        let assets = NoteAssets::new(vec![offered.into()])?;
+        clone.note_id = NoteId::new(recipient, assets.commitment());
+        Ok(Some(clone))
+    }
+
+    /// Encodes the order back into limit-swap note inputs
+    pub fn to_inputs(&self) -> [Felt; LIMIT_SWAP_NUM_INPUTS] {
+        encode_limit_swap_inputs(
+            self.payback_recipient,
+            self.requested.into(),
+            self.maker,
+            &self.options(),
+            self.partial_recipient,
+        )
+    }
+
+    /// Computes the fill for paying up to `in_amount` of the requested asset.
+    ///
+    /// The payment is capped to what the order asks for and the received
+    /// amount is rounded down in favour of the maker. Returns None if the
+    /// fill breaks the order's minimum, see [LimitOrder::allows_fill].
+    pub fn fill_for_input(&self, in_amount: u64) -> Option<Fill> {
+        let in_amount = in_amount.min(self.requested.amount());
+        let out_amount = (in_amount as u128 * self.offered.amount() as u128
+            / self.requested.amount() as u128) as u64;
+        self.allows_fill(out_amount).then_some(Fill {
+            note_id: self.note_id,
+            in_amount,
+            out_amount,
+            remainder: self.remainder_after(out_amount),
+        })
+    }
+
+    /// Returns true if a taker may receive `out_amount` of the offered asset:
+    /// at least the order's minimum fill, or everything that is left, as
+    /// `proc.assert_min_fill` of the limit-swap script accepts
+    pub fn allows_fill(&self, out_amount: u64) -> bool {
+        let offered = self.offered.amount();
+        out_amount > 0
+            && out_amount <= offered
+            && (out_amount >= self.min_fill || out_amount == offered)
+    }
+
+    /// Smallest payment of the requested asset accepted by the order
+    pub fn min_input(&self) -> u64 {
+        self.input_for(self.min_fill.clamp(1, self.offered.amount()))
+    }
+
+    /// Smallest payment of the requested asset for which the order gives
+    /// `out_amount` of the offered asset, rounded up in favour of the maker
+    pub fn input_for(&self, out_amount: u64) -> u64 {
+        let requested = self.requested.amount() as u128;
+        let offered = self.offered.amount() as u128;
+        ((out_amount as u128 * requested).div_ceil(offered)) as u64
+    }
+
+    /// Predicts what happens to the rest of the order once `out_amount` of the
+    /// offered asset has been taken. The leftover is returned exactly when
+    /// `proc.is_dust` of the limit-swap script says so.
+    pub fn remainder_after(&self, out_amount: u64) -> Remainder {
+        match self.offered.amount().saturating_sub(out_amount) {
+            0 => Remainder::None,
+            remaining if remaining < self.min_remaining => Remainder::Returned(remaining),
+            remaining => Remainder::Clone(remaining),
+        }
+    }
+}
+
+fn read_word(inputs: &[Felt], offset: usize) -> Word {
+    [
+        inputs[offset],
+        inputs[offset + 1],
+        inputs[offset + 2],
+        inputs[offset + 3],
+    ]
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use miden_client::client::accounts::AccountStorageMode;
+use miden_objects::accounts::{Account, AccountId};
+use miden_objects::assets::TokenSymbol;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+
+use crate::backend::ClobBackend;
+use crate::errors::ClobError;
+use crate::tokens::faucet_metadata;
+
+/// Where profiles are kept, next to the client sqlite store.
+///
+/// The schema of the sqlite store belongs to miden-client, which migrates it
+/// and has no table for names of our own, so profiles live in a separate file
+/// of the same directory rather than in the store itself.
+pub const DEFAULT_PROFILES_PATH: &str = "./db/profiles.json";
+
+/// On-disk format of the profiles, account IDs are hex encoded
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    accounts: BTreeMap<String, String>,
+    faucets: BTreeMap<String, String>,
+}
+
+/// Named accounts and faucets kept across runs.
+///
+/// Wallets are looked up by alias (e.g. "maker") and faucets by token symbol,
+/// and only created when no account is known for the name yet.
+#[derive(Debug, Clone)]
+pub struct AccountProfiles {
+    /// File the profiles are saved to, if any
+    path: Option<PathBuf>,
+    storage_mode: AccountStorageMode,
+    accounts: BTreeMap<String, AccountId>,
+    faucets: BTreeMap<String, AccountId>,
+}
+
+impl AccountProfiles {
+    /// Loads the profiles at `path`, starting empty if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ClobError> {
+        let path = path.as_ref().to_path_buf();
+        let file: ProfilesFile = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|err| ClobError::Profile(format!("{}: {err}", path.display())))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => ProfilesFile::default(),
+            Err(err) => return Err(ClobError::Io(err)),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            storage_mode: AccountStorageMode::Local,
+            accounts: parse_ids(file.accounts)?,
+            faucets: parse_ids(file.faucets)?,
+        })
+    }
+
+    /// Loads the profiles at [DEFAULT_PROFILES_PATH]
+    pub fn load_default() -> Result<Self, ClobError> {
+        Self::load(DEFAULT_PROFILES_PATH)
+    }
+
+    /// Empty profiles that are never saved, e.g. for accounts of a
+    /// [SimulatedChain](crate::backend::SimulatedChain)
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            storage_mode: AccountStorageMode::Local,
+            accounts: BTreeMap::new(),
+            faucets: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the storage mode of accounts created from now on
+    pub fn with_storage_mode(mut self, storage_mode: AccountStorageMode) -> Self {
+        self.storage_mode = storage_mode;
+        self
+    }
+
+    /// Returns the wallet registered under `alias`
+    pub fn account(&self, alias: &str) -> Option<AccountId> {
+        self.accounts.get(alias).copied()
+    }
+
+    /// Returns the faucet registered for the token `symbol`
+    pub fn faucet(&self, symbol: &str) -> Option<AccountId> {
+        self.faucets.get(symbol).copied()
+    }
+
+    /// Iterates over the wallet aliases and their account IDs
+    pub fn accounts(&self) -> impl Iterator<Item = (&str, AccountId)> {
+        self.accounts.iter().map(|(alias, id)| (alias.as_str(), *id))
+    }
+
+    /// Iterates over the token symbols and their faucet IDs
+    pub fn faucets(&self) -> impl Iterator<Item = (&str, AccountId)> {
+        self.faucets.iter().map(|(symbol, id)| (symbol.as_str(), *id))
+    }
+
+    /// Returns the wallet registered under `alias`, creating it if needed
+    #[instrument(skip(self, backend))]
+    pub fn get_or_create_account<B: ClobBackend>(
+        &mut self,
+        backend: &mut B,
+        alias: &str,
+    ) -> Result<Account, ClobError> {
+        if let Some(account) = self.existing(backend, self.account(alias))? {
+            return Ok(account);
+        }
+
+        let account = backend.new_wallet(self.storage_mode)?;
+        info!(alias, id = account.id().to_hex(), "Created account");
+
+        self.accounts.insert(alias.to_string(), account.id());
+        self.save()?;
+        Ok(account)
+    }
+
+    /// Returns the faucet registered for `symbol`, creating it if needed
+    ///
+    /// Fails with [ClobError::Profile] if the registered faucet has other
+    /// decimals or another max supply.
+    #[instrument(skip(self, backend))]
+    pub fn get_or_create_faucet<B: ClobBackend>(
+        &mut self,
+        backend: &mut B,
+        symbol: &str,
+        decimals: u8,
+        max_supply: u64,
+    ) -> Result<Account, ClobError> {
+        if let Some(faucet) = self.existing(backend, self.faucet(symbol))? {
+            let (existing_decimals, existing_max_supply) = faucet_metadata(&faucet);
+            if (existing_decimals, existing_max_supply) != (decimals, max_supply) {
+                return Err(ClobError::Profile(format!(
+                    "faucet of {symbol} has {existing_decimals} decimals and a max supply of \
+                     {existing_max_supply}, not {decimals} and {max_supply}"
+                )));
+            }
+            return Ok(faucet);
+        }
+
+        let token_symbol = TokenSymbol::new(symbol)
+            .map_err(|err| ClobError::Profile(format!("invalid token symbol {symbol}: {err}")))?;
+        let faucet = backend.new_faucet(token_symbol, decimals, max_supply, self.storage_mode)?;
+        info!(symbol, id = faucet.id().to_hex(), "Created faucet");
+
+        self.faucets.insert(symbol.to_string(), faucet.id());
+        self.save()?;
+        Ok(faucet)
+    }
+
+    /// Loads a registered account, ignoring registrations the store lost track
+    /// of (e.g. after the sqlite database was wiped)
+    ///
+    /// Only [ClobError::NotFound] means the account is gone: any other error,
+    /// e.g. a locked database, is returned so that the registration is not
+    /// replaced by a new account.
+    fn existing<B: ClobBackend>(
+        &self,
+        backend: &B,
+        account_id: Option<AccountId>,
+    ) -> Result<Option<Account>, ClobError> {
+        let Some(account_id) = account_id else {
+            return Ok(None);
+        };
+        match backend.get_account(account_id) {
+            Ok(account) => Ok(Some(account)),
+            Err(ClobError::NotFound(what)) => {
+                warn!(id = account_id.to_hex(), %what, "Profile account missing from store");
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn save(&self) -> Result<(), ClobError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = ProfilesFile {
+            accounts: self
+                .accounts
+                .iter()
+                .map(|(alias, id)| (alias.clone(), id.to_hex()))
+                .collect(),
+            faucets: self
+                .faucets
+                .iter()
+                .map(|(symbol, id)| (symbol.clone(), id.to_hex()))
+                .collect(),
+        };
+        let contents = serde_json::to_string_pretty(&file)
+            .map_err(|err| ClobError::Profile(err.to_string()))?;
+        fs::write(path, contents).map_err(ClobError::Io)
+    }
+}
+
+fn parse_ids(ids: BTreeMap<String, String>) -> Result<BTreeMap<String, AccountId>, ClobError> {
+    ids.into_iter()
+        .map(|(name, hex)| {
+            AccountId::from_hex(&hex)
+                .map(|id| (name, id))
+                .map_err(|err| ClobError::Profile(format!("invalid account id {hex}: {err}")))
+        })
+        .collect()
+}
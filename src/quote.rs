@@ -0,0 +1,117 @@
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+
+use crate::book::OrderBook;
+use crate::market::{Markets, Price};
+use crate::order::Fill;
+
+/// Cost of taking liquidity from the book, along with the fills to submit.
+///
+/// The fills carry the note args of each order and can be handed as is to
+/// [build_fill_tx_req](crate::build_fill_tx_req).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    /// Total the taker pays
+    pub paid: FungibleAsset,
+    /// Total the taker receives
+    pub received: FungibleAsset,
+    /// Orders taken, cheapest first
+    pub fills: Vec<Fill>,
+}
+
+impl Quote {
+    /// Average price, as paid units per received unit
+    pub fn average_price(&self) -> Price {
+        Price::new(self.paid.amount(), self.received.amount())
+            .expect("a quote always receives something")
+    }
+
+    /// Price of the most expensive fill, rounding included
+    pub fn worst_price(&self) -> Price {
+        self.fills
+            .iter()
+            .filter_map(|fill| Price::new(fill.in_amount, fill.out_amount))
+            .max()
+            .expect("a quote always has fills")
+    }
+
+    /// Notes consumed by the quote
+    pub fn note_ids(&self) -> Vec<NoteId> {
+        self.fills.iter().map(|fill| fill.note_id).collect()
+    }
+}
+
+/// Quotes buying `want` with assets of `give`, cheapest orders first.
+///
+/// Orders whose minimum fill exceeds what is left to buy, or that would be left
+/// with less than a lot of their market, are skipped. The quote
+/// may receive slightly more than asked because of rounding in favour of the
+/// makers, or less if the book is too thin. Returns None if nothing can be
+/// bought.
+pub fn quote_buy(
+    book: &OrderBook,
+    markets: &Markets,
+    want: FungibleAsset,
+    give: AccountId,
+) -> Option<Quote> {
+    let mut left = want.amount();
+    let mut fills = Vec::new();
+    for order in book.offers(want.faucet_id(), give) {
+        if left == 0 {
+            break;
+        }
+        // Pay just enough for the rest, everything if the order is too small
+        let out_amount = left.min(order.offered.amount()) as u128;
+        let in_amount = (out_amount * order.requested.amount() as u128)
+            .div_ceil(order.offered.amount() as u128) as u64;
+        let fill = order.fill_for_input(in_amount);
+        if let Some(fill) = fill.filter(|fill| markets.validate_fill(order, fill).is_ok()) {
+            left = left.saturating_sub(fill.out_amount);
+            fills.push(fill);
+        }
+    }
+    build_quote(give, want.faucet_id(), fills)
+}
+
+/// Quotes selling `give` for assets of `want`, cheapest orders first.
+///
+/// Orders whose minimum fill exceeds what is left to sell, or that would be left
+/// with less than a lot of their market, are skipped. Each fill pays only
+/// what its rounded down amount received is worth, and whatever the book
+/// cannot absorb is left unpaid. Returns None if nothing can be sold.
+pub fn quote_sell(
+    book: &OrderBook,
+    markets: &Markets,
+    give: FungibleAsset,
+    want: AccountId,
+) -> Option<Quote> {
+    let mut left = give.amount();
+    let mut fills = Vec::new();
+    for order in book.offers(want, give.faucet_id()) {
+        if left == 0 {
+            break;
+        }
+        let fill = order.fill_for_input(left);
+        if let Some(mut fill) = fill.filter(|fill| markets.validate_fill(order, fill).is_ok()) {
+            // Pay no more than the rounded down amount received is worth
+            fill.in_amount = order.input_for(fill.out_amount);
+            left -= fill.in_amount;
+            fills.push(fill);
+        }
+    }
+    build_quote(give.faucet_id(), want, fills)
+}
+
+fn build_quote(give: AccountId, want: AccountId, fills: Vec<Fill>) -> Option<Quote> {
+    if fills.is_empty() {
+        return None;
+    }
+    let paid = fills.iter().map(|fill| fill.in_amount).sum();
+    let received = fills.iter().map(|fill| fill.out_amount).sum();
+    Some(Quote {
+        paid: FungibleAsset::new(give, paid).ok()?,
+        received: FungibleAsset::new(want, received).ok()?,
+        fills,
+    })
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use miden_objects::accounts::AccountDelta;
+use miden_objects::notes::{Note, NoteId};
+use miden_objects::transaction::TransactionId;
+
+use crate::limit_swap_note_script;
+
+/// Outcome of a transaction committed by the chain backend
+#[derive(Debug, Clone)]
+pub struct TransactionReceipt {
+    pub transaction_id: TransactionId,
+    /// Block in which the transaction was committed
+    pub block_num: u32,
+    /// Notes consumed by the transaction
+    pub consumed_notes: Vec<NoteId>,
+    /// P2ID notes created by the transaction, e.g. paybacks to makers
+    pub payback_notes: Vec<Note>,
+    /// Limit-swap notes created by the transaction, i.e. the clone of a
+    /// partially filled order or a newly placed order
+    pub clone_notes: Vec<Note>,
+    /// Changes applied to the executing account
+    pub account_delta: AccountDelta,
+    /// Time between submitting the transaction and observing its commit
+    pub commit_latency: Duration,
+    /// Time spent proving the transaction locally
+    pub proving_time: Duration,
+}
+
+impl TransactionReceipt {
+    /// Splits created notes into P2ID paybacks and limit-swap clones
+    pub fn split_created_notes(created_notes: Vec<Note>) -> (Vec<Note>, Vec<Note>) {
+        let limit_swap_script_hash = limit_swap_note_script().hash();
+        created_notes.into_iter().partition(|note| note.script().hash() != limit_swap_script_hash)
+    }
+
+    /// All notes created by the transaction
+    pub fn created_notes(&self) -> impl Iterator<Item = &Note> {
+        self.payback_notes.iter().chain(self.clone_notes.iter())
+    }
+}
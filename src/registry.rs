@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use serde::{Deserialize, Serialize};
+use vm_processor::Digest;
+
+use crate::errors::ClobError;
+use crate::order::LimitOrder;
+
+/// Where the order registry is kept, next to the client sqlite store
+pub const DEFAULT_REGISTRY_PATH: &str = "./db/orders.json";
+
+/// Lifecycle of an order placed by a local maker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// The note is resting on the book, as far as we know
+    Open,
+    /// The note was consumed by a taker
+    Filled,
+    /// The maker reclaimed the note
+    Cancelled,
+    /// The maker reclaimed the note and placed another one in the same
+    /// transaction, see [OrderRecord::replaced_by]
+    Replaced,
+}
+
+/// An order placed by a local maker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderRecord {
+    pub note_id: NoteId,
+    pub maker: AccountId,
+    pub offered: FungibleAsset,
+    pub requested: FungibleAsset,
+    pub status: OrderStatus,
+    /// The order this one was placed in place of
+    pub replaces: Option<NoteId>,
+    /// The order placed in place of this one
+    pub replaced_by: Option<NoteId>,
+}
+
+/// On-disk format of an order record, IDs are hex encoded
+#[derive(Debug, Serialize, Deserialize)]
+struct OrderEntry {
+    maker: String,
+    offered_faucet: String,
+    offered_amount: u64,
+    requested_faucet: String,
+    requested_amount: u64,
+    status: OrderStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replaces: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replaced_by: Option<String>,
+}
+
+/// Orders placed by local makers, kept across runs.
+///
+/// The book only knows about resting notes. The registry remembers what
+/// became of the notes we created, and which note replaced which.
+#[derive(Debug, Clone)]
+pub struct OrderRegistry {
+    /// File the registry is saved to, if any
+    path: Option<PathBuf>,
+    orders: BTreeMap<NoteId, OrderRecord>,
+}
+
+impl OrderRegistry {
+    /// Loads the registry at `path`, starting empty if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ClobError> {
+        let path = path.as_ref().to_path_buf();
+        let entries: BTreeMap<String, OrderEntry> = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|err| ClobError::Registry(format!("{}: {err}", path.display())))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(ClobError::Io(err)),
+        };
+
+        let orders = entries
+            .into_iter()
+            .map(|(note_id, entry)| {
+                let record = entry.decode(parse_note_id(&note_id)?)?;
+                Ok((record.note_id, record))
+            })
+            .collect::<Result<_, ClobError>>()?;
+
+        Ok(Self {
+            path: Some(path),
+            orders,
+        })
+    }
+
+    /// Loads the registry at [DEFAULT_REGISTRY_PATH]
+    pub fn load_default() -> Result<Self, ClobError> {
+        Self::load(DEFAULT_REGISTRY_PATH)
+    }
+
+    /// An empty registry that is never saved
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            orders: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, note_id: &NoteId) -> Option<&OrderRecord> {
+        self.orders.get(note_id)
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &OrderRecord> {
+        self.orders.values()
+    }
+
+    /// Iterates over the orders of `maker` that are still open
+    pub fn open_orders(&self, maker: AccountId) -> impl Iterator<Item = &OrderRecord> {
+        self.orders
+            .values()
+            .filter(move |record| record.maker == maker && record.status == OrderStatus::Open)
+    }
+
+    /// Records a newly placed order as open
+    pub fn record_placed(&mut self, order: &LimitOrder) -> Result<(), ClobError> {
+        self.orders.insert(order.note_id, OrderRecord::open(order));
+        self.save()
+    }
+
+    /// Records that `new` was placed by consuming `old`, linking both orders
+    pub fn record_replaced(&mut self, old: &LimitOrder, new: &LimitOrder) -> Result<(), ClobError> {
+        let old_record = self.orders.entry(old.note_id).or_insert_with(|| OrderRecord::open(old));
+        old_record.status = OrderStatus::Replaced;
+        old_record.replaced_by = Some(new.note_id);
+
+        let new_record = OrderRecord {
+            replaces: Some(old.note_id),
+            ..OrderRecord::open(new)
+        };
+        self.orders.insert(new.note_id, new_record);
+        self.save()
+    }
+
+    /// Updates the status of a known order, ignoring notes we did not place
+    pub fn set_status(&mut self, note_id: &NoteId, status: OrderStatus) -> Result<(), ClobError> {
+        match self.orders.get_mut(note_id) {
+            Some(record) => {
+                record.status = status;
+                self.save()
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn save(&self) -> Result<(), ClobError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let entries = self
+            .orders
+            .values()
+            .map(|record| (record.note_id.to_hex(), OrderEntry::encode(record)))
+            .collect::<BTreeMap<_, _>>();
+        let contents = serde_json::to_string_pretty(&entries)
+            .map_err(|err| ClobError::Registry(err.to_string()))?;
+        fs::write(path, contents).map_err(ClobError::Io)
+    }
+}
+
+impl OrderRecord {
+    fn open(order: &LimitOrder) -> Self {
+        Self {
+            note_id: order.note_id,
+            maker: order.maker,
+            offered: order.offered,
+            requested: order.requested,
+            status: OrderStatus::Open,
+            replaces: None,
+            replaced_by: None,
+        }
+    }
+}
+
+impl OrderEntry {
+    fn encode(record: &OrderRecord) -> Self {
+        Self {
+            maker: record.maker.to_hex(),
+            offered_faucet: record.offered.faucet_id().to_hex(),
+            offered_amount: record.offered.amount(),
+            requested_faucet: record.requested.faucet_id().to_hex(),
+            requested_amount: record.requested.amount(),
+            status: record.status,
+            replaces: record.replaces.map(|id| id.to_hex()),
+            replaced_by: record.replaced_by.map(|id| id.to_hex()),
+        }
+    }
+
+    fn decode(self, note_id: NoteId) -> Result<OrderRecord, ClobError> {
+        Ok(OrderRecord {
+            note_id,
+            maker: parse_account_id(&self.maker)?,
+            offered: parse_asset(&self.offered_faucet, self.offered_amount)?,
+            requested: parse_asset(&self.requested_faucet, self.requested_amount)?,
+            status: self.status,
+            replaces: self.replaces.as_deref().map(parse_note_id).transpose()?,
+            replaced_by: self.replaced_by.as_deref().map(parse_note_id).transpose()?,
+        })
+    }
+}
+
+fn parse_note_id(hex: &str) -> Result<NoteId, ClobError> {
+    Digest::try_from(hex)
+        .map(NoteId::from)
+        .map_err(|err| ClobError::Registry(format!("invalid note id {hex}: {err}")))
+}
+
+fn parse_account_id(hex: &str) -> Result<AccountId, ClobError> {
+    AccountId::from_hex(hex)
+        .map_err(|err| ClobError::Registry(format!("invalid account id {hex}: {err}")))
+}
+
+fn parse_asset(faucet_hex: &str, amount: u64) -> Result<FungibleAsset, ClobError> {
+    FungibleAsset::new(parse_account_id(faucet_hex)?, amount)
+        .map_err(|err| ClobError::Registry(format!("invalid asset of {faucet_hex}: {err}")))
+}
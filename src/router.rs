@@ -0,0 +1,233 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+
+use miden_objects::accounts::{Account, AccountId};
+use miden_objects::assets::{AssetVault, FungibleAsset};
+use tracing::{info, instrument};
+
+use crate::backend::{execute_on, ClobBackend};
+use crate::book::OrderBook;
+use crate::build_fill_tx_req;
+use crate::errors::ClobError;
+use crate::market::{Markets, Price};
+use crate::order::Fill;
+use crate::quote::{quote_sell, Quote};
+use crate::receipt::TransactionReceipt;
+
+/// Longest route searched by [swap]
+pub const DEFAULT_MAX_HOPS: usize = 3;
+
+/// A quote for swapping through one or more intermediate assets, settled in a
+/// single transaction. Each hop is a [Quote] against the orders of one pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub hops: Vec<Quote>,
+}
+
+impl Route {
+    /// What the taker pays on the first leg
+    pub fn paid(&self) -> FungibleAsset {
+        self.hops[0].paid
+    }
+
+    /// What the taker receives on the last leg
+    pub fn received(&self) -> FungibleAsset {
+        self.hops[self.hops.len() - 1].received
+    }
+
+    /// Overall price of the route, as paid units per received unit
+    pub fn price(&self) -> Price {
+        Price::new(self.paid().amount(), self.received().amount())
+            .expect("a route always receives something")
+    }
+
+    /// Fills of every leg, to be consumed together
+    pub fn fills(&self) -> Vec<Fill> {
+        self.hops.iter().flat_map(|hop| hop.fills.iter().copied()).collect()
+    }
+
+    /// Returns what the taker must hold on top of [Route::paid] for the route
+    /// to settle.
+    ///
+    /// Notes are consumed in [NoteId](miden_objects::notes::NoteId) order, not
+    /// leg by leg, so a later leg may need to be paid before the earlier leg
+    /// yielding its asset has been consumed.
+    pub fn inventory_needed(&self) -> Vec<FungibleAsset> {
+        let mut legs = self
+            .hops
+            .iter()
+            .flat_map(|hop| {
+                let (paid, received) = (hop.paid.faucet_id(), hop.received.faucet_id());
+                hop.fills.iter().map(move |fill| (fill, paid, received))
+            })
+            .collect::<Vec<_>>();
+        legs.sort_by_key(|(fill, ..)| fill.note_id);
+
+        let mut vault = BTreeMap::from([(self.paid().faucet_id(), self.paid().amount())]);
+        let mut missing: BTreeMap<AccountId, u64> = BTreeMap::new();
+        for (fill, paid, received) in legs {
+            let balance = vault.entry(paid).or_default();
+            if *balance < fill.in_amount {
+                *missing.entry(paid).or_default() += fill.in_amount - *balance;
+                *balance = 0;
+            } else {
+                *balance -= fill.in_amount;
+            }
+            *vault.entry(received).or_default() += fill.out_amount;
+        }
+
+        missing
+            .into_iter()
+            .map(|(faucet_id, amount)| FungibleAsset::new(faucet_id, amount).unwrap())
+            .collect()
+    }
+
+    /// Fails with [ClobError::InsufficientFunds] unless `vault` holds both
+    /// [Route::paid] and the [Route::inventory_needed] on top of it
+    pub fn check_funds(&self, vault: &AssetVault) -> Result<(), ClobError> {
+        let mut required = BTreeMap::from([(self.paid().faucet_id(), self.paid().amount())]);
+        for asset in self.inventory_needed() {
+            *required.entry(asset.faucet_id()).or_default() += asset.amount();
+        }
+
+        let missing = required
+            .into_iter()
+            .filter_map(|(faucet_id, amount)| {
+                let balance = vault.get_balance(faucet_id).unwrap_or(0);
+                (balance < amount).then(|| FungibleAsset::new(faucet_id, amount - balance).unwrap())
+            })
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ClobError::InsufficientFunds(missing))
+        }
+    }
+}
+
+/// Finds the route of at most `max_hops` legs giving the most of `want` in
+/// exchange for `give`, preferring shorter routes on ties.
+///
+/// Each leg sells everything received from the previous one with [quote_sell],
+/// and an intermediate asset is only ever visited once per route.
+pub fn find_route(
+    book: &OrderBook,
+    markets: &Markets,
+    give: FungibleAsset,
+    want: AccountId,
+    max_hops: usize,
+) -> Option<Route> {
+    let mut best = None;
+    let mut visited = BTreeSet::from([give.faucet_id()]);
+    let search = RouteSearch {
+        book,
+        markets,
+        want,
+    };
+    search.search(give, max_hops, &mut Vec::new(), &mut visited, &mut best);
+    best
+}
+
+/// What [find_route] searches and where it wants to end up
+struct RouteSearch<'a> {
+    book: &'a OrderBook,
+    markets: &'a Markets,
+    want: AccountId,
+}
+
+impl RouteSearch<'_> {
+    /// Depth-first search over the assets reachable from `holding`
+    fn search(
+        &self,
+        holding: FungibleAsset,
+        hops_left: usize,
+        path: &mut Vec<Quote>,
+        visited: &mut BTreeSet<AccountId>,
+        best: &mut Option<Route>,
+    ) {
+        let (book, want) = (self.book, self.want);
+        if hops_left == 0 {
+            return;
+        }
+
+        let next_assets = book
+            .orders()
+            .filter(|order| order.requested.faucet_id() == holding.faucet_id())
+            .map(|order| order.offered.faucet_id())
+            .collect::<BTreeSet<_>>();
+
+        for next in next_assets {
+            if visited.contains(&next) {
+                continue;
+            }
+            let Some(hop) = quote_sell(book, self.markets, holding, next) else {
+                continue;
+            };
+            let received = hop.received;
+            path.push(hop);
+
+            if next == want {
+                let rank = |route_len: usize, received: u64| (received, Reverse(route_len));
+                let is_better = match best {
+                    Some(route) => {
+                        rank(path.len(), received.amount())
+                            > rank(route.hops.len(), route.received().amount())
+                    }
+                    None => true,
+                };
+                if is_better {
+                    *best = Some(Route { hops: path.clone() });
+                }
+            } else {
+                visited.insert(next);
+                self.search(received, hops_left - 1, path, visited, best);
+                visited.remove(&next);
+            }
+
+            path.pop();
+        }
+    }
+}
+
+/// Consumes every order of `route` in a single transaction of `taker`, with the
+/// intermediate assets passing through its vault.
+///
+/// Fails with [ClobError::InsufficientFunds] before executing anything if the
+/// taker does not hold what the route pays plus [Route::inventory_needed].
+#[instrument(skip_all, fields(taker = taker.id().to_hex(), hops = route.hops.len()))]
+pub async fn execute_route<B: ClobBackend>(
+    backend: &mut B,
+    taker: &Account,
+    route: &Route,
+) -> Result<TransactionReceipt, ClobError> {
+    for (index, hop) in route.hops.iter().enumerate() {
+        info!(
+            hop = index,
+            paid = ?hop.paid,
+            received = ?hop.received,
+            price = %hop.average_price(),
+            "Route leg"
+        );
+    }
+    route.check_funds(backend.get_account(taker.id())?.vault())?;
+
+    let tx_req = build_fill_tx_req(backend, taker.id(), &route.fills())?;
+    execute_on(backend, tx_req).await
+}
+
+/// Swaps `give` for as much of `want` as the best route of at most
+/// [DEFAULT_MAX_HOPS] legs yields
+pub async fn swap<B: ClobBackend>(
+    backend: &mut B,
+    book: &mut OrderBook,
+    markets: &Markets,
+    taker: &Account,
+    give: FungibleAsset,
+    want: AccountId,
+) -> Result<(Route, TransactionReceipt), ClobError> {
+    book.refresh(backend).await?;
+    let route =
+        find_route(book, markets, give, want, DEFAULT_MAX_HOPS).ok_or(ClobError::NoLiquidity)?;
+    let receipt = execute_route(backend, taker, &route).await?;
+    Ok((route, receipt))
+}
@@ -0,0 +1,566 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument, warn};
+
+use crate::backend::{ClobBackend, NodeBackend, SimulatedChain};
+use crate::clob::{Clob, OrderInput};
+use crate::config::{ClobConfig, MarketConfig};
+use crate::errors::ClobError;
+use crate::order::{Fill, NewOrder};
+use crate::profiles::AccountProfiles;
+use crate::registry::OrderRegistry;
+use crate::tokens::TokenRegistry;
+
+/// Max supply of scenario faucets that do not set one, in base units
+pub const DEFAULT_MAX_SUPPLY: u64 = 100_000_000_000;
+
+/// A reproducible trading run: faucets, actors and markets to set up, steps to
+/// play in order and checks on the final state.
+///
+/// Amounts are decimal with a token symbol, e.g. "1.5 ETH", and actors are
+/// referred to by their alias.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub faucets: Vec<FaucetSpec>,
+    /// Aliases of the wallets taking part
+    #[serde(default)]
+    pub actors: Vec<String>,
+    #[serde(default)]
+    pub markets: Vec<MarketConfig>,
+    #[serde(default)]
+    pub steps: Vec<StepSpec>,
+    #[serde(default)]
+    pub expect: Vec<Check>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FaucetSpec {
+    pub symbol: String,
+    pub decimals: u8,
+    #[serde(default = "default_max_supply")]
+    pub max_supply: u64,
+}
+
+fn default_max_supply() -> u64 {
+    DEFAULT_MAX_SUPPLY
+}
+
+/// A step and the error it is expected to fail with, if any
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StepSpec {
+    #[serde(flatten)]
+    pub step: Step,
+    /// The step passes if it fails with an error containing this text
+    #[serde(default)]
+    pub expect_error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Step {
+    Mint {
+        account: String,
+        amounts: Vec<String>,
+    },
+    Place {
+        maker: String,
+        #[serde(flatten)]
+        order: OrderInput,
+        /// Name later steps use to refer to the order
+        #[serde(default)]
+        label: Option<String>,
+    },
+    Cancel {
+        maker: String,
+        /// Label of the order
+        order: String,
+    },
+    Take {
+        taker: String,
+        /// Most the taker pays
+        give: String,
+        /// Symbol of the token the taker receives
+        want: String,
+    },
+    /// Consumes the notes sent to the account, i.e. the paybacks of its
+    /// filled orders
+    Consume {
+        account: String,
+    },
+    Sync,
+}
+
+/// A check on the state reached at the end of a scenario
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+pub enum Check {
+    /// The account holds exactly this amount of the token
+    Balance { account: String, amount: String },
+    /// Number of orders resting on the book, of one maker or of everyone
+    RestingOrders {
+        #[serde(default)]
+        maker: Option<String>,
+        count: usize,
+    },
+    /// Number of notes sent to the account and not consumed yet
+    PendingNotes { account: String, count: usize },
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ClobError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(ClobError::Io)?;
+        Self::parse(&contents)
+            .map_err(|err| ClobError::Config(format!("{}: {err}", path.display())))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ClobError> {
+        toml::from_str(contents).map_err(|err| ClobError::Config(err.to_string()))
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Mint { account, amounts } => {
+                write!(f, "mint {} to {account}", amounts.join(", "))
+            }
+            Step::Place { maker, order, .. } => {
+                write!(f, "{maker} offers {} for {}", order.offered, order.requested)
+            }
+            Step::Cancel { maker, order } => write!(f, "{maker} cancels {order}"),
+            Step::Take { taker, give, want } => write!(f, "{taker} gives up to {give} for {want}"),
+            Step::Consume { account } => write!(f, "{account} consumes its notes"),
+            Step::Sync => write!(f, "sync"),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Balance { account, amount } => write!(f, "{account} holds {amount}"),
+            Check::RestingOrders {
+                maker: Some(maker),
+                count,
+            } => write!(f, "{maker} has {count} resting orders"),
+            Check::RestingOrders { maker: None, count } => write!(f, "{count} resting orders"),
+            Check::PendingNotes { account, count } => {
+                write!(f, "{account} has {count} pending notes")
+            }
+        }
+    }
+}
+
+/// What a scenario runs against
+#[tonic::async_trait]
+pub trait ScenarioDriver: Send {
+    /// Tokens created so far, used to parse amounts
+    fn tokens(&self) -> &TokenRegistry;
+
+    async fn create_faucet(&mut self, faucet: &FaucetSpec) -> Result<(), ClobError>;
+
+    async fn create_account(&mut self, alias: &str) -> Result<(), ClobError>;
+
+    async fn create_markets(&mut self, markets: &[MarketConfig]) -> Result<(), ClobError>;
+
+    async fn mint(&mut self, account: &str, assets: Vec<FungibleAsset>) -> Result<(), ClobError>;
+
+    /// Places an order, returning the ID of its note
+    async fn place_order(&mut self, maker: &str, order: &NewOrder) -> Result<NoteId, ClobError>;
+
+    async fn cancel_order(&mut self, maker: &str, note_id: &NoteId) -> Result<(), ClobError>;
+
+    /// Takes the best order offering assets of `want` for at most `give`
+    async fn take(
+        &mut self,
+        taker: &str,
+        want: AccountId,
+        give: FungibleAsset,
+    ) -> Result<Fill, ClobError>;
+
+    /// Consumes the notes sent to `account`, returning how many there were
+    async fn consume_notes(&mut self, account: &str) -> Result<usize, ClobError>;
+
+    async fn sync(&mut self) -> Result<(), ClobError>;
+
+    /// Balance of `account` in the token of `faucet_id`
+    fn balance(&self, account: &str, faucet_id: AccountId) -> Result<u64, ClobError>;
+
+    /// Number of resting orders, of `maker` or of everyone
+    fn resting_orders(&self, maker: Option<&str>) -> Result<usize, ClobError>;
+
+    /// Number of notes sent to `account` and not consumed yet
+    fn pending_notes(&self, account: &str) -> Result<usize, ClobError>;
+}
+
+/// Outcome of a step or a check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    pub description: String,
+    pub passed: bool,
+    /// What happened, or why it failed
+    pub detail: String,
+}
+
+/// Result of running a scenario
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub steps: Vec<Outcome>,
+    /// Empty if a step failed, as the final state is then meaningless
+    pub checks: Vec<Outcome>,
+}
+
+impl ScenarioReport {
+    pub fn passed(&self) -> bool {
+        let steps_passed = self.steps.iter().all(|outcome| outcome.passed);
+        steps_passed && self.checks.iter().all(|outcome| outcome.passed)
+    }
+
+    /// Records the outcome of a step, returning false if the run must stop
+    fn record_step(
+        &mut self,
+        description: String,
+        result: Result<String, ClobError>,
+        expect_error: Option<&str>,
+    ) -> bool {
+        let (passed, detail) = match (result, expect_error) {
+            (Ok(detail), None) => (true, detail),
+            (Ok(_), Some(expected)) => (false, format!("succeeded, expected an error: {expected}")),
+            (Err(err), Some(expected)) if err.to_string().contains(expected) => {
+                (true, format!("failed as expected: {err}"))
+            }
+            (Err(err), _) => (false, err.to_string()),
+        };
+        if !passed {
+            warn!(step = %description, %detail, "Scenario step failed");
+        }
+        self.steps.push(Outcome {
+            description,
+            passed,
+            detail,
+        });
+        passed
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed() { "PASS" } else { "FAIL" };
+        writeln!(f, "{verdict} {}", self.name)?;
+        for (kind, outcomes) in [("step", &self.steps), ("check", &self.checks)] {
+            for outcome in outcomes {
+                let mark = if outcome.passed { "ok" } else { "FAILED" };
+                writeln!(f, "  {kind} {}: {mark} ({})", outcome.description, outcome.detail)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plays `scenario` against `driver`, stopping at the first step that does
+/// not go as expected.
+///
+/// Faucets, actors and markets are set up first, each as one step. The checks
+/// run on the state reached after a final sync.
+#[instrument(skip_all, fields(scenario = %scenario.name))]
+pub async fn run_scenario<D: ScenarioDriver>(
+    driver: &mut D,
+    scenario: &Scenario,
+) -> ScenarioReport {
+    let mut report = ScenarioReport {
+        name: scenario.name.clone(),
+        steps: Vec::new(),
+        checks: Vec::new(),
+    };
+
+    let mut runner = Runner {
+        driver,
+        labels: BTreeMap::new(),
+    };
+    for faucet in &scenario.faucets {
+        let result = runner.driver.create_faucet(faucet).await.map(|_| String::new());
+        if !report.record_step(format!("create faucet {}", faucet.symbol), result, None) {
+            return report;
+        }
+    }
+    for alias in &scenario.actors {
+        let result = runner.driver.create_account(alias).await.map(|_| String::new());
+        if !report.record_step(format!("create account {alias}"), result, None) {
+            return report;
+        }
+    }
+    if !scenario.markets.is_empty() {
+        let result = runner.driver.create_markets(&scenario.markets).await.map(|_| String::new());
+        if !report.record_step("open markets".to_string(), result, None) {
+            return report;
+        }
+    }
+
+    for spec in &scenario.steps {
+        let result = runner.run(&spec.step).await;
+        if !report.record_step(spec.step.to_string(), result, spec.expect_error.as_deref()) {
+            return report;
+        }
+    }
+
+    if let Err(err) = runner.driver.sync().await {
+        report.record_step("final sync".to_string(), Err(err), None);
+        return report;
+    }
+    for check in &scenario.expect {
+        let outcome = match runner.check(check) {
+            Ok(detail) => Outcome {
+                description: check.to_string(),
+                passed: true,
+                detail,
+            },
+            Err(detail) => Outcome {
+                description: check.to_string(),
+                passed: false,
+                detail,
+            },
+        };
+        report.checks.push(outcome);
+    }
+
+    info!(passed = report.passed(), "Scenario done");
+    report
+}
+
+/// Plays steps against a driver, remembering the labelled orders
+struct Runner<'a, D> {
+    driver: &'a mut D,
+    labels: BTreeMap<String, NoteId>,
+}
+
+impl<D: ScenarioDriver> Runner<'_, D> {
+    async fn run(&mut self, step: &Step) -> Result<String, ClobError> {
+        match step {
+            Step::Mint { account, amounts } => {
+                let assets = amounts
+                    .iter()
+                    .map(|amount| self.driver.tokens().parse_amount(amount))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.driver.mint(account, assets).await?;
+                Ok(String::new())
+            }
+            Step::Place {
+                maker,
+                order,
+                label,
+            } => {
+                let order = order.to_order(self.driver.tokens())?;
+                let note_id = self.driver.place_order(maker, &order).await?;
+                if let Some(label) = label {
+                    self.labels.insert(label.clone(), note_id);
+                }
+                Ok(format!("note {}", note_id.to_hex()))
+            }
+            Step::Cancel { maker, order } => {
+                let note_id = *self
+                    .labels
+                    .get(order)
+                    .ok_or_else(|| ClobError::NotFound(format!("order labelled {order}")))?;
+                self.driver.cancel_order(maker, &note_id).await?;
+                Ok(String::new())
+            }
+            Step::Take { taker, give, want } => {
+                let tokens = self.driver.tokens();
+                let give = tokens.parse_amount(give)?;
+                let want = tokens.faucet_id(want)?;
+                let fill = self.driver.take(taker, want, give).await?;
+                Ok(format!(
+                    "paid {} for {} from note {}",
+                    fill.in_amount,
+                    fill.out_amount,
+                    fill.note_id.to_hex()
+                ))
+            }
+            Step::Consume { account } => {
+                let count = self.driver.consume_notes(account).await?;
+                Ok(format!("{count} notes"))
+            }
+            Step::Sync => self.driver.sync().await.map(|_| String::new()),
+        }
+    }
+
+    /// Returns what was found, as the error if it is not what was expected
+    fn check(&self, check: &Check) -> Result<String, String> {
+        let (expected, found) = match check {
+            Check::Balance { account, amount } => {
+                let tokens = self.driver.tokens();
+                let asset = tokens.parse_amount(amount).map_err(|err| err.to_string())?;
+                let balance = self
+                    .driver
+                    .balance(account, asset.faucet_id())
+                    .map_err(|err| err.to_string())?;
+                let found = FungibleAsset::new(asset.faucet_id(), balance)
+                    .map(|balance| tokens.format_amount(&balance))
+                    .unwrap_or_else(|_| balance.to_string());
+                (asset.amount() == balance, found)
+            }
+            Check::RestingOrders { maker, count } => {
+                let found =
+                    self.driver.resting_orders(maker.as_deref()).map_err(|err| err.to_string())?;
+                (found == *count, format!("{found} orders"))
+            }
+            Check::PendingNotes { account, count } => {
+                let found = self.driver.pending_notes(account).map_err(|err| err.to_string())?;
+                (found == *count, format!("{found} notes"))
+            }
+        };
+        if expected {
+            Ok(found)
+        } else {
+            Err(format!("found {found}"))
+        }
+    }
+}
+
+/// Runs scenarios through a [Clob], against the node or a [SimulatedChain]
+pub struct ClobDriver<B: ClobBackend = NodeBackend> {
+    clob: Clob<B>,
+    /// Paybacks of filled orders, per maker alias
+    pending: BTreeMap<String, Vec<NoteId>>,
+}
+
+impl ClobDriver<SimulatedChain> {
+    /// Runs scenarios in memory, on a chain of their own
+    pub fn simulated() -> Result<Self, ClobError> {
+        let clob = Clob::new(
+            SimulatedChain::new(),
+            AccountProfiles::in_memory(),
+            OrderRegistry::in_memory(),
+            &ClobConfig::default(),
+        )?;
+        Ok(Self::new(clob))
+    }
+}
+
+impl<B: ClobBackend> ClobDriver<B> {
+    pub fn new(clob: Clob<B>) -> Self {
+        Self {
+            clob,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    fn alias_of(&self, account_id: AccountId) -> Option<String> {
+        self.clob
+            .profiles()
+            .accounts()
+            .find(|(_, id)| *id == account_id)
+            .map(|(alias, _)| alias.to_string())
+    }
+}
+
+#[tonic::async_trait]
+impl<B: ClobBackend> ScenarioDriver for ClobDriver<B> {
+    fn tokens(&self) -> &TokenRegistry {
+        self.clob.tokens()
+    }
+
+    async fn create_faucet(&mut self, faucet: &FaucetSpec) -> Result<(), ClobError> {
+        self.clob.new_faucet(&faucet.symbol, faucet.decimals, faucet.max_supply)?;
+        Ok(())
+    }
+
+    async fn create_account(&mut self, alias: &str) -> Result<(), ClobError> {
+        self.clob.new_account(alias)?;
+        Ok(())
+    }
+
+    async fn create_markets(&mut self, markets: &[MarketConfig]) -> Result<(), ClobError> {
+        self.clob.add_markets(markets)
+    }
+
+    async fn mint(&mut self, account: &str, assets: Vec<FungibleAsset>) -> Result<(), ClobError> {
+        self.clob.mint(account, assets).await?.into_result()?;
+        Ok(())
+    }
+
+    async fn place_order(&mut self, maker: &str, order: &NewOrder) -> Result<NoteId, ClobError> {
+        let receipt = self.clob.place_order(maker, order).await?;
+        receipt
+            .clone_notes
+            .first()
+            .map(|note| note.id())
+            .ok_or_else(|| ClobError::NotFound("note of the placed order".to_string()))
+    }
+
+    async fn cancel_order(&mut self, maker: &str, note_id: &NoteId) -> Result<(), ClobError> {
+        self.clob.cancel_order(maker, note_id).await?;
+        Ok(())
+    }
+
+    async fn take(
+        &mut self,
+        taker: &str,
+        want: AccountId,
+        give: FungibleAsset,
+    ) -> Result<Fill, ClobError> {
+        let (fill, receipt) = self.clob.take(taker, want, give).await?;
+        let maker = self.clob.registry().get(&fill.note_id).map(|record| record.maker);
+        match maker.and_then(|maker| self.alias_of(maker)) {
+            Some(alias) => self
+                .pending
+                .entry(alias)
+                .or_default()
+                .extend(receipt.payback_notes.iter().map(|note| note.id())),
+            None => warn!(note = fill.note_id.to_hex(), "Payback of an unknown maker"),
+        }
+        Ok(fill)
+    }
+
+    async fn consume_notes(&mut self, account: &str) -> Result<usize, ClobError> {
+        let note_ids = self.pending.remove(account).unwrap_or_default();
+        if note_ids.is_empty() {
+            return Ok(0);
+        }
+        let count = note_ids.len();
+        self.clob.consume_notes(account, note_ids).await?;
+        Ok(count)
+    }
+
+    async fn sync(&mut self) -> Result<(), ClobError> {
+        self.clob.sync().await
+    }
+
+    fn balance(&self, account: &str, faucet_id: AccountId) -> Result<u64, ClobError> {
+        let account = self.clob.account(account)?;
+        Ok(account.vault().get_balance(faucet_id).unwrap_or(0))
+    }
+
+    fn resting_orders(&self, maker: Option<&str>) -> Result<usize, ClobError> {
+        let maker = match maker {
+            Some(alias) => Some(self.clob.account(alias)?.id()),
+            None => None,
+        };
+        Ok(self
+            .clob
+            .book()
+            .orders()
+            .filter(|order| match maker {
+                Some(maker) => order.maker == maker,
+                None => true,
+            })
+            .count())
+    }
+
+    fn pending_notes(&self, account: &str) -> Result<usize, ClobError> {
+        Ok(self.pending.get(account).map_or(0, Vec::len))
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn};
+
+use crate::backend::{ClobBackend, NodeBackend};
+use crate::clob::{parse_note_id, Clob, ClobView, OrderInput};
+use crate::depth::{Depth, DepthUpdate, Level};
+use crate::errors::ClobError;
+use crate::feed::{FeedEvent, MarketFeed, Subscription};
+use crate::maker::CancelReport;
+use crate::market::Pair;
+use crate::order::Fill;
+use crate::receipt::TransactionReceipt;
+use crate::registry::{OrderRecord, OrderStatus};
+use crate::tokens::TokenRegistry;
+
+/// Address the HTTP API listens on by default
+pub const DEFAULT_HTTP_ADDR: &str = "127.0.0.1:3000";
+
+/// Number of levels per side returned by the book endpoint by default
+const DEFAULT_DEPTH_LEVELS: usize = 10;
+
+/// Delay between two syncs of the feed
+pub const FEED_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Events buffered per feed subscriber before it starts missing some
+const FEED_CAPACITY: usize = 1024;
+
+/// The CLOB shared by all requests.
+///
+/// Requests submitting transactions are served one at a time, as they all go
+/// through the same client store, and hold the lock until their transaction
+/// is committed. Reads do not take it, see [AppState::view].
+pub type SharedClob<B = NodeBackend> = Arc<Mutex<Clob<B>>>;
+
+/// State shared by the handlers
+pub struct AppState<B: ClobBackend = NodeBackend> {
+    pub clob: SharedClob<B>,
+    /// What reads are served from, so that they never wait for the lock
+    pub view: Arc<RwLock<ClobView>>,
+    /// Depth of the markets as of the last events sent to `feed`
+    pub market: Arc<RwLock<MarketFeed>>,
+    pub feed: broadcast::Sender<FeedEvent>,
+}
+
+impl<B: ClobBackend> Clone for AppState<B> {
+    fn clone(&self) -> Self {
+        Self {
+            clob: self.clob.clone(),
+            view: self.view.clone(),
+            market: self.market.clone(),
+            feed: self.feed.clone(),
+        }
+    }
+}
+
+impl<B: ClobBackend> AppState<B> {
+    pub fn new(clob: Clob<B>) -> Result<Self, ClobError> {
+        let (feed, _) = broadcast::channel(FEED_CAPACITY);
+        let view = clob.view()?;
+        let market = MarketFeed::new(view.markets.iter().map(|market| market.pair));
+        Ok(Self {
+            view: Arc::new(RwLock::new(view)),
+            market: Arc::new(RwLock::new(market)),
+            clob: Arc::new(Mutex::new(clob)),
+            feed,
+        })
+    }
+
+    /// Returns a copy of the view reads are served from
+    pub fn view(&self) -> ClobView {
+        self.view.read().expect("view lock poisoned").clone()
+    }
+
+    /// Replaces the view by the current state of `clob`
+    pub fn publish(&self, clob: &Clob<B>) {
+        match clob.view() {
+            Ok(view) => *self.view.write().expect("view lock poisoned") = view,
+            Err(err) => warn!(%err, "Could not refresh the view"),
+        }
+    }
+
+    /// Syncs `clob` after a write and publishes its state
+    async fn refresh(&self, clob: &mut Clob<B>) {
+        if let Err(err) = clob.sync().await {
+            warn!(%err, "Sync after write failed");
+        }
+        self.publish(clob);
+    }
+}
+
+/// Serves the HTTP API on `addr` until the server fails, feeding WebSocket
+/// subscribers from a background sync loop
+pub async fn serve<B: ClobBackend + 'static>(
+    addr: SocketAddr,
+    clob: Clob<B>,
+) -> Result<(), ClobError> {
+    let state = AppState::new(clob)?;
+    tokio::spawn(run_feed(state.clone(), FEED_INTERVAL));
+
+    info!(%addr, "Serving HTTP API");
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .await
+        .map_err(|err| ClobError::Io(std::io::Error::other(err)))
+}
+
+/// Routes of the HTTP API. Amounts are decimal strings with a token symbol,
+/// e.g. "1.5 ETH", and accounts are referred to by their profile alias.
+///
+/// Reads are served from [AppState::view], as of the last sync of the feed or
+/// the last write.
+pub fn router<B: ClobBackend + 'static>(state: AppState<B>) -> Router {
+    Router::new()
+        .route("/orders", post(place_order).get(list_orders).delete(cancel_all))
+        .route("/orders/:note_id", get(get_order).put(replace_order).delete(cancel_order))
+        .route("/take", post(take))
+        .route("/book/:base/:quote", get(book))
+        .route("/balances/:account", get(balances))
+        .route("/feed", get(feed))
+        .with_state(state)
+}
+
+/// Syncs the CLOB every `interval` and publishes what changed to the feed
+///
+/// Subscribers taking a snapshot of [AppState::market] before subscribing get
+/// every event following it.
+pub async fn run_feed<B: ClobBackend>(state: AppState<B>, interval: Duration) {
+    loop {
+        let events = {
+            let mut clob = state.clob.lock().await;
+            let events = match clob.sync().await.and_then(|_| clob.known_recipients()) {
+                Ok(paybacks) => {
+                    let mut market = state.market.write().expect("market feed lock poisoned");
+                    market.update(clob.book(), clob.registry(), &paybacks)
+                }
+                Err(err) => {
+                    warn!(%err, "Feed sync failed");
+                    Vec::new()
+                }
+            };
+            state.publish(&clob);
+            events
+        };
+
+        for event in events {
+            // Sending only fails when nobody is subscribed
+            let _ = state.feed.send(event);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// A [ClobError] turned into a JSON error response
+pub struct ApiError(ClobError);
+
+impl From<ClobError> for ApiError {
+    fn from(err: ClobError) -> Self {
+        Self(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self.0 {
+            ClobError::InvalidOrder(_) | ClobError::Token(_) | ClobError::WouldCross(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            ClobError::NotFound(_) => StatusCode::NOT_FOUND,
+            ClobError::NoteAlreadyConsumed(_) | ClobError::RetriesExhausted { .. } => {
+                StatusCode::CONFLICT
+            }
+            ClobError::NoLiquidity | ClobError::InsufficientFunds(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ClobError::CommitTimeout(..) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (
+            status,
+            Json(ErrorBody {
+                error: self.0.to_string(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Body of order placement and re-pricing requests
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderRequest {
+    pub maker: String,
+    #[serde(flatten)]
+    pub order: OrderInput,
+}
+
+/// Query of requests acting on behalf of a maker
+#[derive(Debug, Clone, Deserialize)]
+pub struct MakerQuery {
+    pub maker: String,
+}
+
+/// Query of the cancellation of all the orders of a maker
+#[derive(Debug, Clone, Deserialize)]
+pub struct CancelAllQuery {
+    pub maker: String,
+    /// Only cancel the orders of this market, e.g. "ETH/DAI"
+    pub market: Option<String>,
+}
+
+/// Query of the order listing, optionally restricted to one maker
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrdersQuery {
+    pub maker: Option<String>,
+}
+
+/// Query of the book endpoint
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthQuery {
+    pub levels: Option<usize>,
+}
+
+/// Body of take requests
+#[derive(Debug, Clone, Deserialize)]
+pub struct TakeRequest {
+    pub taker: String,
+    /// Most the taker pays, e.g. "500 DAI"
+    pub give: String,
+    /// Symbol of the token the taker receives
+    pub want: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReceiptView {
+    pub transaction_id: String,
+    pub block_num: u32,
+    pub consumed_notes: Vec<String>,
+    /// Limit-swap notes created by the transaction
+    pub orders: Vec<String>,
+    pub payback_notes: Vec<String>,
+}
+
+impl From<&TransactionReceipt> for ReceiptView {
+    fn from(receipt: &TransactionReceipt) -> Self {
+        Self {
+            transaction_id: receipt.transaction_id.to_hex(),
+            block_num: receipt.block_num,
+            consumed_notes: receipt.consumed_notes.iter().map(|id| id.to_hex()).collect(),
+            orders: receipt.clone_notes.iter().map(|note| note.id().to_hex()).collect(),
+            payback_notes: receipt.payback_notes.iter().map(|note| note.id().to_hex()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelReportView {
+    pub reclaimed: Vec<String>,
+    pub already_filled: Vec<String>,
+    /// Orders still resting because cancelling stopped at `error`
+    pub failed: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub receipts: Vec<ReceiptView>,
+}
+
+impl From<&CancelReport> for CancelReportView {
+    fn from(report: &CancelReport) -> Self {
+        let hex = |note_ids: &[NoteId]| note_ids.iter().map(|id| id.to_hex()).collect();
+        Self {
+            reclaimed: hex(&report.reclaimed),
+            already_filled: hex(&report.already_filled),
+            failed: hex(&report.failed),
+            error: report.error.as_ref().map(|err| err.to_string()),
+            receipts: report.receipts.iter().map(ReceiptView::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderView {
+    pub note_id: String,
+    pub maker: String,
+    pub offered: String,
+    pub requested: String,
+    pub status: OrderStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<String>,
+}
+
+impl OrderView {
+    pub fn new(record: &OrderRecord, tokens: &TokenRegistry) -> Self {
+        Self {
+            note_id: record.note_id.to_hex(),
+            maker: record.maker.to_hex(),
+            offered: tokens.format_amount(&record.offered),
+            requested: tokens.format_amount(&record.requested),
+            status: record.status,
+            replaces: record.replaces.map(|id| id.to_hex()),
+            replaced_by: record.replaced_by.map(|id| id.to_hex()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TakeView {
+    pub note_id: String,
+    pub paid: String,
+    pub received: String,
+    pub receipt: ReceiptView,
+}
+
+impl TakeView {
+    pub fn new(
+        tokens: &TokenRegistry,
+        fill: &Fill,
+        give: FungibleAsset,
+        want: AccountId,
+        receipt: &TransactionReceipt,
+    ) -> Self {
+        let amount = |faucet_id, amount| match FungibleAsset::new(faucet_id, amount) {
+            Ok(asset) => tokens.format_amount(&asset),
+            Err(_) => amount.to_string(),
+        };
+        Self {
+            note_id: fill.note_id.to_hex(),
+            paid: amount(give.faucet_id(), fill.in_amount),
+            received: amount(want, fill.out_amount),
+            receipt: receipt.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelView {
+    pub price: String,
+    pub size: String,
+    pub orders: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DepthView {
+    pub bids: Vec<LevelView>,
+    pub asks: Vec<LevelView>,
+}
+
+impl DepthView {
+    pub fn new(depth: &Depth, tokens: &TokenRegistry) -> Self {
+        let levels = |levels: &[Level]| -> Vec<LevelView> {
+            levels.iter().map(|level| LevelView::new(level, depth.pair, tokens)).collect()
+        };
+        Self {
+            bids: levels(&depth.bids),
+            asks: levels(&depth.asks),
+        }
+    }
+}
+
+impl LevelView {
+    pub fn new(level: &Level, pair: Pair, tokens: &TokenRegistry) -> Self {
+        let size = FungibleAsset::new(pair.base, level.size);
+        let price_base = FungibleAsset::new(pair.base, level.price.base());
+        let price_quote = FungibleAsset::new(pair.quote, level.price.quote());
+        match (size, price_base, price_quote) {
+            (Ok(size), Ok(base), Ok(quote)) => Self {
+                price: tokens.format_price(&base, &quote),
+                size: tokens.format_amount(&size),
+                orders: level.orders,
+            },
+            _ => Self {
+                price: level.price.to_string(),
+                size: level.size.to_string(),
+                orders: level.orders,
+            },
+        }
+    }
+}
+
+async fn place_order<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Json(request): Json<OrderRequest>,
+) -> ApiResult<ReceiptView> {
+    let mut clob = state.clob.lock().await;
+    let order = request.order.to_order(clob.tokens())?;
+    let result = clob.place_order(&request.maker, &order).await;
+    state.refresh(&mut clob).await;
+    Ok(Json((&result?).into()))
+}
+
+async fn list_orders<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<OrdersQuery>,
+) -> ApiResult<Vec<OrderView>> {
+    let view = state.view();
+    let maker = match &query.maker {
+        Some(alias) => Some(view.account_id(alias)?),
+        None => None,
+    };
+    let orders = view
+        .registry
+        .orders()
+        .filter(|record| match maker {
+            Some(maker) => record.maker == maker,
+            None => true,
+        })
+        .map(|record| OrderView::new(record, &view.tokens))
+        .collect();
+    Ok(Json(orders))
+}
+
+async fn get_order<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Path(note_id): Path<String>,
+) -> ApiResult<OrderView> {
+    let note_id = parse_note_id(&note_id)?;
+    let view = state.view();
+    let record = view
+        .registry
+        .get(&note_id)
+        .ok_or_else(|| ClobError::NotFound(format!("order {}", note_id.to_hex())))?;
+    Ok(Json(OrderView::new(record, &view.tokens)))
+}
+
+async fn replace_order<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Path(note_id): Path<String>,
+    Json(request): Json<OrderRequest>,
+) -> ApiResult<ReceiptView> {
+    let note_id = parse_note_id(&note_id)?;
+    let mut clob = state.clob.lock().await;
+    let order = request.order.to_order(clob.tokens())?;
+    let result = clob.replace_order(&request.maker, &note_id, &order).await;
+    state.refresh(&mut clob).await;
+    Ok(Json((&result?).into()))
+}
+
+async fn cancel_order<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Path(note_id): Path<String>,
+    Query(query): Query<MakerQuery>,
+) -> ApiResult<ReceiptView> {
+    let note_id = parse_note_id(&note_id)?;
+    let mut clob = state.clob.lock().await;
+    let result = clob.cancel_order(&query.maker, &note_id).await;
+    state.refresh(&mut clob).await;
+    Ok(Json((&result?).into()))
+}
+
+async fn cancel_all<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Query(query): Query<CancelAllQuery>,
+) -> ApiResult<CancelReportView> {
+    let mut clob = state.clob.lock().await;
+    let pair = match &query.market {
+        Some(market) => Some(clob.parse_pair(market)?),
+        None => None,
+    };
+    let result = clob.cancel_all(&query.maker, pair).await;
+    state.refresh(&mut clob).await;
+    Ok(Json((&result?).into()))
+}
+
+async fn take<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Json(request): Json<TakeRequest>,
+) -> ApiResult<TakeView> {
+    let mut clob = state.clob.lock().await;
+    let give = clob.tokens().parse_amount(&request.give)?;
+    let want = clob.tokens().faucet_id(&request.want)?;
+
+    let result = clob.take(&request.taker, want, give).await;
+    state.refresh(&mut clob).await;
+    let (fill, receipt) = result?;
+    Ok(Json(TakeView::new(clob.tokens(), &fill, give, want, &receipt)))
+}
+
+async fn book<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Path((base, quote)): Path<(String, String)>,
+    Query(query): Query<DepthQuery>,
+) -> ApiResult<DepthView> {
+    let view = state.view();
+    let pair = view.pair(&base, &quote)?;
+    let depth = view.depth(pair, query.levels.unwrap_or(DEFAULT_DEPTH_LEVELS));
+    Ok(Json(DepthView::new(&depth, &view.tokens)))
+}
+
+async fn balances<B: ClobBackend>(
+    State(state): State<AppState<B>>,
+    Path(account): Path<String>,
+) -> ApiResult<Vec<String>> {
+    let view = state.view();
+    let balances = view.balances(&account)?;
+    Ok(Json(balances.iter().map(|asset| view.tokens.format_amount(asset)).collect()))
+}
+
+/// Query of the feed endpoint
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedQuery {
+    /// Comma separated markets, e.g. "ETH/DAI,ETH/USDC", all if missing
+    pub pairs: Option<String>,
+    /// Comma separated account aliases, all if missing
+    pub accounts: Option<String>,
+}
+
+async fn feed<B: ClobBackend>(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState<B>>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Response, ApiError> {
+    let view = state.view();
+    let mut subscription = Subscription::default();
+    for pair in split_list(&query.pairs) {
+        subscription.pairs.insert(view.parse_pair(pair)?);
+    }
+    for alias in split_list(&query.accounts) {
+        subscription.accounts.insert(view.account_id(alias)?);
+    }
+
+    // Subscribing under the read lock, the feed cannot move past the
+    // snapshots before the subscription starts
+    let (snapshots, events) = {
+        let market = state.market.read().expect("market feed lock poisoned");
+        (market.snapshots(&subscription), state.feed.subscribe())
+    };
+    Ok(ws.on_upgrade(move |socket| stream_feed(socket, snapshots, events, subscription)))
+}
+
+/// Sends a snapshot of each subscribed market, then the feed events matching
+/// `subscription`, leaving out the depth updates the snapshots already cover
+async fn stream_feed(
+    mut socket: WebSocket,
+    snapshots: Vec<DepthUpdate>,
+    mut events: broadcast::Receiver<FeedEvent>,
+    subscription: Subscription,
+) {
+    let mut sequences = BTreeMap::new();
+    for snapshot in snapshots {
+        sequences.insert(snapshot.pair, snapshot.sequence);
+        let mut message = FeedEvent::Depth(snapshot).to_json();
+        message["type"] = "snapshot".into();
+        if socket.send(Message::Text(message.to_string())).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        let message = match events.recv().await {
+            Ok(FeedEvent::Depth(update))
+                if sequences.get(&update.pair).is_some_and(|seen| update.sequence <= *seen) =>
+            {
+                continue
+            }
+            Ok(event) if subscription.matches(&event) => event.to_json(),
+            Ok(_) => continue,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(skipped, "Feed subscriber lagging");
+                serde_json::json!({ "type": "lagged", "skipped": skipped })
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if socket.send(Message::Text(message.to_string())).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn split_list(list: &Option<String>) -> impl Iterator<Item = &str> {
+    list.iter().flat_map(|list| list.split(',')).map(str::trim).filter(|item| !item.is_empty())
+}
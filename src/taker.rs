@@ -0,0 +1,141 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use miden_objects::accounts::{Account, AccountId};
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use tracing::{info, instrument, warn};
+
+use crate::backend::{execute_on, ClobBackend};
+use crate::book::OrderBook;
+use crate::build_fill_tx_req;
+use crate::errors::ClobError;
+use crate::market::Markets;
+use crate::order::Fill;
+use crate::receipt::TransactionReceipt;
+
+/// How a taker reacts when it loses the race for a limit-swap note
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Maximum number of orders tried before giving up
+    pub max_attempts: u32,
+    /// Delay before re-routing to the next best order
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Attempts made under a [RetryPolicy], and the notes lost to other takers
+/// along the way
+#[derive(Debug, Clone)]
+pub struct Retries {
+    policy: RetryPolicy,
+    attempts: u32,
+    lost: BTreeSet<NoteId>,
+}
+
+impl Retries {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy,
+            attempts: 0,
+            lost: BTreeSet::new(),
+        }
+    }
+
+    /// Number of failed attempts so far
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Notes consumed by competing transactions so far
+    pub fn lost(&self) -> &BTreeSet<NoteId> {
+        &self.lost
+    }
+
+    /// Records a failed attempt.
+    ///
+    /// On a note conflict the notes are remembered as lost and the delay
+    /// before the next attempt is returned. Other errors, and the conflict
+    /// that uses up the last attempt, are returned as the error to give up with.
+    pub fn record(&mut self, err: ClobError) -> Result<Duration, ClobError> {
+        self.attempts += 1;
+        match err {
+            ClobError::NoteAlreadyConsumed(note_ids) => {
+                self.lost.extend(note_ids);
+                if self.attempts >= self.policy.max_attempts {
+                    return Err(ClobError::RetriesExhausted {
+                        attempts: self.attempts,
+                    });
+                }
+                Ok(self.policy.backoff)
+            }
+            err => Err(err),
+        }
+    }
+}
+
+/// Returns the fill of the best order offering assets of `want` for at most
+/// `in_amount` of `give`, skipping the notes in `lost` and fills that would
+/// leave less than a lot of their market
+pub fn next_fill(
+    book: &OrderBook,
+    markets: &Markets,
+    want: AccountId,
+    give: AccountId,
+    in_amount: u64,
+    lost: &BTreeSet<NoteId>,
+) -> Result<Fill, ClobError> {
+    book.fillable_offers(want, give, in_amount)
+        .into_iter()
+        .filter(|(order, _)| !lost.contains(&order.note_id))
+        .find(|(order, fill)| markets.validate_fill(order, fill).is_ok())
+        .map(|(_, fill)| fill)
+        .ok_or(ClobError::NoLiquidity)
+}
+
+/// Takes liquidity from the best order offering assets of `want` in exchange
+/// for at most `give`.
+///
+/// When another taker consumes the chosen note first, the book is refreshed and
+/// the next best order is tried, as allowed by `policy`.
+#[instrument(skip_all, fields(taker = taker.id().to_hex(), in_amount = give.amount()))]
+pub async fn take_best_order<B: ClobBackend>(
+    backend: &mut B,
+    book: &mut OrderBook,
+    markets: &Markets,
+    taker: &Account,
+    want: AccountId,
+    give: FungibleAsset,
+    policy: RetryPolicy,
+) -> Result<(Fill, TransactionReceipt), ClobError> {
+    let mut retries = Retries::new(policy);
+
+    loop {
+        book.refresh(backend).await?;
+        let fill = next_fill(book, markets, want, give.faucet_id(), give.amount(), retries.lost())?;
+
+        let attempt = retries.attempts() + 1;
+        info!(attempt, note = fill.note_id.to_hex(), ?fill, "Taking order");
+
+        let tx_req = build_fill_tx_req(backend, taker.id(), &[fill])?;
+        match execute_on(backend, tx_req).await {
+            Ok(receipt) => return Ok((fill, receipt)),
+            Err(err) => {
+                let backoff = retries.record(err)?;
+                warn!(attempt, note = fill.note_id.to_hex(), "Note taken by someone else");
+                for note_id in retries.lost() {
+                    book.remove(note_id);
+                }
+                tokio::time::sleep(backoff).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+
+use miden_objects::accounts::{Account, AccountId};
+use miden_objects::assets::FungibleAsset;
+use vm_processor::Word;
+
+use crate::backend::ClobBackend;
+use crate::config::ClobConfig;
+use crate::errors::ClobError;
+use crate::profiles::AccountProfiles;
+
+/// Storage slot holding the metadata of a basic fungible faucet, laid out as
+/// `[max_supply, decimals, token_symbol, 0]`
+const FAUCET_METADATA_SLOT: u8 = 1;
+
+/// A token and the faucet issuing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub faucet_id: AccountId,
+    pub decimals: u8,
+    pub max_supply: u64,
+}
+
+/// Maps token symbols to faucets and converts between base units and
+/// human-readable decimal amounts
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+    tokens: BTreeMap<String, TokenInfo>,
+    symbols: BTreeMap<AccountId, String>,
+}
+
+impl TokenRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a registry from the tokens listed in the configuration
+    pub fn from_config(config: &ClobConfig) -> Result<Self, ClobError> {
+        let mut registry = Self::new();
+        for token in &config.tokens {
+            let faucet_id = AccountId::from_hex(&token.faucet_id).map_err(|err| {
+                ClobError::Config(format!("invalid faucet id for {}: {err}", token.symbol))
+            })?;
+            registry.register(TokenInfo {
+                symbol: token.symbol.clone(),
+                faucet_id,
+                decimals: token.decimals,
+                max_supply: token.max_supply,
+            });
+        }
+        Ok(registry)
+    }
+
+    /// Registers the faucets of the profiles, reading decimals and max supply
+    /// from the faucet metadata known to `backend`
+    pub fn load_faucets<B: ClobBackend>(
+        &mut self,
+        backend: &B,
+        profiles: &AccountProfiles,
+    ) -> Result<(), ClobError> {
+        for (symbol, faucet_id) in profiles.faucets() {
+            let (decimals, max_supply) = faucet_metadata(&backend.get_account(faucet_id)?);
+            self.register(TokenInfo {
+                symbol: symbol.to_string(),
+                faucet_id,
+                decimals,
+                max_supply,
+            });
+        }
+        Ok(())
+    }
+
+    /// Registers a token, replacing any token with the same symbol
+    pub fn register(&mut self, token: TokenInfo) {
+        if let Some(previous) = self.tokens.remove(&token.symbol) {
+            self.symbols.remove(&previous.faucet_id);
+        }
+        self.symbols.insert(token.faucet_id, token.symbol.clone());
+        self.tokens.insert(token.symbol.clone(), token);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&TokenInfo> {
+        self.tokens.get(symbol)
+    }
+
+    /// Returns the faucet of token `symbol`
+    pub fn faucet_id(&self, symbol: &str) -> Result<AccountId, ClobError> {
+        self.get(symbol)
+            .map(|token| token.faucet_id)
+            .ok_or_else(|| ClobError::NotFound(format!("token {symbol}")))
+    }
+
+    pub fn by_faucet(&self, faucet_id: AccountId) -> Option<&TokenInfo> {
+        self.symbols.get(&faucet_id).and_then(|symbol| self.tokens.get(symbol))
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = &TokenInfo> {
+        self.tokens.values()
+    }
+
+    /// Parses an amount such as "1.5 ETH" into an asset
+    pub fn parse_amount(&self, amount: &str) -> Result<FungibleAsset, ClobError> {
+        let mut parts = amount.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(units), Some(symbol), None) => self.asset(symbol, units),
+            _ => Err(ClobError::Token(format!("expected \"<amount> <symbol>\", got \"{amount}\""))),
+        }
+    }
+
+    /// Builds an asset of `symbol` from a decimal amount such as "1.5"
+    pub fn asset(&self, symbol: &str, units: &str) -> Result<FungibleAsset, ClobError> {
+        let token =
+            self.get(symbol).ok_or_else(|| ClobError::Token(format!("unknown token {symbol}")))?;
+        let amount = parse_units(units, token.decimals)?;
+        if amount > token.max_supply {
+            return Err(ClobError::Token(format!("{units} {symbol} exceeds the max supply")));
+        }
+        FungibleAsset::new(token.faucet_id, amount)
+            .map_err(|err| ClobError::Token(format!("invalid amount {units} {symbol}: {err}")))
+    }
+
+    /// Formats an asset as e.g. "1.5 ETH", falling back to base units and the
+    /// faucet ID for unknown tokens
+    pub fn format_amount(&self, asset: &FungibleAsset) -> String {
+        match self.by_faucet(asset.faucet_id()) {
+            Some(token) => {
+                format!("{} {}", format_units(asset.amount(), token.decimals), token.symbol)
+            }
+            None => format!("{} {}", asset.amount(), asset.faucet_id().to_hex()),
+        }
+    }
+
+    /// Formats the price implied by exchanging `base` for `quote`, as quote
+    /// units per whole base unit, e.g. "2500 DAI/ETH". Rounded down.
+    pub fn format_price(&self, base: &FungibleAsset, quote: &FungibleAsset) -> String {
+        let (Some(base_token), Some(quote_token)) =
+            (self.by_faucet(base.faucet_id()), self.by_faucet(quote.faucet_id()))
+        else {
+            return format!("{}/{}", quote.amount(), base.amount());
+        };
+        if base.amount() == 0 {
+            return format!("- {}/{}", quote_token.symbol, base_token.symbol);
+        }
+
+        let price =
+            quote.amount() as u128 * 10u128.pow(base_token.decimals as u32) / base.amount() as u128;
+        format!(
+            "{} {}/{}",
+            format_scaled(price, quote_token.decimals),
+            quote_token.symbol,
+            base_token.symbol
+        )
+    }
+}
+
+/// Reads the decimals and max supply of a basic fungible faucet
+pub fn faucet_metadata(faucet: &Account) -> (u8, u64) {
+    let metadata: Word = faucet.storage().get_item(FAUCET_METADATA_SLOT).into();
+    (metadata[1].as_int() as u8, metadata[0].as_int())
+}
+
+/// Converts a decimal amount such as "1.5" into base units
+pub fn parse_units(units: &str, decimals: u8) -> Result<u64, ClobError> {
+    let invalid = |reason: &str| ClobError::Token(format!("invalid amount \"{units}\": {reason}"));
+
+    let (int_part, frac_part) = units.split_once('.').unwrap_or((units, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(invalid("empty"));
+    }
+    if !int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid("not a decimal number"));
+    }
+    if frac_part.len() > decimals as usize {
+        return Err(invalid(&format!("more than {decimals} decimals")));
+    }
+
+    let scale = 10u64.checked_pow(decimals as u32).ok_or_else(|| invalid("too many decimals"))?;
+    let int_value: u64 = if int_part.is_empty() {
+        0
+    } else {
+        int_part.parse().map_err(|_| invalid("too large"))?
+    };
+    let frac_value: u64 = if frac_part.is_empty() {
+        0
+    } else {
+        let padding = 10u64.pow((decimals as usize - frac_part.len()) as u32);
+        frac_part.parse::<u64>().map_err(|_| invalid("too large"))? * padding
+    };
+
+    int_value
+        .checked_mul(scale)
+        .and_then(|value| value.checked_add(frac_value))
+        .ok_or_else(|| invalid("too large"))
+}
+
+/// Formats base units as a decimal amount, without trailing zeros
+pub fn format_units(amount: u64, decimals: u8) -> String {
+    format_scaled(amount as u128, decimals)
+}
+
+fn format_scaled(amount: u128, decimals: u8) -> String {
+    let scale = 10u128.pow(decimals as u32);
+    let (int_value, frac_value) = (amount / scale, amount % scale);
+    if frac_value == 0 {
+        return int_value.to_string();
+    }
+    let frac = format!("{frac_value:0width$}", width = decimals as usize);
+    format!("{int_value}.{}", frac.trim_end_matches('0'))
+}
@@ -1,102 +1,17 @@
 // PUT ANY FUNCTIONS YOU NEED TO BE USED AS A UTLITY IN THE TESTS HERE
 
+use keom_clob::order::LimitOrder;
 use miden_lib::transaction::TransactionKernel;
 use miden_mock::constants::{ACCOUNT_ID_SENDER, DEFAULT_ACCOUNT_CODE, MIN_PROOF_SECURITY_LEVEL};
-use miden_mock::mock::account::MockAccountType;
-use miden_mock::mock::notes::AssetPreservationStatus;
-use miden_mock::mock::transaction::{mock_inputs, mock_inputs_with_existing};
 use miden_objects::accounts::{Account, AccountCode, AccountId, AccountStorage, StorageSlotType};
 use miden_objects::assembly::{ModuleAst, ProgramAst};
 use miden_objects::assets::{Asset, AssetVault, FungibleAsset};
 use miden_objects::crypto::dsa::rpo_falcon512::KeyPair;
 use miden_objects::notes::{Note, NoteId, NoteScript};
-use miden_objects::transaction::{
-    ChainMmr, ExecutedTransaction, InputNote, InputNotes, ProvenTransaction,
-};
-use miden_objects::{BlockHeader, Felt, Word};
-use miden_tx::{
-    DataStore, DataStoreError, ProvingOptions, TransactionInputs, TransactionProver,
-    TransactionVerifier, TransactionVerifierError,
-};
-
-#[cfg(test)]
-#[derive(Clone)]
-pub struct MockDataStore {
-    pub account: Account,
-    pub block_header: BlockHeader,
-    pub block_chain: ChainMmr,
-    pub notes: Vec<InputNote>,
-}
-
-impl MockDataStore {
-    pub fn new() -> Self {
-        let (account, _, block_header, block_chain, notes) =
-            mock_inputs(MockAccountType::StandardExisting, AssetPreservationStatus::Preserved)
-                .into_parts();
-        Self {
-            account,
-            block_header,
-            block_chain,
-            notes: notes.into_vec(),
-        }
-    }
-
-    pub fn with_existing(account: Option<Account>, input_notes: Option<Vec<Note>>) -> Self {
-        let (account, block_header, block_chain, consumed_notes, _auxiliary_data_inputs) =
-            mock_inputs_with_existing(
-                MockAccountType::StandardExisting,
-                AssetPreservationStatus::Preserved,
-                account,
-                input_notes,
-            );
-        Self {
-            account,
-            block_header,
-            block_chain,
-            notes: consumed_notes,
-        }
-    }
-}
-
-impl Default for MockDataStore {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl DataStore for MockDataStore {
-    fn get_transaction_inputs(
-        &self,
-        account_id: AccountId,
-        block_num: u32,
-        notes: &[NoteId],
-    ) -> Result<TransactionInputs, DataStoreError> {
-        assert_eq!(account_id, self.account.id());
-        assert_eq!(block_num, self.block_header.block_num());
-        assert_eq!(notes.len(), self.notes.len());
-
-        let notes = self
-            .notes
-            .iter()
-            .filter(|note| notes.contains(&note.id()))
-            .cloned()
-            .collect::<Vec<_>>();
-
-        Ok(TransactionInputs::new(
-            self.account.clone(),
-            None,
-            self.block_header,
-            self.block_chain.clone(),
-            InputNotes::new(notes).unwrap(),
-        )
-        .unwrap())
-    }
-
-    fn get_account_code(&self, account_id: AccountId) -> Result<ModuleAst, DataStoreError> {
-        assert_eq!(account_id, self.account.id());
-        Ok(self.account.code().module().clone())
-    }
-}
+use miden_objects::transaction::{ExecutedTransaction, ProvenTransaction};
+use miden_objects::{Digest, Felt, Word};
+use miden_tx::{ProvingOptions, TransactionProver, TransactionVerifier, TransactionVerifierError};
+use vm_processor::{DefaultHost, ExecutionOptions, StackInputs};
 
 #[cfg(test)]
 pub fn get_new_key_pair_with_advice_map() -> (Word, Vec<Felt>) {
@@ -182,3 +97,141 @@ pub fn prove_and_verify_transaction(
 
     verifier.verify(proven_transaction)
 }
+
+// ORDER FIXTURES
+// ================================================================================================
+
+/// Dummy faucet IDs for ETH and DAI
+pub const ETH_FAUCET_ID: u64 = 10000118204333965312;
+pub const DAI_FAUCET_ID: u64 = 10000344073709551615;
+/// Dummy taker account ID
+pub const ACCOUNT_ID_TAKER: u64 = 0x6ec0000000000100;
+
+pub fn eth(amount: u64) -> FungibleAsset {
+    FungibleAsset::new(AccountId::try_from(ETH_FAUCET_ID).unwrap(), amount).unwrap()
+}
+
+pub fn dai(amount: u64) -> FungibleAsset {
+    FungibleAsset::new(AccountId::try_from(DAI_FAUCET_ID).unwrap(), amount).unwrap()
+}
+
+pub fn maker_id() -> AccountId {
+    AccountId::try_from(ACCOUNT_ID_SENDER).unwrap()
+}
+
+pub fn taker_id() -> AccountId {
+    AccountId::try_from(ACCOUNT_ID_TAKER).unwrap()
+}
+
+pub fn digest(seed: u64) -> Digest {
+    Digest::new([Felt::new(seed), Felt::new(0), Felt::new(0), Felt::new(0)])
+}
+
+pub fn note_id(seed: u64) -> NoteId {
+    NoteId::new(digest(seed), Digest::default())
+}
+
+/// Order of the dummy maker, without minimums and with default recipients
+pub fn order(seed: u64, offered: FungibleAsset, requested: FungibleAsset) -> LimitOrder {
+    LimitOrder {
+        note_id: note_id(seed),
+        maker: maker_id(),
+        offered,
+        requested,
+        payback_recipient: Digest::default(),
+        partial_recipient: Digest::default(),
+        min_remaining: 0,
+        min_fill: 0,
+    }
+}
+
+// VM HASH PARITY
+// ================================================================================================
+
+/// First memory address used by the hashing snippets
+const HASH_MEMORY_PTR: u32 = 1000;
+
+/// Formats a `push` instruction leaving `word` on the stack the way the VM
+/// reads words, i.e. with its first element deepest
+pub fn masm_push_word(word: &Word) -> String {
+    let elements = word.iter().map(|element| element.as_int().to_string()).collect::<Vec<_>>();
+    format!("push.{}", elements.join("."))
+}
+
+/// Formats instructions storing `elements`, padded with zeros to whole words,
+/// at consecutive memory addresses starting at `ptr`
+pub fn masm_store_elements(elements: &[Felt], ptr: u32) -> String {
+    elements
+        .chunks(4)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut word = [Felt::new(0); 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            format!("{} push.{} mem_storew dropw", masm_push_word(&word), ptr + i as u32)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Compiles `masm` with the transaction kernel assembler, which links the
+/// standard library, runs it and returns the word left on top of the stack
+pub fn execute_masm_word(masm: &str) -> Word {
+    let program = TransactionKernel::assembler().compile(masm).unwrap();
+    let result = vm_processor::execute(
+        &program,
+        StackInputs::default(),
+        DefaultHost::default(),
+        ExecutionOptions::default(),
+    )
+    .unwrap();
+
+    let stack = result.stack_outputs().stack();
+    [stack[3], stack[2], stack[1], stack[0]].map(Felt::from)
+}
+
+/// VM counterpart of [miden_objects::Hasher::hash_elements], hashing the
+/// elements from memory with the standard library
+pub fn vm_hash_elements(elements: &[Felt]) -> Word {
+    execute_masm_word(&format!(
+        "use.std::crypto::hashes::native\n\
+         begin\n\
+         {}\n\
+         push.{}.{} exec.native::hash_memory\n\
+         end",
+        masm_store_elements(elements, HASH_MEMORY_PTR),
+        elements.len(),
+        HASH_MEMORY_PTR,
+    ))
+}
+
+/// VM counterpart of [miden_objects::Hasher::merge]
+pub fn vm_merge(left: Digest, right: Digest) -> Word {
+    execute_masm_word(&format!(
+        "begin {} {} hmerge end",
+        masm_push_word(&left.into()),
+        masm_push_word(&right.into()),
+    ))
+}
+
+// LIMIT-SWAP PROCEDURES
+// ================================================================================================
+
+/// Runs procedure `proc` of masm/limit_swap.masm with `inputs` on the stack,
+/// the first one on top, and returns the stack it leaves from the top down, or
+/// None if the procedure aborts
+pub fn execute_limit_swap_proc(proc: &str, inputs: &[u64]) -> Option<Vec<u64>> {
+    let script = include_str!("../src/masm/limit_swap.masm");
+    let procs = &script[..script.rfind("\nbegin").unwrap()];
+    let pushes = inputs.iter().rev().map(|input| format!("push.{input}")).collect::<Vec<_>>();
+
+    let masm = format!("{procs}\nbegin\n{}\nexec.{proc}\nend", pushes.join(" "));
+    let program = TransactionKernel::assembler().compile(&masm).unwrap();
+    let result = vm_processor::execute(
+        &program,
+        StackInputs::default(),
+        DefaultHost::default(),
+        ExecutionOptions::default(),
+    )
+    .ok()?;
+    Some(result.stack_outputs().stack().to_vec())
+}
@@ -0,0 +1,392 @@
+pub mod helpers;
+
+use std::collections::BTreeMap;
+
+use keom_clob::backend::{execute_on, prove_transaction, ClobBackend, SimulatedChain};
+use keom_clob::book::OrderBook;
+use keom_clob::errors::ClobError;
+use keom_clob::{
+    build_consume_notes_tx_req, build_send_note_tx_script, create_limit_swap_note, mint,
+    mint_batch, MintRequest,
+};
+use miden_client::client::accounts::AccountStorageMode;
+use miden_client::client::transactions::transaction_request::TransactionRequest;
+use miden_client::client::transactions::TransactionResult;
+use miden_client::store::{InputNoteRecord, NoteFilter};
+use miden_lib::notes::create_p2id_note;
+use miden_objects::accounts::{Account, AccountId};
+use miden_objects::assembly::ProgramAst;
+use miden_objects::assets::{FungibleAsset, TokenSymbol};
+use miden_objects::notes::{Note, NoteId, NoteType};
+use miden_objects::transaction::{ProvenTransaction, TransactionId, TransactionScript};
+use vm_processor::crypto::RpoRandomCoin;
+use vm_processor::Felt;
+
+use crate::helpers::{dai, eth, maker_id};
+
+fn limit_swap_note(seed: u64) -> miden_objects::notes::Note {
+    let rng = RpoRandomCoin::new([Felt::new(seed), Felt::new(2), Felt::new(3), Felt::new(4)]);
+    create_limit_swap_note(maker_id(), eth(100).into(), dai(300).into(), rng).unwrap()
+}
+
+#[tokio::test]
+async fn notes_are_included_in_the_next_block() {
+    let mut chain = SimulatedChain::new();
+    let note = limit_swap_note(1);
+
+    chain.add_note(note.clone());
+    assert_eq!(chain.note_block(&note.id()), None);
+    assert!(chain.get_notes(NoteFilter::Committed).unwrap().is_empty());
+
+    assert_eq!(chain.produce_block().unwrap(), 1);
+    assert_eq!(chain.sync().await.unwrap(), 1);
+    assert_eq!(chain.note_block(&note.id()), Some(1));
+
+    let committed = chain.get_notes(NoteFilter::Committed).unwrap();
+    assert_eq!(committed.iter().map(|record| record.id()).collect::<Vec<_>>(), vec![note.id()]);
+    assert!(chain.get_notes(NoteFilter::Consumed).unwrap().is_empty());
+    assert!(!chain.is_consumed(&note.id()));
+}
+
+#[tokio::test]
+async fn order_book_loads_from_simulated_chain() {
+    let mut chain = SimulatedChain::new();
+    chain.add_note(limit_swap_note(1));
+    chain.add_note(limit_swap_note(2));
+    chain.produce_block().unwrap();
+
+    let mut book = OrderBook::new();
+    book.refresh(&mut chain).await.unwrap();
+
+    assert_eq!(book.len(), 2);
+    assert!(book.orders().all(|order| order.offered.amount() == 100));
+}
+
+#[tokio::test]
+async fn unknown_accounts_are_not_found() {
+    let chain = SimulatedChain::new();
+    let note = limit_swap_note(1);
+
+    let err = chain.get_account(note.metadata().sender()).unwrap_err();
+    assert!(err.to_string().contains("simulated account"));
+    assert_eq!(chain.pending_transactions(), 0);
+}
+
+/// A chain with a faucet, a maker holding 100 of its tokens and an empty taker
+async fn funded_chain() -> (SimulatedChain, FungibleAsset, Account, Account) {
+    let mut chain = SimulatedChain::new();
+    let symbol = TokenSymbol::new("ETH").unwrap();
+    let faucet = chain.new_faucet(symbol, 8, 1_000_000, AccountStorageMode::Local).unwrap();
+    let maker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let taker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+
+    let asset = FungibleAsset::new(faucet.id(), 100).unwrap();
+    mint(&mut chain, &maker, asset).await.into_result().unwrap();
+
+    (chain, asset, maker, taker)
+}
+
+/// Sends `asset` from `maker` to `taker` in a P2ID note
+async fn send_p2id(
+    chain: &mut SimulatedChain,
+    maker: &Account,
+    taker: &Account,
+    asset: FungibleAsset,
+) -> Note {
+    let rng = RpoRandomCoin::new([Felt::new(7), Felt::new(2), Felt::new(3), Felt::new(4)]);
+    let note =
+        create_p2id_note(maker.id(), taker.id(), vec![asset.into()], NoteType::OffChain, rng)
+            .unwrap();
+    let tx_script = build_send_note_tx_script(chain, maker.id(), &note, asset.into()).unwrap();
+    let tx_req =
+        TransactionRequest::new(maker.id(), BTreeMap::new(), vec![note.clone()], Some(tx_script));
+
+    let receipt = execute_on(chain, tx_req).await.unwrap();
+    assert_eq!(receipt.account_delta.vault().removed_assets, vec![asset.into()]);
+    assert_eq!(receipt.created_notes().map(Note::id).collect::<Vec<_>>(), vec![note.id()]);
+    assert_eq!(chain.note_block(&note.id()), Some(receipt.block_num));
+
+    note
+}
+
+#[tokio::test]
+async fn execute_on_settles_a_note_round_trip() {
+    let (mut chain, asset, maker, taker) = funded_chain().await;
+    let maker_balance = |chain: &SimulatedChain| {
+        chain.get_account(maker.id()).unwrap().vault().get_balance(asset.faucet_id()).unwrap()
+    };
+    assert_eq!(maker_balance(&chain), 100);
+
+    let note = send_p2id(&mut chain, &maker, &taker, asset).await;
+    assert_eq!(maker_balance(&chain), 0);
+
+    let notes = BTreeMap::from([(note.id(), None)]);
+    let tx_req = build_consume_notes_tx_req(&chain, taker.id(), notes).unwrap();
+    let receipt = execute_on(&mut chain, tx_req).await.unwrap();
+
+    assert_eq!(receipt.consumed_notes, vec![note.id()]);
+    assert_eq!(receipt.account_delta.vault().added_assets, vec![asset.into()]);
+    assert_eq!(receipt.block_num, chain.block_num());
+    assert!(chain.is_consumed(&note.id()));
+    assert_eq!(chain.note_block(&note.id()), None);
+
+    let taker = chain.get_account(taker.id()).unwrap();
+    assert_eq!(taker.vault().get_balance(asset.faucet_id()).unwrap(), 100);
+    assert_eq!(chain.pending_transactions(), 0);
+}
+
+#[tokio::test]
+async fn consuming_a_nullified_note_is_a_conflict() {
+    let (mut chain, asset, maker, taker) = funded_chain().await;
+    let note = send_p2id(&mut chain, &maker, &taker, asset).await;
+
+    let consume = |chain: &SimulatedChain| {
+        let notes = BTreeMap::from([(note.id(), None)]);
+        build_consume_notes_tx_req(chain, taker.id(), notes).unwrap()
+    };
+
+    // Both takes are executed against the same state, the second one is
+    // submitted while the first is pending
+    let first = chain.execute(consume(&chain)).unwrap();
+    let second = chain.execute(consume(&chain)).unwrap();
+    let (first_proof, _) = prove_transaction(first.executed_transaction().clone()).await.unwrap();
+    let (second_proof, _) = prove_transaction(second.executed_transaction().clone()).await.unwrap();
+    chain.submit(first, first_proof).await.unwrap();
+    let err = chain.submit(second, second_proof).await.unwrap_err();
+    assert!(matches!(&err, ClobError::NoteAlreadyConsumed(ids) if ids == &vec![note.id()]));
+
+    // Once the first take is committed, the note is rejected up front
+    chain.produce_block().unwrap();
+    let err = execute_on(&mut chain, consume(&chain)).await.unwrap_err();
+    assert!(matches!(&err, ClobError::NoteAlreadyConsumed(ids) if ids == &vec![note.id()]));
+    assert!(err.is_note_conflict());
+}
+
+#[tokio::test]
+async fn mint_batch_mints_in_rounds_per_faucet() {
+    let mut chain = SimulatedChain::new();
+    let mut new_faucet = |symbol| {
+        let symbol = TokenSymbol::new(symbol).unwrap();
+        chain.new_faucet(symbol, 8, 1_000_000, AccountStorageMode::Local).unwrap()
+    };
+    let (eth_faucet, dai_faucet) = (new_faucet("ETH"), new_faucet("DAI"));
+    let alice = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let bob = chain.new_wallet(AccountStorageMode::Local).unwrap();
+
+    let eth = |amount| FungibleAsset::new(eth_faucet.id(), amount).unwrap();
+    let dai = |amount| FungibleAsset::new(dai_faucet.id(), amount).unwrap();
+    let requests = [
+        MintRequest::new(alice.id(), vec![eth(10), dai(20)]),
+        MintRequest::new(bob.id(), vec![eth(30)]),
+    ];
+
+    // Three mints in two rounds, as the ETH faucet mints twice, then one
+    // consumption per account
+    let receipts = mint_batch(&mut chain, &requests).await.into_result().unwrap();
+    let blocks = receipts.iter().map(|receipt| receipt.block_num).collect::<Vec<_>>();
+    assert_eq!(blocks, vec![1, 1, 2, 3, 3]);
+
+    let balance = |account: &Account, faucet: &Account| {
+        let account = chain.get_account(account.id()).unwrap();
+        account.vault().get_balance(faucet.id()).unwrap()
+    };
+    assert_eq!(balance(&alice, &eth_faucet), 10);
+    assert_eq!(balance(&alice, &dai_faucet), 20);
+    assert_eq!(balance(&bob, &eth_faucet), 30);
+    assert_eq!(chain.pending_transactions(), 0);
+}
+
+/// A chain on which a rival transaction gets committed right before ours is
+/// submitted, and ours is then rejected without saying why, like a node would
+struct RivalChain {
+    chain: SimulatedChain,
+    rival: Option<TransactionResult>,
+}
+
+#[tonic::async_trait]
+impl ClobBackend for RivalChain {
+    async fn sync(&mut self) -> Result<u32, ClobError> {
+        self.chain.sync().await
+    }
+
+    fn get_notes(&self, filter: NoteFilter) -> Result<Vec<InputNoteRecord>, ClobError> {
+        self.chain.get_notes(filter)
+    }
+
+    fn get_account(&self, account_id: AccountId) -> Result<Account, ClobError> {
+        self.chain.get_account(account_id)
+    }
+
+    fn new_wallet(&mut self, storage_mode: AccountStorageMode) -> Result<Account, ClobError> {
+        self.chain.new_wallet(storage_mode)
+    }
+
+    fn new_faucet(
+        &mut self,
+        symbol: TokenSymbol,
+        decimals: u8,
+        max_supply: u64,
+        storage_mode: AccountStorageMode,
+    ) -> Result<Account, ClobError> {
+        self.chain.new_faucet(symbol, decimals, max_supply, storage_mode)
+    }
+
+    fn compile_tx_script(
+        &self,
+        account_id: AccountId,
+        program: ProgramAst,
+    ) -> Result<TransactionScript, ClobError> {
+        self.chain.compile_tx_script(account_id, program)
+    }
+
+    fn execute(&mut self, tx_request: TransactionRequest) -> Result<TransactionResult, ClobError> {
+        self.chain.execute(tx_request)
+    }
+
+    async fn submit(
+        &mut self,
+        result: TransactionResult,
+        proven: ProvenTransaction,
+    ) -> Result<(), ClobError> {
+        let Some(rival) = self.rival.take() else {
+            return self.chain.submit(result, proven).await;
+        };
+        let (rival_proof, _) =
+            prove_transaction(rival.executed_transaction().clone()).await.unwrap();
+        self.chain.submit(rival, rival_proof).await.unwrap();
+        self.chain.produce_block().unwrap();
+
+        Err(ClobError::Backend("transaction rejected".to_string()))
+    }
+
+    async fn poll_commit(
+        &mut self,
+        transaction_id: TransactionId,
+        input_note_ids: &[NoteId],
+    ) -> Result<Option<u32>, ClobError> {
+        self.chain.poll_commit(transaction_id, input_note_ids).await
+    }
+}
+
+#[tokio::test]
+async fn rejections_of_nullified_notes_are_classified_as_conflicts() {
+    let (mut chain, asset, maker, taker) = funded_chain().await;
+    let note = send_p2id(&mut chain, &maker, &taker, asset).await;
+    let consume = |chain: &SimulatedChain| {
+        let notes = BTreeMap::from([(note.id(), None)]);
+        build_consume_notes_tx_req(chain, taker.id(), notes).unwrap()
+    };
+
+    let rival = chain.execute(consume(&chain)).unwrap();
+    let tx_req = consume(&chain);
+    let mut backend = RivalChain {
+        chain,
+        rival: Some(rival),
+    };
+
+    let err = execute_on(&mut backend, tx_req).await.unwrap_err();
+    assert!(matches!(&err, ClobError::NoteAlreadyConsumed(ids) if ids == &vec![note.id()]));
+    assert!(backend.chain.is_consumed(&note.id()));
+}
+
+/// A chain on which the node accepts a number of submissions, then rejects the
+/// next one
+struct RejectingChain {
+    chain: SimulatedChain,
+    accepted: usize,
+}
+
+#[tonic::async_trait]
+impl ClobBackend for RejectingChain {
+    async fn sync(&mut self) -> Result<u32, ClobError> {
+        self.chain.sync().await
+    }
+
+    fn get_notes(&self, filter: NoteFilter) -> Result<Vec<InputNoteRecord>, ClobError> {
+        self.chain.get_notes(filter)
+    }
+
+    fn get_account(&self, account_id: AccountId) -> Result<Account, ClobError> {
+        self.chain.get_account(account_id)
+    }
+
+    fn new_wallet(&mut self, storage_mode: AccountStorageMode) -> Result<Account, ClobError> {
+        self.chain.new_wallet(storage_mode)
+    }
+
+    fn new_faucet(
+        &mut self,
+        symbol: TokenSymbol,
+        decimals: u8,
+        max_supply: u64,
+        storage_mode: AccountStorageMode,
+    ) -> Result<Account, ClobError> {
+        self.chain.new_faucet(symbol, decimals, max_supply, storage_mode)
+    }
+
+    fn compile_tx_script(
+        &self,
+        account_id: AccountId,
+        program: ProgramAst,
+    ) -> Result<TransactionScript, ClobError> {
+        self.chain.compile_tx_script(account_id, program)
+    }
+
+    fn execute(&mut self, tx_request: TransactionRequest) -> Result<TransactionResult, ClobError> {
+        self.chain.execute(tx_request)
+    }
+
+    async fn submit(
+        &mut self,
+        result: TransactionResult,
+        proven: ProvenTransaction,
+    ) -> Result<(), ClobError> {
+        if self.accepted == 0 {
+            return Err(ClobError::Backend("transaction rejected".to_string()));
+        }
+        self.accepted -= 1;
+        self.chain.submit(result, proven).await
+    }
+
+    async fn poll_commit(
+        &mut self,
+        transaction_id: TransactionId,
+        input_note_ids: &[NoteId],
+    ) -> Result<Option<u32>, ClobError> {
+        self.chain.poll_commit(transaction_id, input_note_ids).await
+    }
+}
+
+#[tokio::test]
+async fn rejected_batches_report_the_transactions_committed_before() {
+    let mut chain = SimulatedChain::new();
+    let mut new_faucet = |symbol| {
+        let symbol = TokenSymbol::new(symbol).unwrap();
+        chain.new_faucet(symbol, 8, 1_000_000, AccountStorageMode::Local).unwrap()
+    };
+    let (eth_faucet, dai_faucet) = (new_faucet("ETH"), new_faucet("DAI"));
+    let alice = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let requests = [MintRequest::new(
+        alice.id(),
+        vec![
+            FungibleAsset::new(eth_faucet.id(), 10).unwrap(),
+            FungibleAsset::new(dai_faucet.id(), 20).unwrap(),
+        ],
+    )];
+
+    // Both mints are submitted in one round, and only the first one goes through
+    let mut backend = RejectingChain { chain, accepted: 1 };
+    let report = mint_batch(&mut backend, &requests).await;
+
+    assert!(matches!(report.error, Some(ClobError::Backend(_))));
+    assert_eq!(report.receipts.len(), 1);
+    let receipt = &report.receipts[0];
+    assert_eq!(receipt.block_num, 1);
+    let minted = receipt.created_notes().map(Note::id).collect::<Vec<_>>();
+    assert_eq!(minted.len(), 1);
+
+    // The committed mint is left for alice to consume
+    assert_eq!(backend.chain.note_block(&minted[0]), Some(1));
+    assert!(!backend.chain.is_consumed(&minted[0]));
+    assert_eq!(backend.chain.pending_transactions(), 0);
+}
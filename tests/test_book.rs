@@ -0,0 +1,40 @@
+pub mod helpers;
+
+use keom_clob::book::OrderBook;
+
+use crate::helpers::{dai, eth, note_id, order};
+
+/// Asks at 3 and 4 DAI per ETH and a bid at 2 DAI per ETH
+fn book() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(400)));
+    book.insert(order(2, eth(100), dai(300)));
+    book.insert(order(3, dai(200), eth(100)));
+    book
+}
+
+#[test]
+fn offers_are_sorted_cheapest_first() {
+    let book = book();
+
+    let asks = book.offers(eth(0).faucet_id(), dai(0).faucet_id());
+    let ask_ids = asks.iter().map(|order| order.note_id).collect::<Vec<_>>();
+    assert_eq!(ask_ids, vec![note_id(2), note_id(1)]);
+
+    let fillable = book.fillable_offers(eth(0).faucet_id(), dai(0).faucet_id(), 150);
+    assert_eq!(fillable[0].1.out_amount, 50);
+}
+
+#[test]
+fn crossing_orders_are_detected() {
+    let book = book();
+
+    // A bid at 3.5 DAI per ETH trades against the ask at 3
+    assert_eq!(book.crossing_orders(&dai(350), &eth(100)), vec![note_id(2)]);
+    // A bid at 4 DAI per ETH trades against both asks
+    assert_eq!(book.crossing_orders(&dai(400), &eth(100)), vec![note_id(2), note_id(1)]);
+    // A bid at 2.5 DAI per ETH rests on the book
+    assert!(book.crossing_orders(&dai(250), &eth(100)).is_empty());
+    // An ask at 2 DAI per ETH trades against the bid
+    assert_eq!(book.crossing_orders(&eth(100), &dai(200)), vec![note_id(3)]);
+}
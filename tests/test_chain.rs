@@ -0,0 +1,181 @@
+pub mod helpers;
+
+use std::collections::BTreeMap;
+
+use keom_clob::backend::{execute_on, prove_transaction, ClobBackend, SimulatedChain};
+use keom_clob::build_consume_notes_tx_req;
+use keom_clob::errors::ClobError;
+use miden_client::client::accounts::AccountStorageMode;
+use miden_lib::notes::create_p2id_note;
+use miden_lib::transaction::TransactionKernel;
+use miden_objects::accounts::Account;
+use miden_objects::assembly::ProgramAst;
+use miden_objects::assets::{Asset, FungibleAsset};
+use miden_objects::notes::{
+    Note, NoteAssets, NoteExecutionMode, NoteInputs, NoteMetadata, NoteRecipient, NoteScript,
+    NoteTag, NoteType,
+};
+use vm_processor::crypto::RpoRandomCoin;
+use vm_processor::Felt;
+
+use crate::helpers::{dai, eth, maker_id};
+
+/// A P2ID note from the dummy maker to `target`
+fn p2id(seed: u64, target: &Account, asset: FungibleAsset) -> Note {
+    let rng = RpoRandomCoin::new([Felt::new(seed), Felt::new(2), Felt::new(3), Felt::new(4)]);
+    create_p2id_note(maker_id(), target.id(), vec![asset.into()], NoteType::OffChain, rng).unwrap()
+}
+
+/// A note without assets that only `target` can consume up to block `expiry`
+fn expiring(target: &Account, expiry: u32) -> Note {
+    let program = ProgramAst::parse(&format!(
+        "use.miden::tx\n\
+         begin\n\
+         dropw\n\
+         exec.tx::get_block_number push.{expiry} lte assert\n\
+         end"
+    ))
+    .unwrap();
+    let (script, _) = NoteScript::new(program, &TransactionKernel::assembler()).unwrap();
+    let serial_num = [
+        Felt::new(expiry as u64),
+        Felt::new(2),
+        Felt::new(3),
+        Felt::new(4),
+    ];
+    let recipient = NoteRecipient::new(serial_num, script, NoteInputs::new(vec![]).unwrap());
+    let tag = NoteTag::from_account_id(target.id(), NoteExecutionMode::Local).unwrap();
+    let metadata = NoteMetadata::new(maker_id(), NoteType::OffChain, tag, Felt::new(0)).unwrap();
+    Note::new(NoteAssets::new(vec![]).unwrap(), metadata, recipient)
+}
+
+fn balance(chain: &SimulatedChain, account: &Account, asset: FungibleAsset) -> u64 {
+    let account = chain.get_account(account.id()).unwrap();
+    account.vault().get_balance(asset.faucet_id()).unwrap()
+}
+
+#[tokio::test]
+async fn notes_are_consumable_from_the_block_including_them() {
+    let mut chain = SimulatedChain::new();
+    let wallet = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    chain.produce_block().unwrap();
+    chain.produce_block().unwrap();
+
+    let note = p2id(1, &wallet, eth(100));
+    chain.add_note(note.clone());
+    assert_eq!(chain.note_block(&note.id()), None);
+
+    let notes = BTreeMap::from([(note.id(), None)]);
+    let tx_req = build_consume_notes_tx_req(&chain, wallet.id(), notes.clone()).unwrap();
+    assert!(matches!(chain.execute(tx_req), Err(ClobError::NotFound(_))));
+
+    assert_eq!(chain.produce_block().unwrap(), 3);
+    assert_eq!(chain.note_block(&note.id()), Some(3));
+
+    let tx_req = build_consume_notes_tx_req(&chain, wallet.id(), notes).unwrap();
+    let receipt = execute_on(&mut chain, tx_req).await.unwrap();
+    assert_eq!(receipt.block_num, 4);
+    assert!(chain.is_consumed(&note.id()));
+    assert_eq!(balance(&chain, &wallet, eth(0)), 100);
+}
+
+#[tokio::test]
+async fn deltas_apply_to_each_account_in_the_block_committing_them() {
+    let mut chain = SimulatedChain::new();
+    let maker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let taker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let to_maker = p2id(1, &maker, eth(100));
+    let to_taker = p2id(2, &taker, dai(300));
+    chain.add_note(to_maker.clone());
+    chain.produce_block().unwrap();
+    chain.add_note(to_taker.clone());
+    chain.produce_block().unwrap();
+
+    // Executing does not touch the chain until the transaction is committed
+    let notes = BTreeMap::from([(to_maker.id(), None)]);
+    let tx_req = build_consume_notes_tx_req(&chain, maker.id(), notes.clone()).unwrap();
+    let result = chain.execute(tx_req).unwrap();
+    let added = &result.executed_transaction().account_delta().vault().added_assets;
+    assert_eq!(added, &vec![Asset::from(eth(100))]);
+    assert_eq!(balance(&chain, &maker, eth(0)), 0);
+    assert_eq!(chain.note_block(&to_maker.id()), Some(1));
+
+    let tx_req = build_consume_notes_tx_req(&chain, maker.id(), notes).unwrap();
+    let maker_receipt = execute_on(&mut chain, tx_req).await.unwrap();
+    let notes = BTreeMap::from([(to_taker.id(), None)]);
+    let tx_req = build_consume_notes_tx_req(&chain, taker.id(), notes).unwrap();
+    let taker_receipt = execute_on(&mut chain, tx_req).await.unwrap();
+
+    assert_eq!((maker_receipt.block_num, taker_receipt.block_num), (3, 4));
+    assert_eq!(balance(&chain, &maker, eth(0)), 100);
+    assert_eq!(balance(&chain, &maker, dai(0)), 0);
+    assert_eq!(balance(&chain, &taker, dai(0)), 300);
+
+    // Consumed notes are gone for everyone
+    let notes = BTreeMap::from([(to_maker.id(), None)]);
+    let tx_req = build_consume_notes_tx_req(&chain, taker.id(), notes).unwrap();
+    assert!(matches!(chain.execute(tx_req), Err(ClobError::NoteAlreadyConsumed(_))));
+}
+
+#[tokio::test]
+async fn failed_blocks_leave_the_chain_untouched() {
+    let mut chain = SimulatedChain::new();
+    let maker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let taker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let to_maker = p2id(1, &maker, eth(100));
+    let to_taker = p2id(2, &taker, dai(300));
+    chain.add_note(to_maker.clone());
+    chain.add_note(to_taker.clone());
+    chain.produce_block().unwrap();
+
+    for (account, note) in [(&maker, &to_maker), (&taker, &to_taker)] {
+        let notes = BTreeMap::from([(note.id(), None)]);
+        let tx_req = build_consume_notes_tx_req(&chain, account.id(), notes).unwrap();
+        let result = chain.execute(tx_req).unwrap();
+        let (proven, _) = prove_transaction(result.executed_transaction().clone()).await.unwrap();
+        chain.submit(result, proven).await.unwrap();
+    }
+
+    // The taker moved on in the meantime, so its delta no longer applies
+    let moved = Account::new(
+        taker.id(),
+        taker.vault().clone(),
+        taker.storage().clone(),
+        taker.code().clone(),
+        Felt::new(100),
+    );
+    chain.add_account(moved);
+    assert!(chain.produce_block().is_err());
+
+    assert_eq!(chain.block_num(), 1);
+    assert_eq!(chain.pending_transactions(), 2);
+    assert_eq!(balance(&chain, &maker, eth(0)), 0);
+    assert!(!chain.is_consumed(&to_maker.id()));
+
+    chain.add_account(taker.clone());
+    assert_eq!(chain.produce_block().unwrap(), 2);
+    assert_eq!(balance(&chain, &maker, eth(0)), 100);
+    assert_eq!(balance(&chain, &taker, dai(0)), 300);
+}
+
+#[tokio::test]
+async fn transactions_execute_at_the_height_of_the_chain() {
+    let mut chain = SimulatedChain::new();
+    let wallet = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    assert_eq!(chain.advance_blocks(10).unwrap(), 10);
+
+    let note = expiring(&wallet, 12);
+    chain.add_note(note.clone());
+    assert_eq!(chain.produce_block().unwrap(), 11);
+
+    let notes = BTreeMap::from([(note.id(), None)]);
+    let tx_req = build_consume_notes_tx_req(&chain, wallet.id(), notes.clone()).unwrap();
+    let result = chain.execute(tx_req).unwrap();
+    assert_eq!(result.executed_transaction().block_header().block_num(), 11);
+
+    // Past its expiry the same note no longer executes
+    assert_eq!(chain.advance_blocks(2).unwrap(), 13);
+    let tx_req = build_consume_notes_tx_req(&chain, wallet.id(), notes).unwrap();
+    assert!(matches!(chain.execute(tx_req), Err(ClobError::Backend(_))));
+    assert!(!chain.is_consumed(&note.id()));
+}
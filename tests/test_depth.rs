@@ -0,0 +1,76 @@
+pub mod helpers;
+
+use keom_clob::book::OrderBook;
+use keom_clob::depth::{depth, DepthTracker, Level, LevelChange, LevelDiff};
+use keom_clob::market::{Pair, Price, Side};
+
+use crate::helpers::{dai, eth, note_id, order};
+
+fn level(quote: u64, base: u64, size: u64, orders: usize) -> Level {
+    Level {
+        price: Price::new(quote, base).unwrap(),
+        size,
+        orders,
+    }
+}
+
+fn eth_dai() -> Pair {
+    Pair::new(eth(0).faucet_id(), dai(0).faucet_id())
+}
+
+#[test]
+fn orders_are_aggregated_per_price_level() {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(300)));
+    book.insert(order(2, eth(50), dai(150)));
+    book.insert(order(3, eth(100), dai(400)));
+    book.insert(order(4, dai(200), eth(100)));
+    book.insert(order(5, dai(250), eth(100)));
+
+    let depth = depth(&book, eth_dai(), 1);
+    assert_eq!(depth.asks, vec![level(3, 1, 150, 2)]);
+    assert_eq!(depth.bids, vec![level(5, 2, 100, 1)]);
+}
+
+#[test]
+fn tracker_emits_sequenced_level_diffs() {
+    let mut tracker = DepthTracker::new(eth_dai());
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(300)));
+
+    let update = tracker.update(&book).unwrap();
+    assert_eq!(update.sequence, 1);
+    assert_eq!(
+        update.diffs,
+        vec![LevelDiff {
+            side: Side::Ask,
+            change: LevelChange::Added(level(3, 1, 100, 1)),
+        }]
+    );
+    assert!(tracker.update(&book).is_none());
+
+    // A partial fill replaces the order with a smaller clone at the same price
+    book.remove(&note_id(1));
+    book.insert(order(2, eth(40), dai(120)));
+    book.insert(order(3, dai(200), eth(100)));
+
+    let update = tracker.update(&book).unwrap();
+    assert_eq!(update.sequence, 2);
+    assert_eq!(update.diffs.len(), 2);
+    assert!(update.diffs.contains(&LevelDiff {
+        side: Side::Ask,
+        change: LevelChange::Changed(level(3, 1, 40, 1)),
+    }));
+
+    book.remove(&note_id(3));
+    let update = tracker.update(&book).unwrap();
+    assert_eq!(update.sequence, 3);
+    assert_eq!(
+        update.diffs,
+        vec![LevelDiff {
+            side: Side::Bid,
+            change: LevelChange::Removed(Price::new(2, 1).unwrap()),
+        }]
+    );
+    assert_eq!(tracker.snapshot(10).1.asks, vec![level(3, 1, 40, 1)]);
+}
@@ -0,0 +1,150 @@
+pub mod helpers;
+
+use std::collections::BTreeSet;
+
+use keom_clob::book::OrderBook;
+use keom_clob::feed::{FeedEvent, MarketFeed, OrderState, Subscription};
+use keom_clob::market::{Pair, Side};
+use keom_clob::order::LimitOrder;
+use keom_clob::registry::OrderRegistry;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+
+use crate::helpers::{dai, digest, eth, note_id};
+
+/// Order whose payback and partial recipients are derived from `serial`, as
+/// its clones share the partial recipient
+fn order(seed: u64, serial: u64, offered: FungibleAsset, requested: FungibleAsset) -> LimitOrder {
+    LimitOrder {
+        payback_recipient: digest(100 + seed),
+        partial_recipient: digest(serial),
+        ..helpers::order(seed, offered, requested)
+    }
+}
+
+fn eth_dai() -> Pair {
+    Pair::new(eth(0).faucet_id(), dai(0).faucet_id())
+}
+
+fn trades(events: &[FeedEvent]) -> Vec<(NoteId, Side, u64)> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            FeedEvent::Trade(trade) => Some((trade.note_id, trade.side, trade.size)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn order_state(events: &[FeedEvent], note: NoteId) -> Option<OrderState> {
+    events.iter().find_map(|event| match event {
+        FeedEvent::Order(update) if update.note_id == note => Some(update.state),
+        _ => None,
+    })
+}
+
+#[test]
+fn trades_are_detected_from_clones_and_paybacks() {
+    let registry = OrderRegistry::load(std::env::temp_dir().join("keom-feed-unused.json")).unwrap();
+    let mut feed = MarketFeed::new([eth_dai()]);
+    let mut book = OrderBook::new();
+    book.insert(order(1, 1, eth(100), dai(300)));
+    book.insert(order(2, 2, dai(400), eth(100)));
+    book.insert(order(3, 3, eth(100), dai(500)));
+
+    let events = feed.update(&book, &registry, &BTreeSet::new());
+    assert_eq!(order_state(&events, note_id(1)), Some(OrderState::Open));
+    assert!(trades(&events).is_empty());
+
+    // The ask is partially filled, the bid fully filled and the last ask
+    // disappears without any trace
+    book.remove(&note_id(1));
+    book.insert(order(4, 1, eth(40), dai(120)));
+    book.remove(&note_id(2));
+    book.remove(&note_id(3));
+    let paybacks = BTreeSet::from([digest(102)]);
+
+    let events = feed.update(&book, &registry, &paybacks);
+    assert_eq!(trades(&events), vec![(note_id(1), Side::Ask, 60), (note_id(2), Side::Bid, 100)]);
+    assert_eq!(
+        order_state(&events, note_id(1)),
+        Some(OrderState::PartiallyFilled {
+            remaining: note_id(4)
+        })
+    );
+    assert_eq!(order_state(&events, note_id(2)), Some(OrderState::Filled));
+    assert_eq!(order_state(&events, note_id(3)), Some(OrderState::Closed));
+    assert_eq!(order_state(&events, note_id(4)), Some(OrderState::Open));
+}
+
+#[test]
+fn replaced_orders_are_reported_without_trades() {
+    let mut registry = OrderRegistry::in_memory();
+    let mut feed = MarketFeed::new([eth_dai()]);
+    let mut book = OrderBook::new();
+    let old = order(1, 1, eth(100), dai(300));
+    registry.record_placed(&old).unwrap();
+    book.insert(old.clone());
+    feed.update(&book, &registry, &BTreeSet::new());
+
+    // The new note looks like a clone of the old one, yet nothing was traded
+    let new = order(2, 1, eth(50), dai(200));
+    registry.record_replaced(&old, &new).unwrap();
+    book.remove(&old.note_id);
+    book.insert(new.clone());
+
+    let events = feed.update(&book, &registry, &BTreeSet::new());
+    assert!(trades(&events).is_empty());
+    assert_eq!(order_state(&events, old.note_id), Some(OrderState::Replaced { by: new.note_id }));
+    assert_eq!(order_state(&events, new.note_id), Some(OrderState::Open));
+}
+
+#[test]
+fn subscriptions_filter_by_pair_and_account() {
+    let registry = OrderRegistry::load(std::env::temp_dir().join("keom-feed-unused.json")).unwrap();
+    let mut feed = MarketFeed::new([eth_dai()]);
+    let mut book = OrderBook::new();
+    book.insert(order(1, 1, eth(100), dai(300)));
+    let events = feed.update(&book, &registry, &BTreeSet::new());
+    assert!(!events.is_empty());
+
+    let everything = Subscription::default();
+    assert!(events.iter().all(|event| everything.matches(event)));
+
+    let other_pair = Subscription {
+        pairs: BTreeSet::from([Pair::new(dai(0).faucet_id(), eth(0).faucet_id())]),
+        ..Subscription::default()
+    };
+    assert!(!events.iter().any(|event| other_pair.matches(event)));
+
+    // Depth is market-wide, order updates belong to the maker
+    let other_account = Subscription {
+        accounts: BTreeSet::from([eth(0).faucet_id()]),
+        ..Subscription::default()
+    };
+    let matched = events.iter().filter(|event| other_account.matches(event)).collect::<Vec<_>>();
+    assert!(matched.iter().all(|event| matches!(event, FeedEvent::Depth(_))));
+    assert_eq!(matched.len(), 1);
+}
+
+#[test]
+fn snapshots_cover_the_subscribed_markets() {
+    let registry = OrderRegistry::in_memory();
+    let mut feed = MarketFeed::new([eth_dai()]);
+    let mut book = OrderBook::new();
+    book.insert(order(1, 1, eth(100), dai(300)));
+    book.insert(order(2, 2, dai(400), eth(100)));
+    let events = feed.update(&book, &registry, &BTreeSet::new());
+
+    let snapshots = feed.snapshots(&Subscription::default());
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!((snapshots[0].pair, snapshots[0].sequence), (eth_dai(), 1));
+    assert_eq!(snapshots[0].diffs.len(), 2);
+    assert!(events.contains(&FeedEvent::Depth(snapshots[0].clone())));
+
+    let other_pair = Subscription {
+        pairs: BTreeSet::from([Pair::new(dai(0).faucet_id(), eth(0).faucet_id())]),
+        ..Subscription::default()
+    };
+    assert!(feed.snapshots(&other_pair).is_empty());
+}
@@ -0,0 +1,258 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use keom_clob::backend::SimulatedChain;
+use keom_clob::clob::Clob;
+use keom_clob::config::{ClobConfig, MarketConfig};
+use keom_clob::grpc::proto::clob_client::ClobClient;
+use keom_clob::grpc::proto::{self, Side};
+use keom_clob::grpc::{ClobApi, ClobService};
+use keom_clob::profiles::AccountProfiles;
+use keom_clob::registry::OrderRegistry;
+use keom_clob::server::{run_feed, AppState};
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::StreamExt;
+use tonic::transport::{Channel, Server};
+use tonic::{Code, Streaming};
+use vm_processor::Digest;
+
+/// A CLOB over a simulated chain with an ETH/DAI market, whose tokens have no
+/// decimals, and "alice" holding 10 ETH
+struct Fixture {
+    state: AppState<SimulatedChain>,
+    eth: AccountId,
+    dai: AccountId,
+}
+
+impl Fixture {
+    async fn new() -> Self {
+        let mut clob = Clob::new(
+            SimulatedChain::new(),
+            AccountProfiles::in_memory(),
+            OrderRegistry::in_memory(),
+            &ClobConfig::default(),
+        )
+        .unwrap();
+        let eth = clob.new_faucet("ETH", 0, 1_000_000).unwrap().id();
+        let dai = clob.new_faucet("DAI", 0, 1_000_000).unwrap().id();
+        clob.add_markets(&[MarketConfig {
+            base: "ETH".to_string(),
+            quote: "DAI".to_string(),
+            tick_size: "1".to_string(),
+            lot_size: "1".to_string(),
+        }])
+        .unwrap();
+
+        clob.new_account("alice").unwrap();
+        let eth_10 = vec![FungibleAsset::new(eth, 10).unwrap()];
+        clob.mint("alice", eth_10).await.unwrap().into_result().unwrap();
+
+        Self {
+            state: AppState::new(clob).unwrap(),
+            eth,
+            dai,
+        }
+    }
+
+    fn eth(&self, amount: u64) -> proto::Asset {
+        FungibleAsset::new(self.eth, amount).unwrap().into()
+    }
+
+    fn dai(&self, amount: u64) -> proto::Asset {
+        FungibleAsset::new(self.dai, amount).unwrap().into()
+    }
+
+    fn pair(&self) -> proto::Pair {
+        proto::Pair {
+            base: self.eth.to_hex(),
+            quote: self.dai.to_hex(),
+        }
+    }
+
+    /// Serves the service over the shared CLOB on a local port, returning a
+    /// connected client
+    async fn connect(&self) -> ClobClient<Channel> {
+        let service = ClobService::new(self.state.clob.clone(), &self.state);
+        connect(service).await
+    }
+
+    /// Places an order of alice, returning its note id
+    async fn place(&self, client: &mut ClobClient<Channel>, eth: u64, dai: u64) -> String {
+        let receipt = client
+            .place_order(proto::PlaceOrderRequest {
+                maker: "alice".to_string(),
+                offered: Some(self.eth(eth)),
+                requested: Some(self.dai(dai)),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(receipt.orders.len(), 1);
+        receipt.orders[0].clone()
+    }
+
+    async fn stream_book(&self, client: &mut ClobClient<Channel>) -> Streaming<proto::BookUpdate> {
+        client
+            .stream_book(proto::StreamBookRequest {
+                pair: Some(self.pair()),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+    }
+}
+
+async fn connect<A: ClobApi>(service: ClobService<A>) -> ClobClient<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(service.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    ClobClient::connect(format!("http://{addr}")).await.unwrap()
+}
+
+#[tokio::test]
+async fn placed_orders_are_quoted_and_shown() {
+    let fixture = Fixture::new().await;
+    let mut client = fixture.connect().await;
+
+    // Asks at 3 and 4 DAI per ETH
+    let cheap = fixture.place(&mut client, 5, 15).await;
+    let dear = fixture.place(&mut client, 3, 12).await;
+
+    let quote = client
+        .quote(proto::QuoteRequest {
+            want: Some(fixture.eth(6)),
+            give_faucet_id: fixture.dai.to_hex(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(quote.paid, Some(fixture.dai(19)));
+    assert_eq!(quote.received, Some(fixture.eth(6)));
+    assert_eq!(
+        quote.fills.iter().map(|fill| fill.note_id.clone()).collect::<Vec<_>>(),
+        vec![cheap.clone(), dear]
+    );
+
+    let order =
+        client.get_order(proto::GetOrderRequest { note_id: cheap }).await.unwrap().into_inner();
+    assert_eq!(order.offered, Some(fixture.eth(5)));
+    assert_eq!(order.requested, Some(fixture.dai(15)));
+}
+
+#[tokio::test]
+async fn errors_map_to_status_codes() {
+    let fixture = Fixture::new().await;
+    let mut client = fixture.connect().await;
+
+    let status = client
+        .quote(proto::QuoteRequest {
+            want: Some(fixture.eth(1)),
+            give_faucet_id: fixture.dai.to_hex(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    let status = client
+        .take(proto::TakeRequest {
+            taker: "alice".to_string(),
+            give: Some(fixture.eth(1)),
+            want_faucet_id: fixture.dai.to_hex(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    let status = client
+        .get_order(proto::GetOrderRequest {
+            note_id: "not a note".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = client
+        .place_order(proto::PlaceOrderRequest {
+            maker: "alice".to_string(),
+            offered: Some(fixture.eth(1)),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = client
+        .place_order(proto::PlaceOrderRequest {
+            maker: "bob".to_string(),
+            offered: Some(fixture.eth(1)),
+            requested: Some(fixture.dai(3)),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = client
+        .cancel_order(proto::CancelOrderRequest {
+            maker: "alice".to_string(),
+            note_id: NoteId::from(Digest::default()).to_hex(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = client
+        .stream_book(proto::StreamBookRequest {
+            pair: Some(proto::Pair {
+                base: fixture.dai.to_hex(),
+                quote: fixture.eth.to_hex(),
+            }),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn book_streams_start_from_a_snapshot_then_follow_the_feed() {
+    let fixture = Fixture::new().await;
+    let mut client = fixture.connect().await;
+    fixture.place(&mut client, 5, 15).await;
+    fixture.place(&mut client, 3, 12).await;
+
+    // Before the feed runs, the snapshot is of the empty book
+    let mut early = fixture.stream_book(&mut client).await;
+    let first = early.next().await.unwrap().unwrap();
+    assert_eq!((first.sequence, first.levels.len()), (0, 0));
+
+    tokio::spawn(run_feed(fixture.state.clone(), Duration::from_millis(10)));
+    let second = early.next().await.unwrap().unwrap();
+    assert_eq!(second.sequence, 1);
+    assert_eq!(second.levels.len(), 2);
+    assert!(second.levels.iter().all(|level| level.side() == Side::Ask && level.orders == 1));
+
+    // Later subscribers get the same levels in their snapshot
+    let mut late = fixture.stream_book(&mut client).await;
+    let snapshot = late.next().await.unwrap().unwrap();
+    assert_eq!(snapshot, second);
+
+    // An ask at 5 DAI per ETH only adds its level
+    fixture.place(&mut client, 2, 10).await;
+    for stream in [&mut early, &mut late] {
+        let update = stream.next().await.unwrap().unwrap();
+        assert_eq!(update.sequence, 2);
+        assert_eq!(update.levels.len(), 1);
+        let added = &update.levels[0];
+        assert_eq!((added.side(), added.size, added.orders), (Side::Ask, 2, 1));
+        assert_eq!(added.price, Some(proto::Price { quote: 10, base: 2 }));
+    }
+}
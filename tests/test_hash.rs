@@ -1,46 +1,123 @@
-// use miden_objects::assembly::Assembler;
-// use miden_objects::Felt;
-// use miden_objects::Hasher;
-// use vm_processor::DefaultHost;
-// use vm_processor::ExecutionOptions;
-// use vm_processor::StackInputs;
-// use vm_processor::Word;
-
-// #[test]
-// fn test_hash() {
-//     let hash_inputs: &[Felt] = &[Felt::from(110 as u8), Felt::from(120 as u8)];
-
-//     // From Rust
-//     let hash = Hasher::hash_elements(hash_inputs);
-
-//     // From VM
-//     let masm = r#"
-//       begin
-//       debug.stack
-//       hash
-//       debug.stack
-//       end
-//     "#;
-
-//     let assembler = Assembler::default().with_debug_mode(true);
-//     let program = assembler.compile(masm).unwrap();
-//     let result = vm_processor::execute(
-//         &program,
-//         StackInputs::new(hash_inputs.to_vec()),
-//         DefaultHost::default(),
-//         ExecutionOptions::default(),
-//     )
-//     .unwrap();
-
-//     let stack_out = result
-//         .stack_outputs()
-//         .stack()
-//         .iter()
-//         .take(4)
-//         .rev()
-//         .cloned()
-//         .collect::<Vec<_>>();
-
-//     println!("Hash: {:?}", hash);
-//     println!("Result: {:?}", stack_out);
-// }
+pub mod helpers;
+
+use keom_clob::{build_partial_recipient, create_limit_swap_note, limit_swap_note_script};
+use miden_lib::notes::create_p2id_note;
+use miden_lib::notes::utils::build_p2id_recipient;
+use miden_objects::crypto::rand::{FeltRng, RpoRandomCoin};
+use miden_objects::notes::{Note, NoteInputs, NoteType};
+use miden_objects::{Digest, Felt, Hasher, Word};
+
+use crate::helpers::{
+    dai, eth, execute_masm_word, maker_id, masm_push_word, taker_id, vm_hash_elements, vm_merge,
+};
+
+fn word(seed: u64) -> Word {
+    [
+        Felt::new(seed),
+        Felt::new(seed + 1),
+        Felt::new(seed + 2),
+        Felt::new(seed + 3),
+    ]
+}
+
+fn limit_swap_note() -> Note {
+    let rng = RpoRandomCoin::new(word(1));
+    create_limit_swap_note(maker_id(), eth(100).into(), dai(300).into(), rng).unwrap()
+}
+
+#[test]
+fn hash_instruction_matches_hash_elements() {
+    let input = word(110);
+    let vm_hash = execute_masm_word(&format!("begin {} hash end", masm_push_word(&input)));
+
+    assert_eq!(Digest::from(vm_hash), Hasher::hash_elements(&input));
+}
+
+#[test]
+fn hash_memory_matches_hash_elements() {
+    for len in 1..=16u64 {
+        let elements = (0..len).map(|i| Felt::new(i * 7 + 1)).collect::<Vec<_>>();
+        assert_eq!(
+            Digest::from(vm_hash_elements(&elements)),
+            Hasher::hash_elements(&elements),
+            "{len} elements"
+        );
+    }
+}
+
+#[test]
+fn hmerge_matches_merge() {
+    let (left, right) = (Digest::new(word(1)), Digest::new(word(5)));
+
+    assert_eq!(Digest::from(vm_merge(left, right)), Hasher::merge(&[left, right]));
+}
+
+#[test]
+fn partial_recipient_matches_vm() {
+    let script = limit_swap_note_script();
+    let serial_num = word(42);
+
+    let vm_partial = execute_masm_word(&format!(
+        "begin {} padw hmerge {} hmerge end",
+        masm_push_word(&serial_num),
+        masm_push_word(&script.hash().into()),
+    ));
+
+    assert_eq!(Digest::from(vm_partial), build_partial_recipient(script, serial_num).unwrap());
+}
+
+#[test]
+fn limit_swap_recipient_and_note_id_match_vm() {
+    let note = limit_swap_note();
+    let inputs = note.inputs().values().to_vec();
+    let partial_recipient = Digest::new([inputs[12], inputs[13], inputs[14], inputs[15]]);
+
+    // Inputs hash, as committed to by the note recipient
+    let vm_inputs_hash = Digest::from(vm_hash_elements(&inputs));
+    assert_eq!(vm_inputs_hash, NoteInputs::new(inputs).unwrap().commitment());
+
+    let vm_recipient = Digest::from(vm_merge(partial_recipient, vm_inputs_hash));
+    assert_eq!(vm_recipient, note.recipient().digest());
+
+    // A single asset is padded with an empty word before hashing
+    let asset: Word = (*note.assets().iter().next().unwrap()).into();
+    let vm_assets_hash = vm_hash_elements(&[asset, [Felt::new(0); 4]].concat());
+    assert_eq!(Digest::from(vm_assets_hash), note.assets().commitment());
+
+    let vm_note_id = Digest::from(vm_merge(vm_recipient, Digest::from(vm_assets_hash)));
+    assert_eq!(vm_note_id, note.id().inner());
+}
+
+/// Recipient of the P2ID payback note to the maker computed in the VM, from
+/// the script and inputs of any P2ID note to the maker
+fn vm_payback_recipient(serial_num: Word) -> Digest {
+    let rng = RpoRandomCoin::new(word(3));
+    let p2id =
+        create_p2id_note(taker_id(), maker_id(), vec![dai(1).into()], NoteType::OffChain, rng)
+            .unwrap();
+    let inputs = p2id.inputs().values().to_vec();
+    let vm_inputs_hash = Digest::from(vm_hash_elements(&inputs));
+    assert_eq!(vm_inputs_hash, p2id.inputs().commitment());
+
+    let vm_partial = execute_masm_word(&format!(
+        "begin {} padw hmerge {} hmerge end",
+        masm_push_word(&serial_num),
+        masm_push_word(&p2id.script().hash().into()),
+    ));
+    Digest::from(vm_merge(Digest::from(vm_partial), vm_inputs_hash))
+}
+
+#[test]
+fn payback_recipient_matches_vm() {
+    let serial_num = word(7);
+    let recipient = build_p2id_recipient(maker_id(), serial_num).unwrap();
+    assert_eq!(vm_payback_recipient(serial_num), recipient);
+
+    // The limit-swap note commits to the payback recipient of the first
+    // serial number drawn from its rng
+    let note = limit_swap_note();
+    let payback_serial_num = RpoRandomCoin::new(word(1)).draw_word();
+    let inputs = note.inputs().values();
+    let payback_recipient = Digest::new([inputs[0], inputs[1], inputs[2], inputs[3]]);
+    assert_eq!(vm_payback_recipient(payback_serial_num), payback_recipient);
+}
@@ -1,30 +1,22 @@
 pub mod helpers;
 
 //use super::*;
+use keom_clob::backend::{ClobBackend, SimulatedChain};
 use keom_clob::limit_swap::{build_partial_recipient, create_limit_swap_note};
+use miden_client::client::accounts::AccountStorageMode;
+use miden_client::client::transactions::transaction_request::TransactionRequest;
 use miden_lib::notes::utils::{build_note_script, build_p2id_recipient};
-use miden_mock::constants::{
-    ACCOUNT_ID_NON_FUNGIBLE_FAUCET_ON_CHAIN, ACCOUNT_ID_REGULAR_ACCOUNT_UPDATABLE_CODE_ON_CHAIN,
-    ACCOUNT_ID_SENDER, DEFAULT_AUTH_SCRIPT,
-};
-use miden_objects::accounts::{Account, AccountId, ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN};
+use miden_mock::constants::DEFAULT_AUTH_SCRIPT;
+use miden_objects::accounts::{Account, AccountId};
 use miden_objects::assembly::ProgramAst;
-use miden_objects::assets::{
-    Asset, AssetVault, FungibleAsset, NonFungibleAsset, NonFungibleAssetDetails,
-};
+use miden_objects::assets::{Asset, AssetVault, FungibleAsset};
 use miden_objects::crypto::rand::RpoRandomCoin;
 use miden_objects::notes::{Note, NoteAssets, NoteInputs, NoteMetadata, NoteRecipient};
-use miden_objects::transaction::{OutputNote, TransactionArgs};
-use miden_objects::vm::AdviceMap;
+use miden_objects::transaction::OutputNote;
 use miden_objects::{Felt, Hasher, Word, ZERO};
-use miden_tx::{ProvingOptions, TransactionExecutor, TransactionProver};
+use miden_tx::{ProvingOptions, TransactionProver};
 use std::collections::BTreeMap;
 
-use crate::helpers::{
-    get_account_with_default_account_code, get_new_key_pair_with_advice_map,
-    prove_and_verify_transaction, MockDataStore,
-};
-
 #[test]
 fn prove_limit_swap_script() {
     // Maker's initial offered/desired of limit swap order
@@ -63,13 +55,13 @@ fn prove_limit_swap_script() {
     // Create Accounts //
     /////////////////////
 
+    let mut chain = SimulatedChain::new();
+
     // Maker Account
     // Initialized without funds in account
 
-    let maker_account_id = AccountId::try_from(ACCOUNT_ID_SENDER).unwrap();
-    let (maker_pub_key, maker_sk_felt) = get_new_key_pair_with_advice_map();
-    let maker_account =
-        get_account_with_default_account_code(maker_account_id, maker_pub_key, None);
+    let maker_account = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let maker_account_id = maker_account.id();
 
     println!(">>>>>> Built agent: Maker");
 
@@ -87,14 +79,16 @@ fn prove_limit_swap_script() {
     // Taker Account
     // Initialized with the entirety of asset b
 
-    let taker_account_id =
-        AccountId::try_from(ACCOUNT_ID_REGULAR_ACCOUNT_UPDATABLE_CODE_ON_CHAIN).unwrap();
-    let (taker_pub_key, taker_sk_felt) = get_new_key_pair_with_advice_map();
-    let taker_account = get_account_with_default_account_code(
+    let taker_wallet = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let taker_account_id = taker_wallet.id();
+    let taker_account = Account::new(
         taker_account_id,
-        taker_pub_key,
-        Some(fungible_asset_b),
+        AssetVault::new(&[fungible_asset_b]).unwrap(),
+        taker_wallet.storage().clone(),
+        taker_wallet.code().clone(),
+        taker_wallet.nonce(),
     );
+    chain.add_account(taker_account.clone());
 
     taker_account.vault().assets().for_each(|asset| {
         println!("Taker asset: {:?}", asset);
@@ -108,30 +102,18 @@ fn prove_limit_swap_script() {
     //.     Test for success              //
     ////////////////////////////////////////
 
-    // Build dummy blockchain state as seen by Taker
-
-    // TODO: read from blockchain
-    let taker_data_store = MockDataStore::with_existing(
-        Some(taker_account.clone()),
-        Some(vec![limit_swap_note.clone()]),
-    );
-
-    let block_ref = taker_data_store.block_header.block_num();
-    let note_ids = vec![limit_swap_note.id()];
+    // Include the Maker's note in the simulated chain
 
-    println!(">>>>>> Initialized Taker's dummy blockchain");
+    chain.add_note(limit_swap_note.clone());
+    chain.produce_block().unwrap();
 
-    // Build Taker's TX executor
-
-    let mut taker_executor = TransactionExecutor::new(taker_data_store.clone());
-    taker_executor.load_account(taker_account_id).unwrap();
+    println!(">>>>>> Included Maker's note in the simulated chain");
 
     // Construct template transaction script for Taker
 
     let tx_script_code = ProgramAst::parse(DEFAULT_AUTH_SCRIPT).unwrap();
-    let tx_script_target = taker_executor
-        .compile_tx_script(tx_script_code.clone(), vec![(taker_pub_key, taker_sk_felt)], vec![])
-        .unwrap();
+    let tx_script_target =
+        chain.compile_tx_script(taker_account_id, tx_script_code.clone()).unwrap();
 
     // Build input arguments for Note consumption
     // Taker will consume amount_to_consume and satisfy amount_to_send
@@ -144,16 +126,15 @@ fn prove_limit_swap_script() {
         Felt::new(amount_to_send),
         Felt::new(amount_to_consume),
     ];
-    note_args_map.insert(limit_swap_note.id(), takers_args);
+    note_args_map.insert(limit_swap_note.id(), Some(takers_args));
 
-    let tx_args_taker =
-        TransactionArgs::new(Some(tx_script_target), Some(note_args_map), AdviceMap::new());
+    let tx_req_taker =
+        TransactionRequest::new(taker_account_id, note_args_map, vec![], Some(tx_script_target));
 
     // Execute Taker transaction
 
-    let transaction_result = taker_executor
-        .execute_transaction(taker_account_id, block_ref, &note_ids, tx_args_taker)
-        .unwrap();
+    let transaction_result = chain.execute(tx_req_taker).unwrap();
+    let transaction_result = transaction_result.executed_transaction();
 
     println!(">>>>>> Executed Taker's transaction");
 
@@ -297,29 +278,17 @@ fn prove_limit_swap_script() {
     assert_eq!(limit_clone_note_recipient, created_limit_clone_note_recipient.recipient().clone());
     println!(">>>>>> New dummy clone note matches output from Taker tests");
 
-    // Build dummy blockchain state as seen by Maker
-
-    let maker_data_store = MockDataStore::with_existing(
-        Some(maker_account.clone()),
-        Some(vec![limit_clone_note.clone()]),
-    );
-
-    let block_ref = maker_data_store.block_header.block_num();
-    let limit_clone_ids =
-        maker_data_store.notes.iter().map(|note| limit_clone_note.id()).collect::<Vec<_>>();
-
-    println!(">>>>>> Initialized Maker's's dummy blockchain");
+    // Include the clone note in the simulated chain
 
-    // Build Taker's TX executor
+    chain.add_note(limit_clone_note.clone());
+    chain.produce_block().unwrap();
 
-    let mut maker_executor = TransactionExecutor::new(maker_data_store.clone());
-    maker_executor.load_account(maker_account_id).unwrap();
+    println!(">>>>>> Included the clone note in the simulated chain");
 
     // Build transaction script
 
-    let tx_script_target = maker_executor
-        .compile_tx_script(tx_script_code.clone(), vec![(maker_pub_key, maker_sk_felt)], vec![])
-        .unwrap();
+    let tx_script_target =
+        chain.compile_tx_script(maker_account_id, tx_script_code.clone()).unwrap();
 
     println!(">>>>>> Initialized Maker's Executor");
 
@@ -333,15 +302,15 @@ fn prove_limit_swap_script() {
         Felt::new(new_amount_desired),
         Felt::new(new_amount_offered),
     ];
-    note_args_map.insert(limit_clone_created_note.id(), maker_args);
+    note_args_map.insert(limit_clone_note.id(), Some(maker_args));
 
-    let tx_args_maker = TransactionArgs::new(Some(tx_script_target), Some(note_args_map));
+    let tx_req_maker =
+        TransactionRequest::new(maker_account_id, note_args_map, vec![], Some(tx_script_target));
 
     // Execute the transaction where Maker updates the limit-swap clone
 
-    let transaction_result = maker_executor
-        .execute_transaction(maker_account_id, block_ref, &limit_clone_ids, Some(tx_args_maker))
-        .unwrap();
+    let transaction_result = chain.execute(tx_req_maker).unwrap();
+    let transaction_result = transaction_result.executed_transaction();
 
     // measure time
     let start = std::time::Instant::now();
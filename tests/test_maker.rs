@@ -0,0 +1,74 @@
+pub mod helpers;
+
+use std::collections::BTreeSet;
+
+use keom_clob::backend::{ClobBackend, SimulatedChain};
+use keom_clob::create_limit_swap_note;
+use keom_clob::maker::cancel_all;
+use keom_clob::market::Pair;
+use keom_clob::registry::OrderRegistry;
+use miden_client::client::accounts::AccountStorageMode;
+use miden_objects::accounts::{AccountId, ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN};
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::NoteId;
+use vm_processor::crypto::RpoRandomCoin;
+use vm_processor::Felt;
+
+use crate::helpers::{dai, eth, taker_id};
+
+fn usdc(amount: u64) -> FungibleAsset {
+    let faucet_id = AccountId::try_from(ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN).unwrap();
+    FungibleAsset::new(faucet_id, amount).unwrap()
+}
+
+/// Adds a limit-swap note of `maker` to the next block and returns its id
+fn add_order(
+    chain: &mut SimulatedChain,
+    seed: u64,
+    maker: AccountId,
+    offered: FungibleAsset,
+    requested: FungibleAsset,
+) -> NoteId {
+    let rng = RpoRandomCoin::new([Felt::new(seed), Felt::new(2), Felt::new(3), Felt::new(4)]);
+    let note = create_limit_swap_note(maker, offered.into(), requested.into(), rng).unwrap();
+    let note_id = note.id();
+    chain.add_note(note);
+    note_id
+}
+
+#[tokio::test]
+async fn cancel_all_reports_the_orders_left_when_a_batch_fails() {
+    let mut chain = SimulatedChain::new();
+    let maker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let ask = add_order(&mut chain, 1, maker.id(), eth(100), dai(300));
+    let bid = add_order(&mut chain, 2, maker.id(), dai(300), eth(100));
+    add_order(&mut chain, 3, maker.id(), eth(100), usdc(300));
+    add_order(&mut chain, 4, taker_id(), eth(100), dai(300));
+    chain.produce_block().unwrap();
+
+    // The body of the limit-swap script is maintained outside this repository,
+    // so its notes cannot be consumed here and the first batch fails
+    let mut registry = OrderRegistry::in_memory();
+    let pair = Pair::new(eth(0).faucet_id(), dai(0).faucet_id());
+    let report = cancel_all(&mut chain, &mut registry, &maker, Some(pair)).await.unwrap();
+
+    assert!(report.reclaimed.is_empty());
+    assert!(report.receipts.is_empty());
+    assert_eq!(report.failed.iter().copied().collect::<BTreeSet<_>>(), BTreeSet::from([ask, bid]));
+    assert!(report.error.is_some());
+    assert_eq!(chain.pending_transactions(), 0);
+}
+
+#[tokio::test]
+async fn cancel_all_without_resting_orders_does_nothing() {
+    let mut chain = SimulatedChain::new();
+    let maker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    add_order(&mut chain, 1, taker_id(), eth(100), dai(300));
+    chain.produce_block().unwrap();
+
+    let mut registry = OrderRegistry::in_memory();
+    let report = cancel_all(&mut chain, &mut registry, &maker, None).await.unwrap();
+
+    assert!(report.reclaimed.is_empty() && report.failed.is_empty());
+    assert!(report.error.is_none());
+}
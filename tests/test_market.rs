@@ -0,0 +1,136 @@
+pub mod helpers;
+
+use keom_clob::config::{ClobConfig, MarketConfig, TokenConfig};
+use keom_clob::errors::ClobError;
+use keom_clob::market::{Market, Markets, Pair, Price, Side};
+use keom_clob::order::{Fill, LimitOrder, Remainder};
+use keom_clob::tokens::TokenRegistry;
+use miden_mock::constants::ACCOUNT_ID_SENDER;
+use miden_objects::accounts::AccountId;
+use miden_objects::notes::NoteId;
+use vm_processor::Digest;
+
+use crate::helpers::{dai, eth};
+
+fn eth_dai_market() -> Market {
+    Market {
+        pair: Pair::new(eth(0).faucet_id(), dai(0).faucet_id()),
+        tick_size: Price::new(1, 1).unwrap(),
+        lot_size: 10,
+    }
+}
+
+#[test]
+fn prices_compare_exactly() {
+    assert_eq!(Price::new(1, 2).unwrap(), Price::new(2, 4).unwrap());
+    assert!(Price::new(1, 3).unwrap() < Price::new(1, 2).unwrap());
+    assert!(Price::new(u64::MAX, u64::MAX - 1).unwrap() > Price::new(1, 1).unwrap());
+    assert!(Price::new(1, 0).is_none());
+
+    let tick = Price::new(1, 100).unwrap();
+    assert!(Price::new(5, 100).unwrap().is_multiple_of(tick));
+    assert!(Price::new(50, 1000).unwrap().is_multiple_of(tick));
+    assert!(!Price::new(5, 1000).unwrap().is_multiple_of(tick));
+}
+
+#[test]
+fn orders_are_validated_against_the_market() {
+    let market = eth_dai_market();
+
+    assert_eq!(market.validate_order(&eth(100), &dai(300)).unwrap(), Side::Ask);
+    assert_eq!(market.validate_order(&dai(300), &eth(100)).unwrap(), Side::Bid);
+
+    // 2.5 DAI per ETH is off the tick grid
+    assert!(market.validate_order(&eth(100), &dai(250)).is_err());
+    // Below one lot and not a whole number of lots
+    assert!(market.validate_order(&eth(5), &dai(15)).is_err());
+    assert!(market.validate_order(&eth(105), &dai(315)).is_err());
+    // Not an ETH/DAI order
+    assert!(market.validate_order(&eth(100), &eth(300)).is_err());
+}
+
+#[test]
+fn fills_must_not_leave_dust_below_one_lot() {
+    let market = eth_dai_market();
+    let note_id = NoteId::new(Digest::default(), Digest::default());
+    let order = LimitOrder {
+        note_id,
+        maker: AccountId::try_from(ACCOUNT_ID_SENDER).unwrap(),
+        offered: eth(100),
+        requested: dai(300),
+        payback_recipient: Digest::default(),
+        partial_recipient: Digest::default(),
+        min_remaining: 0,
+        min_fill: 0,
+    };
+
+    let fill = |out_amount: u64| Fill {
+        note_id,
+        in_amount: out_amount * 3,
+        out_amount,
+        remainder: order.remainder_after(out_amount),
+    };
+    assert!(market.validate_fill(&order, &fill(90)).is_ok());
+    assert!(market.validate_fill(&order, &fill(100)).is_ok());
+    assert!(market.validate_fill(&order, &fill(95)).is_err());
+}
+
+#[test]
+fn dust_returned_to_the_maker_still_breaks_the_lot_size() {
+    let market = eth_dai_market();
+    let order = LimitOrder {
+        note_id: NoteId::new(Digest::default(), Digest::default()),
+        maker: AccountId::try_from(ACCOUNT_ID_SENDER).unwrap(),
+        offered: eth(100),
+        requested: dai(300),
+        payback_recipient: Digest::default(),
+        partial_recipient: Digest::default(),
+        min_remaining: 20,
+        min_fill: 0,
+    };
+
+    // 5 ETH left is below the maker's minimum and would join the payback, but
+    // the market refuses the fill before it gets there
+    let fill = order.fill_for_input(285).unwrap();
+    assert_eq!(fill.remainder, Remainder::Returned(5));
+    assert!(market.validate_fill(&order, &fill).is_err());
+
+    let fill = order.fill_for_input(270).unwrap();
+    assert_eq!(fill.remainder, Remainder::Returned(10));
+    assert!(market.validate_fill(&order, &fill).is_ok());
+}
+
+#[test]
+fn markets_without_a_lot_size_reject_orders() {
+    let market = Market {
+        lot_size: 0,
+        ..eth_dai_market()
+    };
+
+    assert!(matches!(market.validate_order(&eth(100), &dai(300)), Err(ClobError::Config(_))));
+}
+
+#[test]
+fn base_tokens_with_too_many_decimals_are_rejected() {
+    let token = |symbol: &str, faucet_id: AccountId, decimals| TokenConfig {
+        symbol: symbol.to_string(),
+        faucet_id: faucet_id.to_hex(),
+        decimals,
+        max_supply: 1_000_000,
+    };
+    let config = ClobConfig {
+        tokens: vec![
+            token("ETH", eth(0).faucet_id(), 20),
+            token("DAI", dai(0).faucet_id(), 6),
+        ],
+        markets: vec![MarketConfig {
+            base: "ETH".to_string(),
+            quote: "DAI".to_string(),
+            tick_size: "1".to_string(),
+            lot_size: "1".to_string(),
+        }],
+    };
+    let tokens = TokenRegistry::from_config(&config).unwrap();
+
+    assert!(matches!(Markets::from_config(&config, &tokens), Err(ClobError::Config(_))));
+}
@@ -0,0 +1,51 @@
+pub mod helpers;
+
+use keom_clob::book::OrderBook;
+use keom_clob::market::{Markets, Pair};
+use keom_clob::matcher::{find_matches, Match};
+
+use crate::helpers::{dai, eth, note_id, order};
+
+#[test]
+fn crossing_orders_are_matched_for_the_spread() {
+    // Ask at 3 DAI per ETH, bid at 4 DAI per ETH for half the size
+    let ask = order(1, eth(100), dai(300));
+    let bid = order(2, dai(200), eth(50));
+
+    let matched = Match::between(&ask, &bid).unwrap();
+    assert_eq!((matched.ask.in_amount, matched.ask.out_amount), (150, 50));
+    assert_eq!((matched.bid.in_amount, matched.bid.out_amount), (50, 200));
+    assert_eq!(matched.quote_profit, dai(50));
+    assert_eq!(matched.base_profit, eth(0));
+
+    // Whichever leg is consumed first is paid from the matcher's inventory
+    let first_leg_payment = if ask.note_id < bid.note_id {
+        dai(150)
+    } else {
+        eth(50)
+    };
+    assert_eq!(matched.inventory_needed(), first_leg_payment);
+
+    // Orders that do not cross, or only touch, leave nothing to the matcher
+    assert!(Match::between(&ask, &order(3, dai(250), eth(100))).is_none());
+    assert!(Match::between(&ask, &order(3, dai(300), eth(100))).is_none());
+    assert!(Match::between(&ask, &order(3, eth(100), dai(400))).is_none());
+}
+
+#[test]
+fn best_orders_are_matched_first() {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(300)));
+    book.insert(order(2, eth(100), dai(350)));
+    book.insert(order(3, dai(400), eth(100)));
+    book.insert(order(4, dai(340), eth(100)));
+    book.insert(order(5, dai(320), eth(100)));
+
+    let pair = Pair::new(eth(0).faucet_id(), dai(0).faucet_id());
+    let matches = find_matches(&book, &Markets::default(), pair);
+
+    // The 3.5 ask does not cross the 3.4 bid, which stops the matching
+    assert_eq!(matches.len(), 1);
+    assert_eq!((matches[0].ask.note_id, matches[0].bid.note_id), (note_id(1), note_id(3)));
+    assert_eq!(matches[0].quote_profit, dai(100));
+}
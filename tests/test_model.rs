@@ -0,0 +1,361 @@
+pub mod helpers;
+
+use keom_clob::backend::{ClobBackend, SimulatedChain};
+use keom_clob::model::{consume, Consumption, ModelOutcome};
+use keom_clob::order::{Fill, LimitOrder, OrderOptions, OrderUpdate, Remainder};
+use keom_clob::{build_fill_tx_req, create_limit_swap_note_with_options};
+use miden_client::client::accounts::AccountStorageMode;
+use miden_objects::accounts::Account;
+use miden_objects::assets::{Asset, AssetVault, FungibleAsset};
+use miden_objects::crypto::rand::RpoRandomCoin;
+use miden_objects::notes::{Note, NoteId};
+use miden_objects::{Felt, Word};
+
+use crate::helpers::{dai, eth, execute_limit_swap_proc, maker_id, taker_id};
+
+/// Limit-swap note offering ETH for DAI
+fn order_note(seed: u64, offered: u64, requested: u64, options: OrderOptions) -> Note {
+    let rng = RpoRandomCoin::new([Felt::new(seed), Felt::new(2), Felt::new(3), Felt::new(4)]);
+    create_limit_swap_note_with_options(
+        maker_id(),
+        eth(offered).into(),
+        dai(requested).into(),
+        &options,
+        rng,
+    )
+    .unwrap()
+}
+
+/// Deterministic xorshift generator for the randomized cases
+struct Cases(u64);
+
+impl Cases {
+    fn next(&mut self, max: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        1 + self.0 % max
+    }
+}
+
+#[test]
+fn model_takes_follow_fill_math() {
+    let mut cases = Cases(0x2545_f491_4f6c_dd1d);
+    for seed in 0..200 {
+        let options = OrderOptions {
+            min_remaining: cases.next(50) - 1,
+            min_fill: cases.next(50) - 1,
+        };
+        let note = order_note(seed, cases.next(200), cases.next(1_000), options);
+        let order = LimitOrder::from_note(&note).unwrap();
+        let payment = cases.next(order.requested.amount());
+
+        let Some(fill) = order.fill_for_input(payment) else {
+            continue;
+        };
+        let outcome = consume(
+            &order,
+            taker_id(),
+            Consumption::Take {
+                in_amount: fill.in_amount,
+                out_amount: fill.out_amount,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(outcome.added, vec![eth(fill.out_amount)]);
+        assert_eq!(outcome.removed, vec![dai(fill.in_amount)]);
+        let payback = outcome.payback.as_ref().unwrap();
+        assert_eq!(payback.recipient, order.payback_recipient);
+
+        match fill.remainder {
+            Remainder::None => {
+                assert!(outcome.clone.is_none());
+                assert_eq!(payback.assets, vec![dai(fill.in_amount)]);
+            }
+            Remainder::Returned(amount) => {
+                assert!(outcome.clone.is_none());
+                assert_eq!(payback.assets, vec![dai(fill.in_amount), eth(amount)]);
+            }
+            Remainder::Clone(amount) => {
+                let (_, clone) = outcome.clone.as_ref().unwrap();
+                assert_eq!(clone.offered, eth(amount));
+                assert_eq!(clone.requested, dai(order.requested.amount() - fill.in_amount));
+                assert_eq!(clone.options(), order.options());
+            }
+        }
+    }
+}
+
+#[test]
+fn model_applies_the_minimum_fill_and_remaining() {
+    let options = OrderOptions {
+        min_remaining: 5,
+        min_fill: 10,
+    };
+    let order = LimitOrder::from_note(&order_note(1, 100, 300, options)).unwrap();
+    let take = |in_amount, out_amount| Consumption::Take {
+        in_amount,
+        out_amount,
+    };
+
+    assert!(consume(&order, taker_id(), take(27, 9)).is_err());
+    let outcome = consume(&order, taker_id(), take(30, 10)).unwrap();
+    let (_, clone) = outcome.clone.unwrap();
+    assert_eq!((clone.offered, clone.requested), (eth(90), dai(270)));
+
+    // A leftover of exactly the minimum remaining size is still cloned
+    let outcome = consume(&order, taker_id(), take(285, 95)).unwrap();
+    let (_, clone) = outcome.clone.unwrap();
+    assert_eq!((clone.offered, clone.requested), (eth(5), dai(15)));
+    assert_eq!(outcome.payback.unwrap().assets, vec![dai(285)]);
+
+    // Below it, the leftover joins the payback
+    let outcome = consume(&order, taker_id(), take(288, 96)).unwrap();
+    assert!(outcome.clone.is_none());
+    assert_eq!(outcome.payback.unwrap().assets, vec![dai(288), eth(4)]);
+
+    let outcome = consume(&order, taker_id(), take(300, 100)).unwrap();
+    assert!(outcome.clone.is_none());
+    assert_eq!(outcome.payback.unwrap().assets, vec![dai(300)]);
+
+    // What is left may be taken whole even when below the minimum fill
+    let last_lot = LimitOrder::from_note(&order_note(2, 8, 24, options)).unwrap();
+    assert!(consume(&last_lot, taker_id(), take(21, 7)).is_err());
+    assert!(consume(&last_lot, taker_id(), take(24, 8)).is_ok());
+}
+
+#[test]
+fn model_matches_the_script_procedures() {
+    let mut cases = Cases(0x6a09_e667_f3bc_c908);
+    for seed in 0..40 {
+        let options = OrderOptions {
+            min_remaining: cases.next(30) - 1,
+            min_fill: cases.next(30) - 1,
+        };
+        let offered = cases.next(60);
+        let order =
+            LimitOrder::from_note(&order_note(seed, offered, cases.next(600), options)).unwrap();
+        let out_amount = cases.next(offered);
+        let in_amount = (out_amount * order.requested.amount()).div_ceil(offered);
+
+        let outcome = consume(
+            &order,
+            taker_id(),
+            Consumption::Take {
+                in_amount,
+                out_amount,
+            },
+        );
+        let inputs = [out_amount, options.min_fill, offered];
+        let accepted = execute_limit_swap_proc("assert_min_fill", &inputs).is_some();
+        assert_eq!(outcome.is_ok(), accepted, "{out_amount} of {offered}, {options:?}");
+        let Ok(outcome) = outcome else {
+            continue;
+        };
+
+        let remaining = offered - out_amount;
+        let stack = execute_limit_swap_proc("is_dust", &[remaining, options.min_remaining]);
+        let dust = stack.unwrap()[0] == 1;
+        assert_eq!(outcome.payback.unwrap().assets.len() == 2, dust);
+        assert_eq!(outcome.clone.is_some(), remaining > 0 && !dust);
+    }
+}
+
+#[test]
+fn unchanged_update_recreates_the_note() {
+    let note = order_note(1, 100, 300, OrderOptions::default());
+    let order = LimitOrder::from_note(&note).unwrap();
+
+    let outcome = consume(
+        &order,
+        maker_id(),
+        Consumption::Update {
+            offered: 100,
+            requested: 300,
+        },
+    )
+    .unwrap();
+
+    let (clone_note, clone) = outcome.clone.unwrap();
+    assert_eq!(clone_note.recipient, note.recipient().digest());
+    assert_eq!(clone_note.id(), note.id());
+    assert_eq!(clone.note_id, note.id());
+    assert!(outcome.added.is_empty());
+}
+
+#[test]
+fn maker_update_and_cancel_reclaim_the_difference() {
+    let order = LimitOrder::from_note(&order_note(1, 100, 300, OrderOptions::default())).unwrap();
+
+    let args: Word = [Felt::new(0), Felt::new(0), Felt::new(21), Felt::new(10)];
+    let update = Consumption::from_args(&order, maker_id(), args);
+    assert_eq!(
+        update,
+        Consumption::Update {
+            offered: 10,
+            requested: 21
+        }
+    );
+    let outcome = consume(&order, maker_id(), update).unwrap();
+    assert_eq!(outcome.added, vec![eth(90)]);
+    assert_eq!(outcome.clone.unwrap().1.requested, dai(21));
+    assert!(outcome.payback.is_none());
+
+    // Updates keep the order's options and may raise the price
+    let options = OrderOptions {
+        min_remaining: 5,
+        min_fill: 10,
+    };
+    let strict = LimitOrder::from_note(&order_note(2, 100, 300, options)).unwrap();
+    let update = Consumption::Update {
+        offered: 60,
+        requested: 240,
+    };
+    let (clone_note, clone) = consume(&strict, maker_id(), update).unwrap().clone.unwrap();
+    assert_eq!(clone.options(), options);
+    assert_eq!((clone.offered, clone.requested), (eth(60), dai(240)));
+    assert_eq!(clone_note.assets, vec![eth(60)]);
+
+    // Updates offering more than the note holds, or only one amount, abort
+    let more = Consumption::Update {
+        offered: 101,
+        requested: 300,
+    };
+    assert!(consume(&order, maker_id(), more).is_err());
+    let one_sided = Consumption::Update {
+        offered: 0,
+        requested: 300,
+    };
+    assert!(consume(&order, maker_id(), one_sided).is_err());
+
+    let cancel = Consumption::from_args(&order, maker_id(), LimitOrder::cancel_args());
+    let outcome = consume(&order, maker_id(), cancel).unwrap();
+    assert_eq!(outcome.added, vec![eth(100)]);
+    assert!(outcome.clone.is_none());
+    assert!(outcome.output_note_ids().is_empty());
+}
+
+#[test]
+fn updates_and_cancels_share_one_encoding() {
+    let order = LimitOrder::from_note(&order_note(1, 100, 300, OrderOptions::default())).unwrap();
+    let update = OrderUpdate {
+        offered: 10,
+        requested: 21,
+    };
+    assert_eq!(OrderUpdate::from_note_args(update.note_args()), update);
+    assert_eq!(LimitOrder::cancel_args(), OrderUpdate::CANCEL.note_args());
+
+    // The clone predicted for the maker matches the one the model creates
+    let consumption = Consumption::from_args(&order, maker_id(), update.note_args());
+    let (_, clone) = consume(&order, maker_id(), consumption).unwrap().clone.unwrap();
+    assert_eq!(order.updated(update).unwrap(), Some(clone));
+
+    let consumption = Consumption::from_args(&order, maker_id(), LimitOrder::cancel_args());
+    assert!(consume(&order, maker_id(), consumption).unwrap().clone.is_none());
+    assert_eq!(order.updated(OrderUpdate::CANCEL).unwrap(), None);
+
+    // The same args from a taker ask for nothing and are rejected
+    let consumption = Consumption::from_args(&order, taker_id(), LimitOrder::cancel_args());
+    assert!(consume(&order, taker_id(), consumption).is_err());
+
+    // Updates offering more than the note holds, or only one amount, are refused
+    let more = OrderUpdate {
+        offered: 101,
+        requested: 300,
+    };
+    assert!(order.updated(more).is_err());
+    let one_sided = OrderUpdate {
+        offered: 10,
+        requested: 0,
+    };
+    assert!(order.updated(one_sided).is_err());
+}
+
+#[test]
+fn model_rejects_what_the_script_aborts_on() {
+    let options = OrderOptions {
+        min_remaining: 0,
+        min_fill: 10,
+    };
+    let order = LimitOrder::from_note(&order_note(1, 100, 300, options)).unwrap();
+    let take = |in_amount, out_amount| Consumption::Take {
+        in_amount,
+        out_amount,
+    };
+
+    // Better than the order's price
+    assert!(consume(&order, taker_id(), take(30, 11)).is_err());
+    // Below the minimum fill
+    assert!(consume(&order, taker_id(), take(27, 9)).is_err());
+    // More than requested
+    assert!(consume(&order, taker_id(), take(301, 100)).is_err());
+    // Only the maker may update, and it may not take
+    let update = Consumption::Update {
+        offered: 50,
+        requested: 150,
+    };
+    assert!(consume(&order, taker_id(), update).is_err());
+    assert!(consume(&order, maker_id(), take(30, 10)).is_err());
+    assert!(consume(&order, taker_id(), take(30, 10)).is_ok());
+}
+
+/// Runs one take through the transaction executor of a simulated chain and
+/// returns the assets added to and removed from the taker's vault, and the ids
+/// of the created notes
+fn execute_take(note: &Note, fill: &Fill) -> (Vec<Asset>, Vec<Asset>, Vec<NoteId>) {
+    let mut chain = SimulatedChain::new();
+    let taker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let vault = AssetVault::new(&[dai(fill.in_amount.max(1)).into()]).unwrap();
+    let storage = taker.storage().clone();
+    chain.add_account(Account::new(
+        taker.id(),
+        vault,
+        storage,
+        taker.code().clone(),
+        taker.nonce(),
+    ));
+    chain.add_note(note.clone());
+    chain.produce_block().unwrap();
+
+    let tx_req = build_fill_tx_req(&chain, taker.id(), &[*fill]).unwrap();
+    let result = chain.execute(tx_req).unwrap();
+    let executed = result.executed_transaction();
+    let vault = executed.account_delta().vault();
+    let output_note_ids = executed.output_notes().iter().map(|note| note.id()).collect();
+
+    (vault.added_assets.clone(), vault.removed_assets.clone(), output_note_ids)
+}
+
+fn expected_delta(outcome: &ModelOutcome) -> (Vec<Asset>, Vec<Asset>) {
+    let assets =
+        |assets: &[FungibleAsset]| assets.iter().map(|asset| Asset::from(*asset)).collect();
+    (assets(&outcome.added), assets(&outcome.removed))
+}
+
+#[test]
+#[ignore = "the body of masm/limit_swap.masm is omitted from this repository"]
+fn model_matches_transaction_executor() {
+    let mut cases = Cases(0x9e37_79b9_7f4a_7c15);
+    for seed in 0..20 {
+        let note = order_note(seed, cases.next(1_000), cases.next(1_000), OrderOptions::default());
+        let order = LimitOrder::from_note(&note).unwrap();
+        let Some(fill) = order.fill_for_input(cases.next(order.requested.amount())) else {
+            continue;
+        };
+
+        let outcome = consume(
+            &order,
+            taker_id(),
+            Consumption::Take {
+                in_amount: fill.in_amount,
+                out_amount: fill.out_amount,
+            },
+        )
+        .unwrap();
+        let (added, removed, output_note_ids) = execute_take(&note, &fill);
+
+        assert_eq!((added, removed), expected_delta(&outcome));
+        assert_eq!(output_note_ids, outcome.output_note_ids());
+    }
+}
@@ -0,0 +1,108 @@
+pub mod helpers;
+
+use keom_clob::order::{LimitOrder, Remainder};
+use miden_objects::assets::FungibleAsset;
+use miden_objects::notes::{NoteAssets, NoteId};
+use vm_processor::{Digest, Felt};
+
+use crate::helpers::{dai, eth, execute_limit_swap_proc, maker_id};
+
+/// Order offering 100 ETH for 300 DAI
+fn eth_dai_order(min_remaining: u64, min_fill: u64) -> LimitOrder {
+    LimitOrder {
+        note_id: NoteId::new(Digest::default(), Digest::default()),
+        maker: maker_id(),
+        offered: eth(100),
+        requested: dai(300),
+        payback_recipient: Digest::new([Felt::new(1), Felt::new(2), Felt::new(3), Felt::new(4)]),
+        partial_recipient: Digest::new([Felt::new(5), Felt::new(6), Felt::new(7), Felt::new(8)]),
+        min_remaining,
+        min_fill,
+    }
+}
+
+#[test]
+fn fills_predict_clone_or_returned_dust() {
+    let order = eth_dai_order(20, 0);
+
+    let fill = order.fill_for_input(150).unwrap();
+    assert_eq!((fill.in_amount, fill.out_amount), (150, 50));
+    assert_eq!(fill.remainder, Remainder::Clone(50));
+
+    // 10 ETH left is below the maker's minimum of 20
+    let fill = order.fill_for_input(270).unwrap();
+    assert_eq!((fill.in_amount, fill.out_amount), (270, 90));
+    assert_eq!(fill.remainder, Remainder::Returned(10));
+
+    // Overpaying is capped to the requested amount
+    let fill = order.fill_for_input(1000).unwrap();
+    assert_eq!((fill.in_amount, fill.out_amount), (300, 100));
+    assert_eq!(fill.remainder, Remainder::None);
+
+    // Without a minimum, any leftover is cloned
+    let fill = eth_dai_order(0, 0).fill_for_input(270).unwrap();
+    assert_eq!(fill.remainder, Remainder::Clone(10));
+}
+
+#[test]
+fn remainders_match_the_is_dust_procedure() {
+    for min_remaining in 0..8 {
+        let order = eth_dai_order(min_remaining, 0);
+        for out_amount in 90..=100 {
+            let remaining = 100 - out_amount;
+            let stack = execute_limit_swap_proc("is_dust", &[remaining, min_remaining]).unwrap();
+            let returned = matches!(order.remainder_after(out_amount), Remainder::Returned(_));
+            assert_eq!(stack[0] == 1, returned, "{remaining} left, minimum {min_remaining}");
+        }
+    }
+}
+
+#[test]
+fn fills_below_the_minimum_fill_are_rejected() {
+    let order = eth_dai_order(0, 30);
+
+    // 20 ETH is below the maker's minimum fill of 30
+    assert!(order.fill_for_input(60).is_none());
+    assert_eq!(order.min_input(), 90);
+    assert_eq!(order.fill_for_input(90).unwrap().out_amount, 30);
+
+    // Payments too small to receive anything are never valid
+    assert!(eth_dai_order(0, 0).fill_for_input(2).is_none());
+    assert_eq!(eth_dai_order(0, 0).min_input(), 3);
+
+    // Taking everything that is left is always allowed
+    let last_lot = LimitOrder {
+        offered: FungibleAsset::new(order.offered.faucet_id(), 10).unwrap(),
+        requested: FungibleAsset::new(order.requested.faucet_id(), 30).unwrap(),
+        ..order
+    };
+    assert_eq!(last_lot.fill_for_input(30).unwrap().out_amount, 10);
+    assert_eq!(last_lot.min_input(), 30);
+}
+
+#[test]
+fn fills_match_the_assert_min_fill_procedure() {
+    for min_fill in [0, 10, 30, 100] {
+        let order = eth_dai_order(0, min_fill);
+        for out_amount in [1, 9, 10, 11, 29, 30, 31, 99, 100] {
+            let inputs = [out_amount, min_fill, order.offered.amount()];
+            let accepted = execute_limit_swap_proc("assert_min_fill", &inputs).is_some();
+            assert_eq!(
+                accepted,
+                order.allows_fill(out_amount),
+                "{out_amount} out, minimum {min_fill}"
+            );
+        }
+    }
+}
+
+#[test]
+fn order_decodes_from_its_inputs() {
+    let order = eth_dai_order(20, 30);
+    let assets = NoteAssets::new(vec![order.offered.into()]).unwrap();
+
+    let decoded = LimitOrder::from_parts(order.note_id, &assets, &order.to_inputs()).unwrap();
+    assert_eq!(decoded, order);
+
+    assert!(LimitOrder::from_parts(order.note_id, &assets, &order.to_inputs()[..15]).is_err());
+}
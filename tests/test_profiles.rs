@@ -0,0 +1,155 @@
+pub mod helpers;
+
+use keom_clob::backend::{ClobBackend, SimulatedChain};
+use keom_clob::errors::ClobError;
+use keom_clob::profiles::AccountProfiles;
+use keom_clob::tokens::faucet_metadata;
+use miden_client::client::accounts::AccountStorageMode;
+use miden_client::client::transactions::transaction_request::TransactionRequest;
+use miden_client::client::transactions::TransactionResult;
+use miden_client::store::{InputNoteRecord, NoteFilter};
+use miden_objects::accounts::{Account, AccountId};
+use miden_objects::assembly::ProgramAst;
+use miden_objects::assets::TokenSymbol;
+use miden_objects::notes::NoteId;
+use miden_objects::transaction::{ProvenTransaction, TransactionId, TransactionScript};
+
+#[test]
+fn accounts_are_reused_across_reloads() {
+    let path = std::env::temp_dir().join(format!("keom-profiles-{}.json", std::process::id()));
+    let mut chain = SimulatedChain::new();
+
+    let mut profiles = AccountProfiles::load(&path).unwrap();
+    let maker = profiles.get_or_create_account(&mut chain, "maker").unwrap();
+    let faucet = profiles.get_or_create_faucet(&mut chain, "ETH", 8, 1_000_000).unwrap();
+    assert_eq!(faucet_metadata(&faucet), (8, 1_000_000));
+
+    let mut profiles = AccountProfiles::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(profiles.account("maker"), Some(maker.id()));
+    assert_eq!(profiles.faucet("ETH"), Some(faucet.id()));
+
+    let again = profiles.get_or_create_account(&mut chain, "maker").unwrap();
+    assert_eq!(again.id(), maker.id());
+    let again = profiles.get_or_create_faucet(&mut chain, "ETH", 8, 1_000_000).unwrap();
+    assert_eq!(again.id(), faucet.id());
+}
+
+#[test]
+fn faucets_with_other_parameters_are_rejected() {
+    let mut chain = SimulatedChain::new();
+    let mut profiles = AccountProfiles::in_memory();
+    let faucet = profiles.get_or_create_faucet(&mut chain, "ETH", 8, 1_000_000).unwrap();
+
+    let err = profiles.get_or_create_faucet(&mut chain, "ETH", 6, 1_000_000).unwrap_err();
+    assert!(matches!(err, ClobError::Profile(_)));
+    let err = profiles.get_or_create_faucet(&mut chain, "ETH", 8, 2_000_000).unwrap_err();
+    assert!(matches!(err, ClobError::Profile(_)));
+    assert_eq!(profiles.faucet("ETH"), Some(faucet.id()));
+}
+
+#[test]
+fn accounts_are_created_with_the_chosen_storage_mode() {
+    let mut chain = SimulatedChain::new();
+    let mut profiles = AccountProfiles::in_memory();
+    let local = profiles.get_or_create_account(&mut chain, "local").unwrap();
+
+    let mut profiles = profiles.with_storage_mode(AccountStorageMode::OnChain);
+    let on_chain = profiles.get_or_create_account(&mut chain, "on-chain").unwrap();
+    let faucet = profiles.get_or_create_faucet(&mut chain, "DAI", 6, 1_000).unwrap();
+
+    assert!(!local.id().is_on_chain());
+    assert!(on_chain.id().is_on_chain());
+    assert!(faucet.id().is_on_chain());
+}
+
+#[test]
+fn profiles_drop_accounts_the_backend_lost() {
+    let mut profiles = AccountProfiles::in_memory();
+    let lost = profiles.get_or_create_account(&mut SimulatedChain::new(), "maker").unwrap();
+
+    // A fresh chain knows nothing of the registered account, so a new one is created
+    let mut chain = SimulatedChain::new();
+    let maker = profiles.get_or_create_account(&mut chain, "maker").unwrap();
+    assert_eq!(profiles.account("maker"), Some(maker.id()));
+    assert_ne!(maker.id(), lost.id());
+    assert!(chain.get_account(maker.id()).is_ok());
+}
+
+/// A chain whose store cannot be read, e.g. while another process holds the
+/// sqlite database
+struct LockedStore(SimulatedChain);
+
+#[tonic::async_trait]
+impl ClobBackend for LockedStore {
+    async fn sync(&mut self) -> Result<u32, ClobError> {
+        self.0.sync().await
+    }
+
+    fn get_notes(&self, filter: NoteFilter) -> Result<Vec<InputNoteRecord>, ClobError> {
+        self.0.get_notes(filter)
+    }
+
+    fn get_account(&self, _account_id: AccountId) -> Result<Account, ClobError> {
+        Err(ClobError::Backend("database is locked".to_string()))
+    }
+
+    fn new_wallet(&mut self, storage_mode: AccountStorageMode) -> Result<Account, ClobError> {
+        self.0.new_wallet(storage_mode)
+    }
+
+    fn new_faucet(
+        &mut self,
+        symbol: TokenSymbol,
+        decimals: u8,
+        max_supply: u64,
+        storage_mode: AccountStorageMode,
+    ) -> Result<Account, ClobError> {
+        self.0.new_faucet(symbol, decimals, max_supply, storage_mode)
+    }
+
+    fn compile_tx_script(
+        &self,
+        account_id: AccountId,
+        program: ProgramAst,
+    ) -> Result<TransactionScript, ClobError> {
+        self.0.compile_tx_script(account_id, program)
+    }
+
+    fn execute(&mut self, tx_request: TransactionRequest) -> Result<TransactionResult, ClobError> {
+        self.0.execute(tx_request)
+    }
+
+    async fn submit(
+        &mut self,
+        result: TransactionResult,
+        proven: ProvenTransaction,
+    ) -> Result<(), ClobError> {
+        self.0.submit(result, proven).await
+    }
+
+    async fn poll_commit(
+        &mut self,
+        transaction_id: TransactionId,
+        input_note_ids: &[NoteId],
+    ) -> Result<Option<u32>, ClobError> {
+        self.0.poll_commit(transaction_id, input_note_ids).await
+    }
+}
+
+#[test]
+fn profiles_keep_accounts_the_backend_fails_to_load() {
+    let mut chain = SimulatedChain::new();
+    let mut profiles = AccountProfiles::in_memory();
+    let maker = profiles.get_or_create_account(&mut chain, "maker").unwrap();
+    let faucet = profiles.get_or_create_faucet(&mut chain, "ETH", 8, 1_000_000).unwrap();
+
+    let mut locked = LockedStore(chain);
+    let err = profiles.get_or_create_account(&mut locked, "maker").unwrap_err();
+    assert!(matches!(err, ClobError::Backend(_)));
+    let err = profiles.get_or_create_faucet(&mut locked, "ETH", 8, 1_000_000).unwrap_err();
+    assert!(matches!(err, ClobError::Backend(_)));
+
+    assert_eq!(profiles.account("maker"), Some(maker.id()));
+    assert_eq!(profiles.faucet("ETH"), Some(faucet.id()));
+}
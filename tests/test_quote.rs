@@ -0,0 +1,80 @@
+pub mod helpers;
+
+use keom_clob::book::OrderBook;
+use keom_clob::market::{Market, Markets, Pair, Price};
+use keom_clob::quote::{quote_buy, quote_sell};
+use vm_processor::Felt;
+
+use crate::helpers::{dai, eth, note_id, order};
+
+/// Asks at 3 and 4 DAI per ETH
+fn book() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(400)));
+    book.insert(order(2, eth(100), dai(300)));
+    book
+}
+
+#[test]
+fn buying_walks_the_book_cheapest_first() {
+    let book = book();
+
+    let quote = quote_buy(&book, &Markets::default(), eth(150), dai(0).faucet_id()).unwrap();
+    assert_eq!(quote.note_ids(), vec![note_id(2), note_id(1)]);
+    assert_eq!((quote.paid, quote.received), (dai(500), eth(150)));
+    assert_eq!(quote.average_price(), Price::new(10, 3).unwrap());
+    assert_eq!(quote.worst_price(), Price::new(4, 1).unwrap());
+
+    // The fills carry the note args a taker consumes the orders with
+    let last = quote.fills[1];
+    assert_eq!((last.in_amount, last.out_amount), (200, 50));
+    assert_eq!(last.note_args()[2..], [Felt::new(200), Felt::new(50)]);
+
+    // A thin book fills as much as it can
+    let quote = quote_buy(&book, &Markets::default(), eth(500), dai(0).faucet_id()).unwrap();
+    assert_eq!((quote.paid, quote.received), (dai(700), eth(200)));
+
+    assert!(quote_buy(&book, &Markets::default(), dai(100), eth(0).faucet_id()).is_none());
+}
+
+#[test]
+fn selling_spends_as_much_as_the_book_absorbs() {
+    let book = book();
+
+    let quote = quote_sell(&book, &Markets::default(), dai(500), eth(0).faucet_id()).unwrap();
+    assert_eq!((quote.paid, quote.received), (dai(500), eth(150)));
+
+    let quote = quote_sell(&book, &Markets::default(), dai(1000), eth(0).faucet_id()).unwrap();
+    assert_eq!((quote.paid, quote.received), (dai(700), eth(200)));
+}
+
+#[test]
+fn selling_pays_only_what_the_rounded_fill_is_worth() {
+    let book = book();
+
+    // 10 DAI left for the ask at 4 buy 2.5 ETH, rounded down to 2 for 8 DAI
+    let quote = quote_sell(&book, &Markets::default(), dai(310), eth(0).faucet_id()).unwrap();
+    assert_eq!((quote.paid, quote.received), (dai(308), eth(102)));
+    let last = quote.fills[1];
+    assert_eq!((last.in_amount, last.out_amount), (8, 2));
+}
+
+#[test]
+fn fills_leaving_less_than_a_lot_are_skipped() {
+    let book = book();
+    let mut markets = Markets::default();
+    markets.insert(Market {
+        pair: Pair::new(eth(0).faucet_id(), dai(0).faucet_id()),
+        tick_size: Price::new(1, 1).unwrap(),
+        lot_size: 60,
+    });
+
+    // Taking 50 of the ask at 4 would leave it with 50, less than a lot
+    let quote = quote_buy(&book, &markets, eth(150), dai(0).faucet_id()).unwrap();
+    assert_eq!(quote.note_ids(), vec![note_id(2)]);
+    assert_eq!(quote.received, eth(100));
+
+    let quote = quote_sell(&book, &markets, dai(500), eth(0).faucet_id()).unwrap();
+    assert_eq!(quote.note_ids(), vec![note_id(2)]);
+    assert_eq!(quote.paid, dai(300));
+}
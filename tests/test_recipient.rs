@@ -0,0 +1,147 @@
+pub mod helpers;
+
+use std::sync::OnceLock;
+
+use keom_clob::order::{LimitOrder, OrderOptions, LIMIT_SWAP_NUM_INPUTS};
+use keom_clob::{
+    build_partial_recipient, create_limit_swap_note_with_options, limit_swap_note_script,
+};
+use miden_lib::transaction::TransactionKernel;
+use miden_mock::constants::ACCOUNT_ID_SENDER;
+use miden_objects::accounts::AccountId;
+use miden_objects::assembly::ProgramAst;
+use miden_objects::assets::{Asset, FungibleAsset};
+use miden_objects::crypto::rand::RpoRandomCoin;
+use miden_objects::notes::{NoteAssets, NoteId, NoteInputs, NoteRecipient, NoteScript};
+use miden_objects::{Hasher, Word};
+use proptest::prelude::*;
+use vm_processor::{Digest, Felt};
+
+use crate::helpers::{DAI_FAUCET_ID, ETH_FAUCET_ID};
+
+/// Largest value below the field modulus, so that `Felt::new` keeps it as is
+const MAX_FELT: u64 = 0xffff_ffff_0000_0000;
+
+/// The limit-swap script and a few unrelated ones, compiled once
+fn scripts() -> &'static [NoteScript] {
+    static SCRIPTS: OnceLock<Vec<NoteScript>> = OnceLock::new();
+    SCRIPTS.get_or_init(|| {
+        let assembler = TransactionKernel::assembler();
+        let mut scripts = vec![limit_swap_note_script()];
+        for n in 1..4 {
+            let program = ProgramAst::parse(&format!("begin push.{n} drop end")).unwrap();
+            scripts.push(NoteScript::new(program, &assembler).unwrap().0);
+        }
+        scripts
+    })
+}
+
+fn felt() -> impl Strategy<Value = Felt> {
+    (0..=MAX_FELT).prop_map(Felt::new)
+}
+
+fn word() -> impl Strategy<Value = Word> {
+    [felt(), felt(), felt(), felt()]
+}
+
+fn digest() -> impl Strategy<Value = Digest> {
+    word().prop_map(Digest::new)
+}
+
+fn amount() -> impl Strategy<Value = u64> {
+    1..=FungibleAsset::MAX_AMOUNT
+}
+
+fn options() -> impl Strategy<Value = OrderOptions> {
+    (0..=MAX_FELT, 0..=MAX_FELT).prop_map(|(min_remaining, min_fill)| OrderOptions {
+        min_remaining,
+        min_fill,
+    })
+}
+
+fn limit_order() -> impl Strategy<Value = LimitOrder> {
+    (any::<bool>(), amount(), amount(), digest(), digest(), options()).prop_map(
+        |(eth_offered, offered, requested, payback_recipient, partial_recipient, options)| {
+            let eth = AccountId::try_from(ETH_FAUCET_ID).unwrap();
+            let dai = AccountId::try_from(DAI_FAUCET_ID).unwrap();
+            let (offered_faucet, requested_faucet) =
+                if eth_offered { (eth, dai) } else { (dai, eth) };
+            LimitOrder {
+                note_id: NoteId::new(payback_recipient, partial_recipient),
+                maker: AccountId::try_from(ACCOUNT_ID_SENDER).unwrap(),
+                offered: FungibleAsset::new(offered_faucet, offered).unwrap(),
+                requested: FungibleAsset::new(requested_faucet, requested).unwrap(),
+                payback_recipient,
+                partial_recipient,
+                min_remaining: options.min_remaining,
+                min_fill: options.min_fill,
+            }
+        },
+    )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn partial_recipient_merged_with_inputs_is_the_recipient(
+        serial_num in word(),
+        script in 0..4usize,
+        inputs in prop::collection::vec(felt(), 0..=LIMIT_SWAP_NUM_INPUTS),
+    ) {
+        let script = scripts()[script].clone();
+        let inputs = NoteInputs::new(inputs).unwrap();
+
+        let partial_recipient = build_partial_recipient(script.clone(), serial_num).unwrap();
+        let recipient = NoteRecipient::new(serial_num, script, inputs.clone());
+
+        let merged = Hasher::merge(&[partial_recipient, inputs.commitment()]);
+        prop_assert_eq!(merged, recipient.digest());
+    }
+
+    #[test]
+    fn limit_order_inputs_round_trip(order in limit_order()) {
+        let assets = NoteAssets::new(vec![Asset::from(order.offered)]).unwrap();
+        let inputs = order.to_inputs();
+
+        let decoded = LimitOrder::from_parts(order.note_id, &assets, &inputs).unwrap();
+        prop_assert_eq!(&decoded, &order);
+        prop_assert_eq!(decoded.to_inputs(), inputs);
+    }
+
+    #[test]
+    fn limit_swap_notes_decode_to_their_order(
+        seed in word(),
+        offered in amount(),
+        requested in amount(),
+        options in options(),
+    ) {
+        let maker = AccountId::try_from(ACCOUNT_ID_SENDER).unwrap();
+        let eth = AccountId::try_from(ETH_FAUCET_ID).unwrap();
+        let dai = AccountId::try_from(DAI_FAUCET_ID).unwrap();
+        let offered = FungibleAsset::new(eth, offered).unwrap();
+        let requested = FungibleAsset::new(dai, requested).unwrap();
+        let note = create_limit_swap_note_with_options(
+            maker,
+            offered.into(),
+            requested.into(),
+            &options,
+            RpoRandomCoin::new(seed),
+        )
+        .unwrap();
+
+        let order = LimitOrder::from_note(&note).unwrap();
+        prop_assert_eq!(order.note_id, note.id());
+        prop_assert_eq!(order.maker, maker);
+        prop_assert_eq!(order.offered, offered);
+        prop_assert_eq!(order.requested, requested);
+        prop_assert_eq!(order.options(), options);
+
+        // The clone of an untouched order is the order's own note
+        let inputs = NoteInputs::new(order.to_inputs().to_vec()).unwrap();
+        prop_assert_eq!(
+            Hasher::merge(&[order.partial_recipient, inputs.commitment()]),
+            note.recipient().digest()
+        );
+    }
+}
@@ -0,0 +1,36 @@
+pub mod helpers;
+
+use keom_clob::order::LimitOrder;
+use keom_clob::registry::{OrderRegistry, OrderStatus};
+
+use crate::helpers::{dai, eth, maker_id};
+
+fn order(seed: u64, offered: u64, requested: u64) -> LimitOrder {
+    helpers::order(seed, eth(offered), dai(requested))
+}
+
+#[test]
+fn replacements_are_linked_across_reloads() {
+    let path = std::env::temp_dir().join(format!("keom-orders-{}.json", std::process::id()));
+    let maker = maker_id();
+    let (old, new) = (order(1, 100, 300), order(2, 120, 350));
+
+    let mut registry = OrderRegistry::load(&path).unwrap();
+    registry.record_placed(&old).unwrap();
+    registry.record_replaced(&old, &new).unwrap();
+
+    let registry = OrderRegistry::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let old_record = registry.get(&old.note_id).unwrap();
+    assert_eq!(old_record.status, OrderStatus::Replaced);
+    assert_eq!(old_record.replaced_by, Some(new.note_id));
+
+    let new_record = registry.get(&new.note_id).unwrap();
+    assert_eq!(new_record.status, OrderStatus::Open);
+    assert_eq!(new_record.replaces, Some(old.note_id));
+    assert_eq!(new_record.offered, new.offered);
+
+    let open = registry.open_orders(maker).map(|record| record.note_id).collect::<Vec<_>>();
+    assert_eq!(open, vec![new.note_id]);
+}
@@ -0,0 +1,140 @@
+use keom_clob::backend::{ClobBackend, SimulatedChain};
+use keom_clob::book::OrderBook;
+use keom_clob::errors::ClobError;
+use keom_clob::market::{Markets, Price};
+use keom_clob::order::LimitOrder;
+use keom_clob::router::{execute_route, find_route};
+use miden_client::client::accounts::AccountStorageMode;
+use miden_mock::constants::ACCOUNT_ID_SENDER;
+use miden_objects::accounts::{AccountId, ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN};
+use miden_objects::assets::{AssetVault, FungibleAsset};
+use miden_objects::notes::NoteId;
+use vm_processor::{Digest, Felt};
+
+// Dummy faucet IDs for ETH and DAI
+const ETH_FAUCET_ID: u64 = 10000118204333965312;
+const DAI_FAUCET_ID: u64 = 10000344073709551615;
+
+fn eth(amount: u64) -> FungibleAsset {
+    FungibleAsset::new(AccountId::try_from(ETH_FAUCET_ID).unwrap(), amount).unwrap()
+}
+
+fn dai(amount: u64) -> FungibleAsset {
+    FungibleAsset::new(AccountId::try_from(DAI_FAUCET_ID).unwrap(), amount).unwrap()
+}
+
+fn usdc(amount: u64) -> FungibleAsset {
+    let faucet_id = AccountId::try_from(ACCOUNT_ID_FUNGIBLE_FAUCET_ON_CHAIN).unwrap();
+    FungibleAsset::new(faucet_id, amount).unwrap()
+}
+
+fn note_id(seed: u64) -> NoteId {
+    NoteId::new(
+        Digest::new([Felt::new(seed), Felt::new(0), Felt::new(0), Felt::new(0)]),
+        Digest::default(),
+    )
+}
+
+fn order(seed: u64, offered: FungibleAsset, requested: FungibleAsset) -> LimitOrder {
+    LimitOrder {
+        note_id: note_id(seed),
+        maker: AccountId::try_from(ACCOUNT_ID_SENDER).unwrap(),
+        offered,
+        requested,
+        payback_recipient: Digest::default(),
+        partial_recipient: Digest::default(),
+        min_remaining: 0,
+        min_fill: 0,
+    }
+}
+
+#[test]
+fn swaps_are_routed_through_intermediate_assets() {
+    let mut book = OrderBook::new();
+    // 3 DAI per ETH, then 1 DAI per USDC up to 200 DAI
+    book.insert(order(1, eth(100), dai(300)));
+    book.insert(order(2, dai(200), usdc(200)));
+    book.insert(order(3, dai(100), usdc(125)));
+
+    let route = find_route(&book, &Markets::default(), usdc(300), eth(0).faucet_id(), 3).unwrap();
+    assert_eq!(route.hops.len(), 2);
+    assert_eq!(route.paid(), usdc(300));
+    assert_eq!(route.received(), eth(93));
+
+    assert_eq!(route.hops[0].received, dai(280));
+    assert_eq!(route.hops[0].average_price(), Price::new(300, 280).unwrap());
+    // 280 DAI buy 93.33 ETH, so only the 279 DAI that 93 ETH cost are paid
+    assert_eq!(route.hops[1].paid, dai(279));
+    assert_eq!(route.hops[1].average_price(), Price::new(3, 1).unwrap());
+    assert_eq!(route.fills().len(), 3);
+
+    // DAI must be held upfront unless the DAI/ETH note is consumed last
+    let needed = route.inventory_needed();
+    let consumed_last = note_id(1) > note_id(2) && note_id(1) > note_id(3);
+    assert_eq!(needed.is_empty(), consumed_last);
+    assert!(needed.iter().all(|asset| asset.faucet_id() == dai(0).faucet_id()));
+
+    assert!(find_route(&book, &Markets::default(), usdc(300), eth(0).faucet_id(), 1).is_none());
+}
+
+#[test]
+fn shorter_routes_win_ties_and_better_routes_win_otherwise() {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(300)));
+    book.insert(order(2, dai(300), usdc(300)));
+
+    // A direct order at the same overall price is preferred
+    book.insert(order(3, eth(100), usdc(300)));
+    let route = find_route(&book, &Markets::default(), usdc(300), eth(0).faucet_id(), 3).unwrap();
+    assert_eq!(route.hops.len(), 1);
+
+    // A worse direct order loses to the two-leg route
+    book.remove(&note_id(3));
+    book.insert(order(4, eth(50), usdc(300)));
+    let route = find_route(&book, &Markets::default(), usdc(300), eth(0).faucet_id(), 3).unwrap();
+    assert_eq!(route.hops.len(), 2);
+    assert_eq!(route.received(), eth(100));
+}
+
+#[test]
+fn two_hop_routes_need_the_paid_asset_and_the_inventory() {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(300)));
+    book.insert(order(2, dai(300), usdc(300)));
+    let route = find_route(&book, &Markets::default(), usdc(300), eth(0).faucet_id(), 3).unwrap();
+    assert_eq!(route.hops.len(), 2);
+
+    let needed = route.inventory_needed();
+    let mut held = vec![usdc(300).into()];
+    held.extend(needed.iter().map(|&asset| asset.into()));
+    assert!(route.check_funds(&AssetVault::new(&held).unwrap()).is_ok());
+
+    // Only the paid USDC is held, so DAI is missing if the DAI/ETH note is
+    // consumed before the USDC/DAI one yields it
+    let result = route.check_funds(&AssetVault::new(&[usdc(300).into()]).unwrap());
+    match result {
+        Ok(()) => assert!(needed.is_empty()),
+        Err(ClobError::InsufficientFunds(missing)) => assert_eq!(missing, needed),
+        Err(err) => panic!("unexpected error: {err}"),
+    }
+
+    let result = route.check_funds(&AssetVault::new(&[usdc(100).into()]).unwrap());
+    let Err(ClobError::InsufficientFunds(missing)) = result else {
+        panic!("a short vault must be rejected");
+    };
+    assert!(missing.contains(&usdc(200)));
+}
+
+#[tokio::test]
+async fn routes_are_not_executed_without_the_funds() {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(300)));
+    book.insert(order(2, dai(300), usdc(300)));
+    let route = find_route(&book, &Markets::default(), usdc(300), eth(0).faucet_id(), 3).unwrap();
+
+    let mut chain = SimulatedChain::new();
+    let taker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+    let result = execute_route(&mut chain, &taker, &route).await;
+    assert!(matches!(result, Err(ClobError::InsufficientFunds(_))));
+    assert_eq!(chain.pending_transactions(), 0);
+}
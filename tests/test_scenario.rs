@@ -0,0 +1,128 @@
+use std::fs;
+
+use keom_clob::scenario::{run_scenario, ClobDriver, Scenario, Step};
+
+/// The body of the limit-swap script is maintained outside this repository,
+/// so its notes cannot be consumed here: checked-in scenarios play on the
+/// simulated chain up to their first take or cancellation, which stops them.
+#[tokio::test]
+async fn checked_in_scenarios_play_on_the_simulated_chain() {
+    let mut paths = fs::read_dir("scenarios")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let scenario = Scenario::load(&path).unwrap();
+        let report = run_scenario(&mut ClobDriver::simulated().unwrap(), &scenario).await;
+
+        let setup = scenario.faucets.len()
+            + scenario.actors.len()
+            + usize::from(!scenario.markets.is_empty());
+        let consuming = scenario
+            .steps
+            .iter()
+            .position(|spec| matches!(spec.step, Step::Take { .. } | Step::Cancel { .. }));
+        match consuming {
+            Some(index) => {
+                let (last, played) = report.steps.split_last().unwrap();
+                assert_eq!(played.len(), setup + index, "{}: {report}", path.display());
+                assert!(played.iter().all(|outcome| outcome.passed), "{}", path.display());
+                assert!(!last.passed);
+            }
+            None => assert!(report.passed(), "{}: {report}", path.display()),
+        }
+    }
+}
+
+const SCENARIO: &str = r#"
+name = "unfilled order"
+actors = ["maker", "taker"]
+
+[[faucets]]
+symbol = "ETH"
+decimals = 8
+
+[[faucets]]
+symbol = "DAI"
+decimals = 8
+
+[[markets]]
+base = "ETH"
+quote = "DAI"
+tick_size = "0.01"
+lot_size = "0.001"
+
+[[steps]]
+action = "mint"
+account = "maker"
+amounts = ["1 ETH"]
+
+[[steps]]
+action = "place"
+maker = "maker"
+offered = "1 ETH"
+requested = "5 DAI"
+
+[[expect]]
+check = "balance"
+account = "maker"
+amount = "1 ETH"
+
+[[expect]]
+check = "resting_orders"
+count = 1
+"#;
+
+#[tokio::test]
+async fn failed_checks_and_steps_are_reported() {
+    let scenario = Scenario::parse(SCENARIO).unwrap();
+    let report = run_scenario(&mut ClobDriver::simulated().unwrap(), &scenario).await;
+    assert!(!report.passed());
+    assert!(report.steps.iter().all(|outcome| outcome.passed));
+    let failed = report.checks.iter().filter(|outcome| !outcome.passed).collect::<Vec<_>>();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].detail, "found 0 ETH");
+    assert!(report.to_string().starts_with("FAIL unfilled order"));
+
+    // Taking without any DAI stops the run before the checks
+    let mut scenario = scenario;
+    scenario.steps.push(
+        toml::from_str(
+            r#"
+            action = "take"
+            taker = "taker"
+            give = "5 DAI"
+            want = "ETH"
+            "#,
+        )
+        .unwrap(),
+    );
+    let report = run_scenario(&mut ClobDriver::simulated().unwrap(), &scenario).await;
+    assert!(!report.passed());
+    assert!(!report.steps.last().unwrap().passed);
+    assert!(report.checks.is_empty());
+}
+
+#[tokio::test]
+async fn order_options_must_be_in_the_offered_token() {
+    let mut scenario = Scenario::parse(SCENARIO).unwrap();
+    scenario.steps[1] = toml::from_str(
+        r#"
+        action = "place"
+        maker = "maker"
+        offered = "1 ETH"
+        requested = "5 DAI"
+        min_fill = "1 DAI"
+        expect_error = "is not of the offered token"
+        "#,
+    )
+    .unwrap();
+    scenario.expect.clear();
+
+    let report = run_scenario(&mut ClobDriver::simulated().unwrap(), &scenario).await;
+    assert!(report.passed(), "{report}");
+}
@@ -0,0 +1,206 @@
+pub mod helpers;
+
+use std::time::Duration;
+
+use axum::body::{Body, HttpBody};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use keom_clob::backend::SimulatedChain;
+use keom_clob::clob::Clob;
+use keom_clob::config::{ClobConfig, MarketConfig};
+use keom_clob::profiles::AccountProfiles;
+use keom_clob::registry::OrderRegistry;
+use keom_clob::server::{router, AppState};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use crate::helpers::note_id;
+
+/// A CLOB over a simulated chain with an ETH/DAI market, whose tokens have no
+/// decimals, and "alice" holding 10 ETH
+async fn clob() -> Clob<SimulatedChain> {
+    let mut clob = Clob::new(
+        SimulatedChain::new(),
+        AccountProfiles::in_memory(),
+        OrderRegistry::in_memory(),
+        &ClobConfig::default(),
+    )
+    .unwrap();
+    clob.new_faucet("ETH", 0, 1_000_000).unwrap();
+    clob.new_faucet("DAI", 0, 1_000_000).unwrap();
+    clob.add_markets(&[MarketConfig {
+        base: "ETH".to_string(),
+        quote: "DAI".to_string(),
+        tick_size: "1".to_string(),
+        lot_size: "1".to_string(),
+    }])
+    .unwrap();
+
+    clob.new_account("alice").unwrap();
+    let eth = clob.tokens().parse_amount("10 ETH").unwrap();
+    clob.mint("alice", vec![eth]).await.unwrap().into_result().unwrap();
+    clob
+}
+
+/// Sends one request to `app` and returns the status and JSON body of the
+/// response, or null if the body is not JSON
+async fn send(app: &Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    let mut response = app.clone().oneshot(request.unwrap()).await.unwrap();
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.body_mut().data().await {
+        bytes.extend_from_slice(&chunk.unwrap());
+    }
+    (response.status(), serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn order_request(maker: &str, offered: &str, requested: &str) -> Value {
+    json!({ "maker": maker, "offered": offered, "requested": requested })
+}
+
+#[tokio::test]
+async fn placed_orders_are_listed_and_shown_on_the_book() {
+    let app = router(AppState::new(clob().await).unwrap());
+
+    let request = order_request("alice", "5 ETH", "15 DAI");
+    let (status, receipt) = send(&app, Method::POST, "/orders", Some(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    let note_id = receipt["orders"][0].as_str().unwrap().to_string();
+
+    let (status, orders) = send(&app, Method::GET, "/orders?maker=alice", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(orders.as_array().unwrap().len(), 1);
+    assert_eq!(orders[0]["note_id"], note_id);
+
+    let (status, order) = send(&app, Method::GET, &format!("/orders/{note_id}"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(order["offered"], "5 ETH");
+
+    let (status, depth) = send(&app, Method::GET, "/book/ETH/DAI", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(depth["asks"].as_array().unwrap().len(), 1);
+    assert!(depth["bids"].as_array().unwrap().is_empty());
+
+    let (status, balances) = send(&app, Method::GET, "/balances/alice", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(balances, json!(["5 ETH"]));
+
+    // The body of the limit-swap script is maintained outside this repository,
+    // so the order cannot be reclaimed here: the failure is a server error for
+    // one order, and is reported with the orders left for all of them
+    let uri = format!("/orders/{note_id}?maker=alice");
+    let (status, _) = send(&app, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    let uri = "/orders?maker=alice&market=ETH/DAI";
+    let (status, report) = send(&app, Method::DELETE, uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["failed"], json!([note_id]));
+    assert!(report["error"].is_string());
+}
+
+#[tokio::test]
+async fn malformed_requests_are_bad_requests() {
+    let app = router(AppState::new(clob().await).unwrap());
+
+    for method in [Method::GET, Method::DELETE] {
+        let (status, body) = send(&app, method, "/orders/0xnothex?maker=alice", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("invalid note id"));
+    }
+    let request = order_request("alice", "5 ETH", "15 DAI");
+    let (status, _) = send(&app, Method::PUT, "/orders/0xnothex", Some(request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let request = order_request("alice", "five ETH", "15 DAI");
+    let (status, _) = send(&app, Method::POST, "/orders", Some(request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let mut request = order_request("alice", "5 ETH", "15 DAI");
+    request["min_fill"] = json!("1 DAI");
+    let (status, _) = send(&app, Method::POST, "/orders", Some(request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let request = json!({ "taker": "alice", "give": "five DAI", "want": "ETH" });
+    let (status, _) = send(&app, Method::POST, "/take", Some(request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn unknown_names_are_not_found() {
+    let app = router(AppState::new(clob().await).unwrap());
+
+    let request = order_request("bob", "5 ETH", "15 DAI");
+    let (status, _) = send(&app, Method::POST, "/orders", Some(request)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let request = order_request("alice", "5 ETH", "15 DAI");
+    let uri = format!("/orders/{}", note_id(1).to_hex());
+    let (status, _) = send(&app, Method::PUT, &uri, Some(request)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let uris = [
+        "/orders?maker=bob".to_string(),
+        format!("/orders/{}", note_id(1).to_hex()),
+        "/book/ETH/BTC".to_string(),
+        "/balances/bob".to_string(),
+    ];
+    for uri in uris {
+        let (status, body) = send(&app, Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        assert!(body["error"].is_string());
+    }
+
+    for uri in ["/orders?maker=bob", "/orders?maker=alice&market=ETH/BTC"] {
+        let (status, _) = send(&app, Method::DELETE, uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+    }
+
+    let request = json!({ "taker": "alice", "give": "5 DAI", "want": "BTC" });
+    let (status, _) = send(&app, Method::POST, "/take", Some(request)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn takes_without_liquidity_are_unprocessable() {
+    let app = router(AppState::new(clob().await).unwrap());
+
+    let request = json!({ "taker": "alice", "give": "5 DAI", "want": "ETH" });
+    let (status, body) = send(&app, Method::POST, "/take", Some(request)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["error"].is_string());
+
+    let (status, report) = send(&app, Method::DELETE, "/orders?maker=alice", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["reclaimed"], json!([]));
+    assert_eq!(report["failed"], json!([]));
+}
+
+#[tokio::test]
+async fn the_feed_only_serves_websockets() {
+    let app = router(AppState::new(clob().await).unwrap());
+
+    let (status, _) = send(&app, Method::GET, "/feed", None).await;
+    assert!(status.is_client_error());
+}
+
+#[tokio::test]
+async fn reads_do_not_wait_for_writes() {
+    let state = AppState::new(clob().await).unwrap();
+    let app = router(state.clone());
+
+    // A write holding the CLOB, e.g. while its transaction gets committed
+    let _write = state.clob.lock().await;
+
+    let read = send(&app, Method::GET, "/balances/alice", None);
+    let (status, balances) = tokio::time::timeout(Duration::from_secs(5), read).await.unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(balances, json!(["10 ETH"]));
+}
@@ -0,0 +1,110 @@
+pub mod helpers;
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use keom_clob::backend::{ClobBackend, SimulatedChain};
+use keom_clob::book::OrderBook;
+use keom_clob::build_fill_tx_req;
+use keom_clob::errors::ClobError;
+use keom_clob::market::{Market, Markets, Pair, Price};
+use keom_clob::taker::{next_fill, Retries, RetryPolicy};
+use miden_client::client::accounts::AccountStorageMode;
+
+use crate::helpers::{dai, eth, note_id, order};
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        backoff: Duration::from_millis(10),
+    }
+}
+
+/// Asks at 3 and 4 DAI per ETH
+fn book() -> OrderBook {
+    let mut book = OrderBook::new();
+    book.insert(order(1, eth(100), dai(400)));
+    book.insert(order(2, eth(100), dai(300)));
+    book
+}
+
+#[test]
+fn conflicts_are_retried_until_the_policy_runs_out() {
+    let mut retries = Retries::new(policy());
+
+    let backoff = retries.record(ClobError::NoteAlreadyConsumed(vec![note_id(2)])).unwrap();
+    assert_eq!(backoff, Duration::from_millis(10));
+    let backoff = retries.record(ClobError::NoteAlreadyConsumed(vec![note_id(1)])).unwrap();
+    assert_eq!(backoff, Duration::from_millis(10));
+    assert_eq!(retries.lost(), &BTreeSet::from([note_id(1), note_id(2)]));
+
+    let err = retries.record(ClobError::NoteAlreadyConsumed(vec![note_id(3)])).unwrap_err();
+    assert!(matches!(err, ClobError::RetriesExhausted { attempts: 3 }));
+    assert_eq!(retries.attempts(), 3);
+}
+
+#[test]
+fn other_errors_are_not_retried() {
+    let mut retries = Retries::new(policy());
+
+    let err = retries.record(ClobError::Backend("proof rejected".to_string())).unwrap_err();
+    assert!(matches!(err, ClobError::Backend(_)));
+    assert!(retries.lost().is_empty());
+}
+
+#[test]
+fn lost_notes_are_routed_around() {
+    let book = book();
+    let (eth_id, dai_id) = (eth(0).faucet_id(), dai(0).faucet_id());
+    let mut retries = Retries::new(policy());
+
+    let fill = next_fill(&book, &Markets::default(), eth_id, dai_id, 150, retries.lost()).unwrap();
+    assert_eq!(fill.note_id, note_id(2));
+    assert_eq!(fill.out_amount, 50);
+
+    // The best ask was taken by someone else, the next best one is tried
+    retries.record(ClobError::NoteAlreadyConsumed(vec![fill.note_id])).unwrap();
+    let fill = next_fill(&book, &Markets::default(), eth_id, dai_id, 150, retries.lost()).unwrap();
+    assert_eq!(fill.note_id, note_id(1));
+    assert_eq!(fill.out_amount, 37);
+
+    retries.record(ClobError::NoteAlreadyConsumed(vec![fill.note_id])).unwrap();
+    assert!(matches!(
+        next_fill(&book, &Markets::default(), eth_id, dai_id, 150, retries.lost()),
+        Err(ClobError::NoLiquidity)
+    ));
+}
+
+#[test]
+fn fills_leaving_less_than_a_lot_are_routed_around() {
+    let book = book();
+    let (eth_id, dai_id) = (eth(0).faucet_id(), dai(0).faucet_id());
+    let mut markets = Markets::default();
+    markets.insert(Market {
+        pair: Pair::new(eth_id, dai_id),
+        tick_size: Price::new(1, 1).unwrap(),
+        lot_size: 60,
+    });
+
+    // 50 of the best ask would leave it with 50, 37 of the next one leaves 63
+    let fill = next_fill(&book, &markets, eth_id, dai_id, 150, &BTreeSet::new()).unwrap();
+    assert_eq!(fill.note_id, note_id(1));
+    assert_eq!(fill.out_amount, 37);
+}
+
+#[test]
+fn fills_are_paid_through_the_note_args() {
+    let book = book();
+    let (eth_id, dai_id) = (eth(0).faucet_id(), dai(0).faucet_id());
+    let mut chain = SimulatedChain::new();
+    let taker = chain.new_wallet(AccountStorageMode::Local).unwrap();
+
+    let fill =
+        next_fill(&book, &Markets::default(), eth_id, dai_id, 150, &BTreeSet::new()).unwrap();
+    let tx_req = build_fill_tx_req(&chain, taker.id(), &[fill]).unwrap();
+
+    // No separate payment note: the script pays the maker out of the vault
+    assert_eq!(tx_req.get_input_note_ids(), vec![fill.note_id]);
+    assert_eq!(tx_req.get_note_args().get(&fill.note_id), Some(&fill.note_args()));
+    assert!(tx_req.expected_output_notes().is_empty());
+}
@@ -0,0 +1,119 @@
+pub mod helpers;
+
+use keom_clob::clob::{parse_note_id, OrderInput};
+use keom_clob::config::ClobConfig;
+use keom_clob::errors::ClobError;
+use keom_clob::tokens::{format_units, parse_units, TokenInfo, TokenRegistry};
+use miden_objects::accounts::AccountId;
+use miden_objects::assets::FungibleAsset;
+
+use crate::helpers::{note_id, DAI_FAUCET_ID, ETH_FAUCET_ID};
+
+fn registry() -> TokenRegistry {
+    let mut registry = TokenRegistry::new();
+    registry.register(TokenInfo {
+        symbol: "ETH".to_string(),
+        faucet_id: AccountId::try_from(ETH_FAUCET_ID).unwrap(),
+        decimals: 8,
+        max_supply: 100_000_000_000,
+    });
+    registry.register(TokenInfo {
+        symbol: "DAI".to_string(),
+        faucet_id: AccountId::try_from(DAI_FAUCET_ID).unwrap(),
+        decimals: 6,
+        max_supply: 1_000_000_000_000_000,
+    });
+    registry
+}
+
+#[test]
+fn parse_and_format_units() {
+    assert_eq!(parse_units("1.5", 8).unwrap(), 150_000_000);
+    assert_eq!(parse_units("0.00000001", 8).unwrap(), 1);
+    assert_eq!(parse_units(".5", 2).unwrap(), 50);
+    assert_eq!(parse_units("42", 0).unwrap(), 42);
+
+    assert!(parse_units("0.000000001", 8).is_err());
+    assert!(parse_units("1,5", 8).is_err());
+    assert!(parse_units("", 8).is_err());
+    assert!(parse_units("184467440737.09551616", 8).is_err());
+
+    assert_eq!(format_units(150_000_000, 8), "1.5");
+    assert_eq!(format_units(1, 8), "0.00000001");
+    assert_eq!(format_units(2_500_000_000, 6), "2500");
+}
+
+#[test]
+fn parse_amounts_with_symbols() {
+    let registry = registry();
+    let eth_faucet_id = AccountId::try_from(ETH_FAUCET_ID).unwrap();
+
+    let asset = registry.parse_amount("1.5 ETH").unwrap();
+    assert_eq!(asset, FungibleAsset::new(eth_faucet_id, 150_000_000).unwrap());
+    assert_eq!(registry.format_amount(&asset), "1.5 ETH");
+
+    assert!(registry.parse_amount("1.5 BTC").is_err());
+    assert!(registry.parse_amount("1.5").is_err());
+    assert!(registry.parse_amount("1001 ETH").is_err());
+}
+
+#[test]
+fn format_prices() {
+    let registry = registry();
+
+    let base = registry.parse_amount("2 ETH").unwrap();
+    let quote = registry.parse_amount("5000.5 DAI").unwrap();
+    assert_eq!(registry.format_price(&base, &quote), "2500.25 DAI/ETH");
+}
+
+#[test]
+fn registry_from_config() {
+    let config = ClobConfig::parse(&format!(
+        r#"
+        [[tokens]]
+        symbol = "ETH"
+        faucet_id = "{}"
+        decimals = 8
+        max_supply = 100000000000
+        "#,
+        AccountId::try_from(ETH_FAUCET_ID).unwrap().to_hex()
+    ))
+    .unwrap();
+
+    let registry = TokenRegistry::from_config(&config).unwrap();
+    let eth = registry.get("ETH").unwrap();
+    assert_eq!(eth.faucet_id, AccountId::try_from(ETH_FAUCET_ID).unwrap());
+    assert_eq!(registry.by_faucet(eth.faucet_id).unwrap().symbol, "ETH");
+}
+
+#[test]
+fn order_inputs_take_options_in_the_offered_token() {
+    let registry = registry();
+    let input = OrderInput {
+        offered: "1 ETH".to_string(),
+        requested: "2500 DAI".to_string(),
+        post_only: true,
+        min_fill: Some("0.1 ETH".to_string()),
+        min_remaining: None,
+    };
+    let order = input.to_order(&registry).unwrap();
+    assert_eq!(order.offered.amount(), 100_000_000);
+    assert_eq!(order.requested.amount(), 2_500_000_000);
+    assert_eq!(order.options.min_fill, 10_000_000);
+    assert_eq!(order.options.min_remaining, 0);
+    assert!(order.post_only);
+
+    let input = OrderInput {
+        min_remaining: Some("100 DAI".to_string()),
+        ..input
+    };
+    assert!(matches!(input.to_order(&registry), Err(ClobError::InvalidOrder(_))));
+}
+
+#[test]
+fn note_ids_and_faucets_are_looked_up() {
+    let note_id = note_id(7);
+    assert_eq!(parse_note_id(&note_id.to_hex()).unwrap(), note_id);
+    assert!(matches!(parse_note_id("0x12"), Err(ClobError::InvalidOrder(_))));
+    assert!(matches!(registry().faucet_id("BTC"), Err(ClobError::NotFound(_))));
+}